@@ -0,0 +1,443 @@
+use std::error::Error;
+
+// Interplay's ACM codec, as used by most of the games' sounds and music
+// Ported from libacm: https://github.com/markokr/libacm
+pub const ACM_SIGNATURE: [u8; 4] = [0x97, 0x28, 0x03, 0x01];
+const HEADER_SIZE: usize = 14;
+// Offset of the zero entry in the amplitude table, which is indexed from -0x8000
+const MIDDLE: i32 = 0x8000;
+// The encoder only uses a single column, so each block is a run of samples
+const ENCODER_ROWS: usize = 2048;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcmHeader {
+    // Across all channels
+    pub sample_count: u32,
+    pub channels: u16,
+    pub sample_rate: u16,
+    pub levels: u32,
+    pub rows: u32,
+}
+
+impl AcmHeader {
+    pub fn parse(buffer: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if !buffer.starts_with(&ACM_SIGNATURE) || buffer.len() < HEADER_SIZE {
+            return Err("Not an acm stream".into());
+        }
+        let packed = u16::from_le_bytes([buffer[12], buffer[13]]);
+        Ok(Self {
+            sample_count: u32::from_le_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]),
+            channels: u16::from_le_bytes([buffer[8], buffer[9]]),
+            sample_rate: u16::from_le_bytes([buffer[10], buffer[11]]),
+            levels: u32::from(packed & 0xf),
+            rows: u32::from(packed >> 4),
+        })
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = ACM_SIGNATURE.to_vec();
+        out.extend(self.sample_count.to_le_bytes());
+        out.extend(self.channels.to_le_bytes());
+        out.extend(self.sample_rate.to_le_bytes());
+        out.extend(((self.rows << 4) as u16 | self.levels as u16).to_le_bytes());
+        out
+    }
+}
+
+// Bits are read least significant first, running out of data reads zeros as libacm does
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u64,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            position: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    fn read(&mut self, size: u32) -> u32 {
+        while self.count < size {
+            let byte = self.data.get(self.position).copied().unwrap_or_default();
+            self.position += 1;
+            self.bits |= u64::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = (self.bits & ((1 << size) - 1)) as u32;
+        self.bits >>= size;
+        self.count -= size;
+        value
+    }
+}
+
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, size: u32) {
+        self.bits |= u64::from(value & ((1 << size) - 1)) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.data.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.data.push(self.bits as u8);
+        }
+        self.data
+    }
+}
+
+struct Decoder<'a> {
+    reader: BitReader<'a>,
+    levels: u32,
+    rows: usize,
+    block: Vec<i32>,
+    wrap: Vec<i32>,
+    amplitudes: Vec<i32>,
+}
+
+impl Decoder<'_> {
+    fn set(&mut self, row: usize, column: usize, amplitude: i32) {
+        self.block[(row << self.levels) + column] = self.amplitudes[(MIDDLE + amplitude) as usize];
+    }
+
+    fn set_zero(&mut self, row: usize, column: usize) {
+        self.block[(row << self.levels) + column] = 0;
+    }
+
+    fn read_block(&mut self) -> Result<(), Box<dyn Error>> {
+        let power = self.reader.read(4);
+        let value = self.reader.read(16) as i32;
+        let count = 1 << power;
+        for i in 0..count {
+            self.amplitudes[(MIDDLE + i) as usize] = i.wrapping_mul(value);
+        }
+        for i in 1..=count {
+            self.amplitudes[(MIDDLE - i) as usize] = (-i).wrapping_mul(value);
+        }
+        for column in 0..1 << self.levels {
+            let filler = self.reader.read(5);
+            self.fill_column(filler, column)?;
+        }
+        self.juggle_block();
+        Ok(())
+    }
+
+    // Two zero rows for a single 0 bit, used by the k fillers that start with a 3 way choice
+    fn double_zero(&mut self, row: &mut usize, column: usize) {
+        self.set_zero(*row, column);
+        if *row + 1 < self.rows {
+            self.set_zero(*row + 1, column);
+        }
+        *row += 2;
+    }
+
+    fn fill_column(&mut self, filler: u32, column: usize) -> Result<(), Box<dyn Error>> {
+        const NEAR: [i32; 4] = [-2, -1, 1, 2];
+        const FAR: [i32; 4] = [-3, -2, 2, 3];
+        const WIDE: [i32; 8] = [-4, -3, -2, -1, 1, 2, 3, 4];
+        let rows = self.rows;
+        let mut row = 0;
+        match filler {
+            0 => (0..rows).for_each(|row| self.set_zero(row, column)),
+            3..=16 => {
+                let middle = 1 << (filler - 1);
+                for row in 0..rows {
+                    let bits = self.reader.read(filler) as i32;
+                    self.set(row, column, bits - middle);
+                }
+            }
+            // k13, k24, k35 and k45 start with a pair of zero rows
+            17 | 20 | 23 | 26 => {
+                while row < rows {
+                    if self.reader.read(1) == 0 {
+                        self.double_zero(&mut row, column);
+                        continue;
+                    }
+                    if self.reader.read(1) == 0 {
+                        self.set_zero(row, column);
+                    } else {
+                        let amplitude = self.small_amplitude(filler, &NEAR, &FAR, &WIDE);
+                        self.set(row, column, amplitude);
+                    }
+                    row += 1;
+                }
+            }
+            // k12, k23, k34 and k44 start with a single zero row
+            18 | 21 | 24 | 27 => {
+                while row < rows {
+                    if self.reader.read(1) == 0 {
+                        self.set_zero(row, column);
+                    } else {
+                        // Map onto the filler of the same family that skips the pair of zeros
+                        let amplitude = self.small_amplitude(filler - 1, &NEAR, &FAR, &WIDE);
+                        self.set(row, column, amplitude);
+                    }
+                    row += 1;
+                }
+            }
+            // t15, t27 and t37 pack several rows into one value
+            19 | 22 | 29 => {
+                let (size, base, offset, per_value) = match filler {
+                    19 => (5, 3_i32, 1, 3),
+                    22 => (7, 5, 2, 3),
+                    _ => (7, 11, 5, 2),
+                };
+                while row < rows {
+                    let mut packed = self.reader.read(size) as i32;
+                    if packed >= base.pow(per_value) {
+                        return Err(format!("Invalid packed acm value {packed}").into());
+                    }
+                    for _ in 0..per_value {
+                        if row >= rows {
+                            break;
+                        }
+                        self.set(row, column, packed % base - offset);
+                        packed /= base;
+                        row += 1;
+                    }
+                }
+            }
+            _ => return Err(format!("Invalid acm filler {filler}").into()),
+        }
+        Ok(())
+    }
+
+    // The amplitude after the leading zero bits of a k filler
+    fn small_amplitude(
+        &mut self,
+        filler: u32,
+        near: &[i32; 4],
+        far: &[i32; 4],
+        wide: &[i32; 8],
+    ) -> i32 {
+        match filler {
+            17 => self.sign(),
+            20 => near[self.reader.read(2) as usize],
+            23 => match self.reader.read(1) {
+                0 => self.sign(),
+                _ => far[self.reader.read(2) as usize],
+            },
+            _ => wide[self.reader.read(3) as usize],
+        }
+    }
+
+    fn sign(&mut self) -> i32 {
+        if self.reader.read(1) == 1 { 1 } else { -1 }
+    }
+
+    fn juggle_block(&mut self) {
+        if self.levels == 0 {
+            return;
+        }
+        let columns = 1 << self.levels;
+        let step = (2048_usize >> self.levels).saturating_sub(2).max(1);
+        let mut offset = 0;
+        let mut todo = self.rows;
+        loop {
+            let mut wrap_offset = 0;
+            let mut sub_length = columns / 2;
+            let mut sub_count = step.min(todo) * 2;
+            juggle(
+                &mut self.wrap[wrap_offset..],
+                &mut self.block[offset..],
+                sub_length,
+                sub_count,
+            );
+            wrap_offset += sub_length * 2;
+            for i in 0..sub_count {
+                let index = offset + i * sub_length;
+                self.block[index] = self.block[index].wrapping_add(1);
+            }
+            while sub_length > 1 {
+                sub_length /= 2;
+                sub_count *= 2;
+                juggle(
+                    &mut self.wrap[wrap_offset..],
+                    &mut self.block[offset..],
+                    sub_length,
+                    sub_count,
+                );
+                wrap_offset += sub_length * 2;
+            }
+            if todo <= step {
+                break;
+            }
+            offset += step << self.levels;
+            todo -= step;
+        }
+    }
+}
+
+fn juggle(wrap: &mut [i32], block: &mut [i32], sub_length: usize, sub_count: usize) {
+    for i in 0..sub_length {
+        let mut position = i;
+        let (mut r0, mut r1) = (wrap[i * 2], wrap[i * 2 + 1]);
+        for _ in 0..sub_count / 2 {
+            let r2 = block[position];
+            block[position] = r1.wrapping_mul(2).wrapping_add(r0.wrapping_add(r2));
+            position += sub_length;
+            let r3 = block[position];
+            block[position] = r2.wrapping_mul(2).wrapping_sub(r1.wrapping_add(r3));
+            position += sub_length;
+            r0 = r2;
+            r1 = r3;
+        }
+        wrap[i * 2] = r0;
+        wrap[i * 2 + 1] = r1;
+    }
+}
+
+// Returns interleaved 16 bit samples
+pub fn decode(buffer: &[u8]) -> Result<(AcmHeader, Vec<i16>), Box<dyn Error>> {
+    let header = AcmHeader::parse(buffer)?;
+    let columns = 1_usize << header.levels;
+    let rows = header.rows as usize;
+    if rows == 0 {
+        return Err("Acm stream has no rows".into());
+    }
+    let mut decoder = Decoder {
+        reader: BitReader::new(&buffer[HEADER_SIZE..]),
+        levels: header.levels,
+        rows,
+        block: vec![0; columns * rows],
+        wrap: vec![0; columns * 2 - 2],
+        amplitudes: vec![0; 0x10000],
+    };
+    let sample_count = header.sample_count as usize;
+    let mut samples = Vec::with_capacity(sample_count);
+    while samples.len() < sample_count {
+        decoder.read_block()?;
+        let remaining = sample_count - samples.len();
+        samples.extend(
+            decoder.block.iter().take(remaining).map(|value| {
+                (value >> header.levels).clamp(i16::MIN.into(), i16::MAX.into()) as i16
+            }),
+        );
+    }
+    Ok((header, samples))
+}
+
+// A lossless encoding without the subband transform, each block of samples is stored with
+// the fewest bits that fit it, or skipped entirely if silent
+pub fn encode(samples: &[i16], channels: u16, sample_rate: u16) -> Vec<u8> {
+    let header = AcmHeader {
+        sample_count: samples.len() as u32,
+        channels,
+        sample_rate,
+        levels: 0,
+        rows: ENCODER_ROWS as u32,
+    };
+    let mut writer = BitWriter::default();
+    for chunk in samples.chunks(ENCODER_ROWS) {
+        // Amplitudes cover every 16 bit value, one step apart
+        writer.write(15, 4);
+        writer.write(1, 16);
+        if chunk.iter().all(|sample| *sample == 0) {
+            writer.write(0, 5);
+            continue;
+        }
+        let filler = (3..=16)
+            .find(|size| {
+                let middle = 1 << (size - 1);
+                chunk
+                    .iter()
+                    .all(|sample| (-middle..middle).contains(&i32::from(*sample)))
+            })
+            .unwrap_or(16);
+        writer.write(filler, 5);
+        let middle = 1 << (filler - 1);
+        for row in 0..ENCODER_ROWS {
+            let sample = chunk.get(row).copied().unwrap_or_default();
+            writer.write((i32::from(sample) + middle) as u32, filler);
+        }
+    }
+    [header.to_bytes(), writer.finish()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn Error>> {
+        let mut samples: Vec<i16> = (0..5000)
+            .map(|i| ((i as f64 / 20.0).sin() * 12000.0) as i16)
+            .collect();
+        samples.extend([0; 3000]);
+        samples.extend([i16::MIN, i16::MAX, -1, 1]);
+        let encoded = encode(&samples, 2, 22050);
+        let (header, decoded) = decode(&encoded)?;
+        assert_eq!(header.channels, 2);
+        assert_eq!(header.sample_rate, 22050);
+        assert_eq!(decoded, samples);
+        Ok(())
+    }
+
+    // A single column stream with amplitudes in steps of 10
+    fn stream(rows: u32, filler: u32, values: &[(u32, u32)]) -> Vec<u8> {
+        let header = AcmHeader {
+            sample_count: rows,
+            channels: 1,
+            sample_rate: 22050,
+            levels: 0,
+            rows,
+        };
+        let mut writer = BitWriter::default();
+        writer.write(3, 4);
+        writer.write(10, 16);
+        writer.write(filler, 5);
+        for (value, size) in values {
+            writer.write(*value, *size);
+        }
+        [header.to_bytes(), writer.finish()].concat()
+    }
+
+    #[test]
+    fn fillers() -> Result<(), Box<dyn Error>> {
+        // k13: a pair of zeros, a zero, +1 and -1
+        let buffer = stream(
+            5,
+            17,
+            &[
+                (0, 1),
+                (1, 1),
+                (0, 1),
+                (1, 1),
+                (1, 1),
+                (1, 1),
+                (1, 1),
+                (1, 1),
+                (0, 1),
+            ],
+        );
+        assert_eq!(decode(&buffer)?.1, vec![0, 0, 0, 10, -10]);
+
+        // k44: a zero then the 3 bit map
+        let buffer = stream(3, 27, &[(0, 1), (1, 1), (7, 3), (1, 1), (0, 3)]);
+        assert_eq!(decode(&buffer)?.1, vec![0, 40, -40]);
+
+        // t15 packs three rows into five bits, 0 + 2 * 3 + 1 * 9 => -1, 1, 0
+        let buffer = stream(4, 19, &[(15, 5), (26, 5)]);
+        assert_eq!(decode(&buffer)?.1, vec![-10, 10, 0, 10]);
+
+        assert!(decode(&stream(4, 1, &[])).is_err());
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
 use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor, io::SeekFrom};
 use serde::{Deserialize, Serialize};
 
+use std::fmt::Display;
+
+use crate::bmp::Bmp;
 use crate::common::Resref;
 use crate::common::char_array::CharArray;
 use crate::common::header::Header;
+use crate::common::image::Image;
 use crate::common::strref::Strref;
 use crate::model::Model;
 
@@ -89,6 +93,181 @@ impl Model for Area {
     }
 }
 
+const TRAP_COLOUR: [u8; 4] = [255, 0, 0, 255];
+const INFO_COLOUR: [u8; 4] = [255, 255, 0, 255];
+const TRAVEL_COLOUR: [u8; 4] = [0, 128, 255, 255];
+const CONTAINER_COLOUR: [u8; 4] = [0, 255, 0, 255];
+const DOOR_COLOUR: [u8; 4] = [255, 0, 255, 255];
+const ENTRANCE_COLOUR: [u8; 4] = [0, 255, 255, 255];
+const ACTOR_COLOUR: [u8; 4] = [255, 255, 255, 255];
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bmp.htm#searchmap
+const IMPASSABLE_TERRAIN: [u8; 5] = [0, 8, 10, 12, 13];
+
+#[derive(Debug, PartialEq)]
+pub enum PlacementProblem {
+    OutOfBounds,
+    Impassable(u8),
+}
+
+// Something in the area placed where creatures can not stand
+#[derive(Debug, PartialEq)]
+pub struct PlacementIssue {
+    pub kind: &'static str,
+    pub name: String,
+    pub x_coordinate: u16,
+    pub y_coordinate: u16,
+    pub problem: PlacementProblem,
+}
+
+impl Display for PlacementIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (kind, name, x, y) = (self.kind, &self.name, self.x_coordinate, self.y_coordinate);
+        match self.problem {
+            PlacementProblem::OutOfBounds => {
+                write!(f, "{kind} {name:?} at {x},{y} is outside of the search map")
+            }
+            PlacementProblem::Impassable(value) => write!(
+                f,
+                "{kind} {name:?} at {x},{y} is on impassable terrain ({value})"
+            ),
+        }
+    }
+}
+
+impl Area {
+    fn outline(&self, start: u32, count: u32) -> Vec<(i64, i64)> {
+        let start = start as usize;
+        self.vertices
+            .get(start..start + count as usize)
+            .unwrap_or_default()
+            .iter()
+            .map(|Vertice([x, y])| (*x as i64, *y as i64))
+            .collect()
+    }
+
+    // Draws regions, containers, doors, entrances and actors on top of a rendered wed
+    pub fn draw_overlay(&self, image: &mut Image) {
+        for region in &self.regions {
+            let colour = match region.region_type {
+                0 => TRAP_COLOUR,
+                1 => INFO_COLOUR,
+                _ => TRAVEL_COLOUR,
+            };
+            let points = self.outline(
+                region.index_to_first_vertex,
+                region.count_of_vertices_composing_the_perimeter as u32,
+            );
+            image.draw_polygon(&points, colour);
+        }
+        for container in &self.containers {
+            let points = self.outline(
+                container.index_to_first_vertex_of_the_outline,
+                container.count_of_vertices_making_up_the_outline as u32,
+            );
+            image.draw_polygon(&points, CONTAINER_COLOUR);
+        }
+        for door in &self.doors {
+            let points = self.outline(
+                door.index_of_first_vertex_of_the_door_outline_when_closed,
+                door.count_of_vertices_of_the_door_outline_when_closed as u32,
+            );
+            image.draw_polygon(&points, DOOR_COLOUR);
+        }
+        for entrance in &self.entrances {
+            let point = (entrance.x_coordinate as i64, entrance.y_coordinate as i64);
+            image.draw_marker(point, ENTRANCE_COLOUR);
+        }
+        for actor in &self.actors {
+            let point = (
+                actor.current_x_coordinate as i64,
+                actor.current_y_coordinate as i64,
+            );
+            image.draw_marker(point, ACTOR_COLOUR);
+        }
+    }
+
+    // Checks actors, entrances, spawn points and container trap launch points against the
+    // search map (<area wed>SR.BMP). The search map is stretched over the area, whose size
+    // in pixels comes from its wed, usually making each search map pixel 16x12
+    pub fn lint_placements(&self, search_map: &Bmp, area_size: (u32, u32)) -> Vec<PlacementIssue> {
+        let (area_width, area_height) = area_size;
+        // An area without a size has nowhere to place anything
+        let cell = |value: u16, search_map_size: u32, area_size: u32| {
+            (value as u32 * search_map_size)
+                .checked_div(area_size)
+                .unwrap_or(u32::MAX)
+        };
+        let actors = self.actors.iter().map(|actor| {
+            (
+                "Actor",
+                &actor.name,
+                actor.current_x_coordinate,
+                actor.current_y_coordinate,
+            )
+        });
+        let entrances = self.entrances.iter().map(|entrance| {
+            (
+                "Entrance",
+                &entrance.name,
+                entrance.x_coordinate,
+                entrance.y_coordinate,
+            )
+        });
+        let spawn_points = self.spawn_points.iter().map(|spawn_point| {
+            (
+                "Spawn point",
+                &spawn_point.name,
+                spawn_point.x_coordinate,
+                spawn_point.y_coordinate,
+            )
+        });
+        // Containers without a trap leave the launch point at 0,0
+        let containers = self
+            .containers
+            .iter()
+            .filter(|container| {
+                (
+                    container.trap_launch_x_coordinate,
+                    container.trap_launch_y_coordinate,
+                ) != (0, 0)
+            })
+            .map(|container| {
+                (
+                    "Container trap launch point",
+                    &container.name,
+                    container.trap_launch_x_coordinate,
+                    container.trap_launch_y_coordinate,
+                )
+            });
+
+        actors
+            .chain(entrances)
+            .chain(spawn_points)
+            .chain(containers)
+            .filter_map(|(kind, name, x, y)| {
+                let problem = match search_map.pixel_index(
+                    cell(x, search_map.width(), area_width),
+                    cell(y, search_map.height(), area_height),
+                ) {
+                    None => PlacementProblem::OutOfBounds,
+                    Some(value) if IMPASSABLE_TERRAIN.contains(&value) => {
+                        PlacementProblem::Impassable(value)
+                    }
+                    Some(_) => return None,
+                };
+                Some(PlacementIssue {
+                    kind,
+                    name: name.to_string().replace('\0', ""),
+                    x_coordinate: x,
+                    y_coordinate: y,
+                    problem,
+                })
+            })
+            .collect()
+    }
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/are_v1.htm#formAREAV1_0_Header
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct FileHeader {
@@ -611,4 +790,139 @@ mod tests {
         }
         Ok(())
     }
+
+    // One of everything the overlay draws, outlines side by side along the top and the two
+    // markers below them. None of the fixtures have a door, so it starts out zeroed
+    fn overlay_area() -> Result<Area, Box<dyn Error>> {
+        let mut area = Area::new(&read_file("fixtures/ar0226.are")?);
+        area.regions.truncate(1);
+        area.containers.truncate(1);
+        area.entrances.truncate(1);
+        area.actors.truncate(1);
+        area.doors = vec![Door::read_le(&mut Cursor::new(vec![0; 0xc8]))?];
+        area.vertices = [2_u16, 14, 26]
+            .iter()
+            .flat_map(|left| [[*left, 2], [left + 8, 2], [left + 8, 10], [*left, 10]].map(Vertice))
+            .collect();
+
+        let region = &mut area.regions[0];
+        region.region_type = 1;
+        (
+            region.index_to_first_vertex,
+            region.count_of_vertices_composing_the_perimeter,
+        ) = (0, 4);
+        let container = &mut area.containers[0];
+        (
+            container.index_to_first_vertex_of_the_outline,
+            container.count_of_vertices_making_up_the_outline,
+        ) = (4, 4);
+        let door = &mut area.doors[0];
+        (
+            door.index_of_first_vertex_of_the_door_outline_when_closed,
+            door.count_of_vertices_of_the_door_outline_when_closed,
+        ) = (8, 4);
+        (
+            area.entrances[0].x_coordinate,
+            area.entrances[0].y_coordinate,
+        ) = (8, 24);
+        (
+            area.actors[0].current_x_coordinate,
+            area.actors[0].current_y_coordinate,
+        ) = (24, 24);
+        Ok(area)
+    }
+
+    #[test]
+    fn draw_overlay() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::new(40, 32);
+        overlay_area()?.draw_overlay(&mut image);
+        // Outline corners and edges, with the inside of each left untouched
+        assert_eq!(image.pixel(2, 2), Some(INFO_COLOUR));
+        assert_eq!(image.pixel(10, 6), Some(INFO_COLOUR));
+        assert_eq!(image.pixel(6, 6), Some([0, 0, 0, 0]));
+        assert_eq!(image.pixel(14, 10), Some(CONTAINER_COLOUR));
+        assert_eq!(image.pixel(18, 6), Some([0, 0, 0, 0]));
+        assert_eq!(image.pixel(30, 2), Some(DOOR_COLOUR));
+        assert_eq!(image.pixel(34, 10), Some(DOOR_COLOUR));
+        // Markers are crosses centred on their point
+        assert_eq!(image.pixel(8, 24), Some(ENTRANCE_COLOUR));
+        assert_eq!(image.pixel(4, 28), Some(ENTRANCE_COLOUR));
+        assert_eq!(image.pixel(8, 20), Some([0, 0, 0, 0]));
+        assert_eq!(image.pixel(24, 24), Some(ACTOR_COLOUR));
+        assert_eq!(image.pixel(28, 20), Some(ACTOR_COLOUR));
+        Ok(())
+    }
+
+    // An 8 bit top down search map with the given terrain values, row by row
+    fn search_map(width: u32, height: u32, values: &[u8]) -> Bmp {
+        let row_size = width.div_ceil(4) * 4;
+        let mut buffer = b"BM".to_vec();
+        for value in [1078 + row_size * height, 0, 1078, 40, width] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend((-(height as i32)).to_le_bytes());
+        buffer.extend([1, 0, 8, 0]);
+        for value in [0_u32, row_size * height, 0, 0, 256, 0] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend([0; 1024]);
+        for row in values.chunks(width as usize) {
+            buffer.extend(row);
+            buffer.extend(vec![0; (row_size - width) as usize]);
+        }
+        Bmp::new(&buffer)
+    }
+
+    // Keeps one actor and one entrance from the fixture, placed at the given points
+    fn placed_area(actor: (u16, u16), entrance: (u16, u16)) -> Result<Area, Box<dyn Error>> {
+        let mut area = Area::new(&read_file("fixtures/ar0002.are")?);
+        area.actors.truncate(1);
+        area.entrances.truncate(1);
+        area.spawn_points.clear();
+        area.containers.clear();
+        (
+            area.actors[0].current_x_coordinate,
+            area.actors[0].current_y_coordinate,
+        ) = actor;
+        (
+            area.entrances[0].x_coordinate,
+            area.entrances[0].y_coordinate,
+        ) = entrance;
+        Ok(area)
+    }
+
+    #[test]
+    fn lint_placements() -> Result<(), Box<dyn Error>> {
+        // 2x2 cells of 16x12 over a 32x24 area, only the bottom right cell is impassable
+        let map = search_map(2, 2, &[1, 1, 1, 0]);
+
+        let area = placed_area((5, 5), (20, 15))?;
+        let issues = area.lint_placements(&map, (32, 24));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, "Entrance");
+        assert_eq!((issues[0].x_coordinate, issues[0].y_coordinate), (20, 15));
+        assert_eq!(issues[0].problem, PlacementProblem::Impassable(0));
+
+        let area = placed_area((31, 11), (40, 5))?;
+        let issues = area.lint_placements(&map, (32, 24));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, "Entrance");
+        assert_eq!(issues[0].problem, PlacementProblem::OutOfBounds);
+        Ok(())
+    }
+
+    #[test]
+    fn lint_placements_scale() -> Result<(), Box<dyn Error>> {
+        // A non standard 8x8 pixel cell, a fixed 16x12 cell would check the wrong pixel
+        let map = search_map(4, 3, &[1, 1, 1, 1, 1, 1, 12, 1, 1, 1, 1, 1]);
+        let area = placed_area((4, 4), (17, 9))?;
+        let issues = area.lint_placements(&map, (32, 24));
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].kind, "Entrance");
+        assert_eq!(issues[0].problem, PlacementProblem::Impassable(12));
+
+        // Without a size every placement is outside of the area
+        assert_eq!(area.lint_placements(&map, (0, 0)).len(), 2);
+        Ok(())
+    }
 }
@@ -1,5 +1,9 @@
 use core::str;
-use std::{error::Error, fs::File, path::PathBuf};
+use std::{
+    error::Error,
+    fs::File,
+    path::{Path, PathBuf},
+};
 
 use binrw::{
     BinRead, BinReaderExt, BinResult, BinWrite,
@@ -14,6 +18,10 @@ use crate::{
 };
 use crate::{common::types::ResourceType, from_buffer, model::Model};
 
+// Bits of a resource locator, the top 12 bits are the index of the biff in the key
+const FILE_INDEX_MASK: u32 = 0x3fff;
+const TILESET_INDEX_MASK: u32 = 0xfc000;
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bif_v1.htm
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct Biff {
@@ -92,14 +100,76 @@ impl Biff {
             let start: usize = tileset_entry.offset as usize;
             let end: usize = start + (tileset_entry.tile_count * tileset_entry.tile_size) as usize;
             let buff = buffer.get(start..end).unwrap_or_default();
-            out.push(IEModels::Tileset(Tileset {
-                data: buff.to_vec(),
-            }));
+            out.push(IEModels::Tileset(Tileset::from_tiles(
+                buff,
+                tileset_entry.tile_count,
+                tileset_entry.tile_size,
+            )));
         }
         Ok(out)
     }
 }
 
+// A biff opened for reading single resources, only its file tables are parsed and
+// each resource is read by seeking to its entry
+pub struct BiffFile {
+    path: PathBuf,
+    reader: BufReader<File>,
+    fileset_entries: Vec<FilesetEntry>,
+    tileset_entries: Vec<TilesetEntry>,
+}
+
+impl BiffFile {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let header = BiffHeader::read_le(&mut reader)?;
+        reader.seek(SeekFrom::Start(header.offset_to_file_entries as u64))?;
+        let mut fileset_entries = vec![];
+        for _ in 0..header.count_of_fileset_entries {
+            fileset_entries.push(FilesetEntry::read_le(&mut reader)?);
+        }
+        let mut tileset_entries = vec![];
+        for _ in 0..header.count_of_tileset_entries {
+            tileset_entries.push(TilesetEntry::read_le(&mut reader)?);
+        }
+        Ok(BiffFile {
+            path: path.to_path_buf(),
+            reader,
+            fileset_entries,
+            tileset_entries,
+        })
+    }
+
+    // Tilesets get a tis header
+    pub fn read_resource(&mut self, locator: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        let not_found = || format!("No resource with locator {locator:#x} in {:?}", self.path);
+        let tileset_index = locator & TILESET_INDEX_MASK;
+        if tileset_index != 0 {
+            let entry = self
+                .tileset_entries
+                .iter()
+                .find(|entry| entry.resource_locator & TILESET_INDEX_MASK == tileset_index)
+                .ok_or_else(not_found)?;
+            let (tile_count, tile_size) = (entry.tile_count, entry.tile_size);
+            let tiles = self.read_at(entry.offset, tile_count * tile_size)?;
+            return Ok(Tileset::from_tiles(&tiles, tile_count, tile_size).to_bytes());
+        }
+        let entry = self
+            .fileset_entries
+            .iter()
+            .find(|entry| entry.resource_locator.0 & FILE_INDEX_MASK == locator & FILE_INDEX_MASK)
+            .ok_or_else(not_found)?;
+        self.read_at(entry.offset, entry.size)
+    }
+
+    fn read_at(&mut self, offset: u32, size: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut buffer = vec![0; size as usize];
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        self.reader.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bif_v1.htm#bif_v1_Header
 #[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct BiffHeader {
@@ -161,4 +231,21 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn read_resource() -> Result<(), Box<dyn Error>> {
+        let buffer = read_file("fixtures/effects.bif")?;
+        let biff = Biff::new(&buffer);
+        let mut file = BiffFile::open(Path::new("fixtures/effects.bif"))?;
+        for entry in [
+            &biff.fileset_entries[0],
+            biff.fileset_entries.last().unwrap(),
+        ] {
+            let start = entry.offset as usize;
+            let expected = &buffer[start..start + entry.size as usize];
+            assert_eq!(file.read_resource(entry.resource_locator.0)?, expected);
+        }
+        assert!(file.read_resource(0x3fff).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,213 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor, io::SeekFrom};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{char_array::CharArray, image::Image},
+    model::Model,
+};
+
+// Size of the file header, the info header follows straight after it
+const FILE_HEADER_SIZE: u64 = 14;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bmp.htm
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Bmp {
+    #[serde(skip)]
+    #[br(parse_with = until_eof, restore_position)]
+    pub original_bytes: Vec<u8>,
+    #[bw(ignore)]
+    #[serde(flatten)]
+    pub header: BmpHeader,
+    // BGRA, only present for 8 bits per pixel or less
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(count=header.palette_size(), seek_before=SeekFrom::Start(FILE_HEADER_SIZE + header.size_of_info_header as u64))]
+    pub palette: Vec<[u8; 4]>,
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(parse_with = until_eof, seek_before=SeekFrom::Start(header.offset_to_pixels as u64))]
+    pub data: Vec<u8>,
+}
+
+impl Model for Bmp {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Bmp {
+    pub fn width(&self) -> u32 {
+        self.header.width.unsigned_abs()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.header.height.unsigned_abs()
+    }
+
+    // Rows are padded to 4 bytes
+    fn row_size(&self) -> usize {
+        (self.width() as usize * self.header.bits_per_pixel as usize).div_ceil(32) * 4
+    }
+
+    // Offset of the first byte of a pixel and the bit offset into it, with 0,0 at the top left
+    fn position(&self, x: u32, y: u32) -> Option<(usize, usize)> {
+        if x >= self.width() || y >= self.height() {
+            return None;
+        }
+        // A positive height means the rows are stored bottom to top
+        let row = if self.header.height > 0 {
+            self.height() - 1 - y
+        } else {
+            y
+        };
+        let bit = x as usize * self.header.bits_per_pixel as usize;
+        Some((row as usize * self.row_size() + bit / 8, bit % 8))
+    }
+
+    // The palette index of a pixel, for 4 and 8 bit images
+    pub fn pixel_index(&self, x: u32, y: u32) -> Option<u8> {
+        let (offset, bit) = self.position(x, y)?;
+        let byte = *self.data.get(offset)?;
+        match self.header.bits_per_pixel {
+            4 if bit == 0 => Some(byte >> 4),
+            4 => Some(byte & 0x0f),
+            8 => Some(byte),
+            _ => None,
+        }
+    }
+
+    // RGBA colour of a pixel, palettised pixels are looked up in the palette
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if self.header.bits_per_pixel <= 8 {
+            let [blue, green, red, _] = *self.palette.get(self.pixel_index(x, y)? as usize)?;
+            return Some([red, green, blue, 255]);
+        }
+        let (offset, _) = self.position(x, y)?;
+        match self.header.bits_per_pixel {
+            24 => match self.data.get(offset..offset + 3)? {
+                [blue, green, red] => Some([*red, *green, *blue, 255]),
+                _ => None,
+            },
+            32 => match self.data.get(offset..offset + 4)? {
+                [blue, green, red, _] => Some([*red, *green, *blue, 255]),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::new(self.width(), self.height());
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if let Some(rgba) = self.pixel(x, y) {
+                    image.set_pixel(x, y, rgba);
+                }
+            }
+        }
+        image
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bmp.htm#bmp_Header
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct BmpHeader {
+    // "BM"
+    pub signature: CharArray<2>,
+    pub file_size: u32,
+    #[serde(skip)]
+    #[br(count = 4)]
+    _reserved: Vec<u8>,
+    pub offset_to_pixels: u32,
+    pub size_of_info_header: u32,
+    pub width: i32,
+    // Negative when the rows are stored top to bottom
+    pub height: i32,
+    pub planes: u16,
+    // 4, 8, 24 or 32
+    pub bits_per_pixel: u16,
+    // 0 = uncompressed
+    pub compression: u32,
+    pub size_of_image: u32,
+    pub horizontal_resolution: i32,
+    pub vertical_resolution: i32,
+    // 0 means every colour for the bit depth is present
+    pub count_of_colours: u32,
+    pub count_of_important_colours: u32,
+}
+
+impl BmpHeader {
+    fn palette_size(&self) -> usize {
+        match (self.bits_per_pixel, self.count_of_colours) {
+            (bits, 0) if bits <= 8 => 1 << bits,
+            (bits, count) if bits <= 8 => count as usize,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bmp(width: i32, height: i32, bits_per_pixel: u16, palette: &[u8], data: &[u8]) -> Vec<u8> {
+        let offset_to_pixels = 54 + palette.len() as u32;
+        let mut out = b"BM".to_vec();
+        for value in [
+            offset_to_pixels + data.len() as u32,
+            0,
+            offset_to_pixels,
+            40,
+        ] {
+            out.extend(value.to_le_bytes());
+        }
+        out.extend(width.to_le_bytes());
+        out.extend(height.to_le_bytes());
+        out.extend(1_u16.to_le_bytes());
+        out.extend(bits_per_pixel.to_le_bytes());
+        for value in [0_u32, data.len() as u32, 0, 0, palette.len() as u32 / 4, 0] {
+            out.extend(value.to_le_bytes());
+        }
+        out.extend(palette);
+        out.extend(data);
+        out
+    }
+
+    #[test]
+    fn parse_palettised() {
+        // Bottom row first, each row padded to 4 bytes
+        let palette = [0, 0, 0, 0, 255, 0, 0, 0];
+        let buffer = bmp(3, 2, 4, &palette, &[0x01, 0x00, 0, 0, 0x10, 0x10, 0, 0]);
+        let bmp = Bmp::new(&buffer);
+        assert_eq!(bmp.to_bytes(), buffer);
+        assert_eq!(bmp.palette.len(), 2);
+        assert_eq!(bmp.pixel_index(0, 0), Some(1));
+        assert_eq!(bmp.pixel_index(1, 0), Some(0));
+        assert_eq!(bmp.pixel_index(2, 0), Some(1));
+        assert_eq!(bmp.pixel_index(1, 1), Some(1));
+        assert_eq!(bmp.pixel_index(3, 0), None);
+        assert_eq!(bmp.pixel(0, 0), Some([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn parse_true_colour() {
+        // Top to bottom, one pixel per row
+        let data = [1, 2, 3, 0, 4, 5, 6, 0];
+        let image = Bmp::new(&bmp(1, -2, 24, &[], &data)).to_image();
+        assert_eq!(image.pixel(0, 0), Some([3, 2, 1, 255]));
+        assert_eq!(image.pixel(0, 1), Some([6, 5, 4, 255]));
+    }
+}
@@ -1,45 +1,117 @@
-use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor};
+use binrw::{
+    BinRead, BinResult, BinWrite,
+    helpers::until_eof,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::common::Resref;
 use crate::common::char_array::CharArray;
 use crate::common::header::Header;
 
-use crate::{creature::Creature, model::Model};
+use crate::{creature::Creature, game::GameNPC, model::Model};
+
+// "CHR "
+const SIGNATURE: CharArray<4> = CharArray([67, 72, 82, 32]);
+// "V2.1", the version the enhanced editions export
+const VERSION: CharArray<4> = CharArray([86, 50, 46, 49]);
+const HEADER_SIZE: u32 = 0x64;
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chr_v2.htm
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, BinRead, Serialize, Deserialize)]
 pub struct ExpandedCharacter {
     #[serde(skip)]
     #[br(parse_with = until_eof, restore_position)]
     pub original_bytes: Vec<u8>,
-    #[bw(ignore)]
     #[serde(flatten)]
     pub character: BGCharacter,
-    #[bw(ignore)]
-    #[serde(flatten)]
+    // Kept nested, as the chr and cre headers share their field names. The cre offsets
+    // are relative to the start of the cre, so it is read on its own
+    #[br(parse_with = |reader, _, _:()| parse_creature(reader, &character))]
     pub creature: Creature,
 }
 
+fn parse_creature<R: Read + Seek>(reader: &mut R, character: &BGCharacter) -> BinResult<Creature> {
+    let mut buff = vec![0; character.length_of_the_cre_structure as usize];
+    reader.seek(SeekFrom::Start(character.offset_to_cre_structure as u64))?;
+    reader.read_exact(&mut buff)?;
+    Ok(Creature::new(&buff))
+}
+
 impl Model for ExpandedCharacter {
     fn new(buffer: &[u8]) -> Self {
         let mut reader = Cursor::new(buffer);
-        reader.read_le().unwrap()
+        match ExpandedCharacter::read_le(&mut reader) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
     }
 
+    // The cre directly follows the header
     fn to_bytes(&self) -> Vec<u8> {
+        let creature = self.creature.to_bytes();
         let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
+        self.character
+            .write_le_args(&mut writer, (HEADER_SIZE, creature.len() as u32))
+            .unwrap();
+        writer.write_all(&creature).unwrap();
         writer.into_inner()
     }
 }
 
+impl ExpandedCharacter {
+    // Exports a party member of a save, the quick slots come from the npc and the
+    // creature is copied through its writer
+    pub fn from_game_npc(npc: &GameNPC, creature: &Creature) -> Self {
+        Self {
+            original_bytes: vec![],
+            character: BGCharacter {
+                header: Header {
+                    signature: SIGNATURE,
+                    version: VERSION,
+                },
+                name: npc.name.clone(),
+                offset_to_cre_structure: HEADER_SIZE,
+                length_of_the_cre_structure: 0,
+                index_into_slots_ids_for_quick_weapon_1: npc.index_of_quick_weapon_1,
+                index_into_slots_ids_for_quick_weapon_2: npc.index_of_quick_weapon_2,
+                index_into_slots_ids_for_quick_weapon_3: npc.index_of_quick_weapon_3,
+                index_into_slots_ids_for_quick_weapon_4: npc.index_of_quick_weapon_4,
+                show_quick_weapon_1: npc.quick_weapon_slot_1_ability,
+                show_quick_weapon_2: npc.quick_weapon_slot_2_ability,
+                show_quick_weapon_3: npc.quick_weapon_slot_3_ability,
+                show_quick_weapon_4: npc.quick_weapon_slot_4_ability,
+                quick_spell_1_resource: npc.quick_spell_1_resource.clone(),
+                quick_spell_2_resource: npc.quick_spell_2_resource.clone(),
+                quick_spell_3_resource: npc.quick_spell_3_resource.clone(),
+                index_into_slot_ids_for_quick_item_1: npc.index_of_quick_item_1,
+                index_into_slot_ids_for_quick_item_2: npc.index_of_quick_item_2,
+                index_into_slot_ids_for_quick_item_3: npc.index_of_quick_item_3,
+                show_quick_item_1: npc.quick_item_slot_1_ability,
+                show_quick_item_2: npc.quick_item_slot_2_ability,
+                show_quick_item_3: npc.quick_item_slot_3_ability,
+            },
+            creature: Creature::new(&creature.to_bytes()),
+        }
+    }
+
+    // The quick slots only live in the chr, a bare cre is just the creature
+    pub fn into_creature(self) -> Creature {
+        self.creature
+    }
+}
+
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(cre_offset: u32, cre_size: u32))]
 pub struct BGCharacter {
     #[serde(flatten)]
     pub header: Header,
     pub name: CharArray<32>,
+    #[bw(map = |_: &u32| cre_offset)]
     pub offset_to_cre_structure: u32,
+    #[bw(map = |_: &u32| cre_size)]
     pub length_of_the_cre_structure: u32,
     pub index_into_slots_ids_for_quick_weapon_1: u16,
     pub index_into_slots_ids_for_quick_weapon_2: u16,
@@ -59,3 +131,48 @@ pub struct BGCharacter {
     pub show_quick_item_2: u16,
     pub show_quick_item_3: u16,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use binrw::io::Read;
+    use pretty_assertions::assert_eq;
+    use std::{error::Error, fs::File};
+
+    fn read_file(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn export_party_member() -> Result<(), Box<dyn Error>> {
+        let game = Game::new(&read_file("fixtures/bg2eebaldur.gam")?);
+        let (npc, creature) = game.party_member(1).ok_or("No party member")?;
+        let bytes = ExpandedCharacter::from_game_npc(npc, creature).to_bytes();
+        assert_eq!(&bytes[..8], b"CHR V2.1");
+
+        let character = ExpandedCharacter::new(&bytes);
+        assert_eq!(character.character.name, npc.name);
+        assert_eq!(character.character.offset_to_cre_structure, 0x64);
+        assert_eq!(
+            character.character.length_of_the_cre_structure,
+            npc.size_of_cre_resource
+        );
+        assert_eq!(
+            character.character.quick_spell_1_resource,
+            npc.quick_spell_1_resource
+        );
+        assert_eq!(character.to_bytes(), bytes);
+        let from_json: ExpandedCharacter =
+            serde_json::from_value(serde_json::to_value(&character)?)?;
+        assert_eq!(from_json.to_bytes(), bytes);
+
+        let cre = character.into_creature().to_bytes();
+        assert_eq!(&cre[..], &bytes[0x64..]);
+        assert_eq!(Creature::new(&cre).item_table, creature.item_table);
+        Ok(())
+    }
+}
@@ -0,0 +1,500 @@
+use binrw::{
+    BinRead, BinReaderExt, BinWrite,
+    io::{Cursor, Seek, SeekFrom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{
+        Resref, char_array::CharArray, flags::flags, header::Header, image::Image,
+        sections::Sections,
+    },
+    model::Model,
+};
+
+const HEADER_SIZE: u32 = 0x14;
+const WINDOW_SIZE: u32 = 0x1c;
+const CONTROL_TABLE_ENTRY_SIZE: u32 = 0x08;
+const CONTROL_HEADER_SIZE: u32 = 0x0e;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chu {
+    #[serde(flatten)]
+    pub header: ChuHeader,
+    pub windows: Vec<Window>,
+}
+
+impl Model for Chu {
+    fn new(buffer: &[u8]) -> Self {
+        match Chu::parse(buffer) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    // Windows, the control table then the controls, each window pointing at its first
+    // entry in the table and each entry at a control, whose size depends on its type
+    fn to_bytes(&self) -> Vec<u8> {
+        let controls: Vec<Vec<u8>> = self
+            .windows
+            .iter()
+            .flat_map(|window| &window.controls)
+            .map(|control| {
+                let mut out = Cursor::new(Vec::new());
+                control.write_le(&mut out).unwrap();
+                out.into_inner()
+            })
+            .collect();
+        let mut sections = Sections::starting_at(HEADER_SIZE);
+        let windows = sections.place(self.windows.len(), WINDOW_SIZE);
+        let (offset_to_control_table, _) = sections.place(controls.len(), CONTROL_TABLE_ENTRY_SIZE);
+        let mut writer = Cursor::new(Vec::new());
+        self.header.header.write_le(&mut writer).unwrap();
+        (windows.1, offset_to_control_table, windows.0)
+            .write_le(&mut writer)
+            .unwrap();
+
+        let mut first_control = 0;
+        for window in &self.windows {
+            window.write_le_args(&mut writer, (first_control,)).unwrap();
+            first_control += window.controls.len() as u16;
+        }
+        for control in &controls {
+            let offset = sections.place_bytes(control.len() as u32);
+            (offset, control.len() as u32)
+                .write_le(&mut writer)
+                .unwrap();
+        }
+        for control in controls {
+            control.write_le(&mut writer).unwrap();
+        }
+        writer.into_inner()
+    }
+}
+
+impl Chu {
+    fn parse(buffer: &[u8]) -> binrw::BinResult<Self> {
+        let mut reader = Cursor::new(buffer);
+        let header: ChuHeader = reader.read_le()?;
+        reader.set_position(header.offset_to_windows as u64);
+        let mut windows: Vec<Window> = (0..header.count_of_windows)
+            .map(|_| reader.read_le())
+            .collect::<Result<_, _>>()?;
+        for window in &mut windows {
+            for index in
+                window.first_control_index..window.first_control_index + window.count_of_controls
+            {
+                let table_entry =
+                    header.offset_to_control_table + u32::from(index) * CONTROL_TABLE_ENTRY_SIZE;
+                reader.seek(SeekFrom::Start(table_entry as u64))?;
+                let entry: ControlTableEntry = reader.read_le()?;
+                reader.seek(SeekFrom::Start(entry.offset as u64))?;
+                let mut control: Control = reader.read_le_args((entry.length,))?;
+                let end = entry.offset as usize + entry.length as usize;
+                control.trailing = buffer
+                    .get(reader.position() as usize..end)
+                    .unwrap_or_default()
+                    .to_vec();
+                window.controls.push(control);
+            }
+        }
+        Ok(Chu { header, windows })
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Header
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct ChuHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub count_of_windows: u32,
+    pub offset_to_control_table: u32,
+    pub offset_to_windows: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Window
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(first_control: u16))]
+pub struct Window {
+    pub id: u16,
+    #[serde(skip)]
+    _unknown: u16,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub flags: WindowFlags,
+    #[serde(skip)]
+    #[bw(map = |_: &u16| controls.len() as u16)]
+    pub count_of_controls: u16,
+    pub background: Resref,
+    #[serde(skip)]
+    #[bw(map = |_: &u16| first_control)]
+    pub first_control_index: u16,
+    pub options: u16,
+    #[br(ignore)]
+    #[bw(ignore)]
+    pub controls: Vec<Control>,
+}
+
+impl Window {
+    pub fn has_background(&self) -> bool {
+        self.flags.contains(WindowFlags::HAS_BACKGROUND)
+    }
+
+    // Draws the outline of every control, coloured by type, over the window's background
+    pub fn render(&self, background: Option<&Image>) -> Image {
+        let mut image = Image::new(self.width.into(), self.height.into());
+        if let Some(background) = background {
+            image.blit(background, 0, 0);
+        }
+        for control in &self.controls {
+            let (x, y) = (i64::from(control.x), i64::from(control.y));
+            let (right, bottom) = (
+                x + i64::from(control.width.max(1)) - 1,
+                y + i64::from(control.height.max(1)) - 1,
+            );
+            image.draw_polygon(
+                &[(x, y), (right, y), (right, bottom), (x, bottom)],
+                control.kind.debug_colour(),
+            );
+        }
+        image
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_ControlTable
+#[derive(Debug, PartialEq, BinRead, BinWrite)]
+struct ControlTableEntry {
+    offset: u32,
+    length: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Common
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[br(import(length: u32))]
+pub struct Control {
+    pub id: u32,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    #[serde(skip)]
+    #[bw(map = |_: &u8| kind.control_type())]
+    control_type: u8,
+    #[serde(skip)]
+    _unknown: u8,
+    #[serde(flatten)]
+    #[br(args(control_type, length))]
+    pub kind: ControlKind,
+    // Bytes past the end of a known control that the table entry still covers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[br(ignore)]
+    pub trailing: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[br(import(control_type: u8, length: u32))]
+#[serde(tag = "control_type", rename_all = "snake_case")]
+pub enum ControlKind {
+    #[br(pre_assert(control_type == 0))]
+    Button(Button),
+    #[br(pre_assert(control_type == 2))]
+    Slider(Slider),
+    #[br(pre_assert(control_type == 3))]
+    TextEdit(TextEdit),
+    #[br(pre_assert(control_type == 5))]
+    TextArea(TextArea),
+    #[br(pre_assert(control_type == 6))]
+    Label(Label),
+    #[br(pre_assert(control_type == 7))]
+    ScrollBar(ScrollBar),
+    // Kept as raw bytes so the control still round trips
+    Unknown {
+        #[br(calc = control_type)]
+        #[bw(ignore)]
+        type_id: u8,
+        #[br(count = length.saturating_sub(CONTROL_HEADER_SIZE))]
+        data: Vec<u8>,
+    },
+}
+
+impl ControlKind {
+    pub fn control_type(&self) -> u8 {
+        match self {
+            ControlKind::Button(_) => 0,
+            ControlKind::Slider(_) => 2,
+            ControlKind::TextEdit(_) => 3,
+            ControlKind::TextArea(_) => 5,
+            ControlKind::Label(_) => 6,
+            ControlKind::ScrollBar(_) => 7,
+            ControlKind::Unknown { type_id, .. } => *type_id,
+        }
+    }
+
+    fn debug_colour(&self) -> [u8; 4] {
+        match self {
+            ControlKind::Button(_) => [255, 0, 0, 255],
+            ControlKind::Slider(_) => [255, 128, 0, 255],
+            ControlKind::TextEdit(_) => [0, 255, 0, 255],
+            ControlKind::TextArea(_) => [0, 255, 255, 255],
+            ControlKind::Label(_) => [255, 255, 0, 255],
+            ControlKind::ScrollBar(_) => [0, 0, 255, 255],
+            ControlKind::Unknown { .. } => [255, 0, 255, 255],
+        }
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Button
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Button {
+    pub animation: Resref,
+    pub animation_cycle: u8,
+    pub text_flags: ButtonFlags,
+    pub frame_unpressed: u8,
+    pub anchor_x1: u8,
+    pub frame_pressed: u8,
+    pub anchor_x2: u8,
+    pub frame_selected: u8,
+    pub anchor_y1: u8,
+    pub frame_disabled: u8,
+    pub anchor_y2: u8,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Slider
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Slider {
+    pub background: Resref,
+    pub knob: Resref,
+    pub knob_cycle: u16,
+    pub frame_ungrabbed: u16,
+    pub frame_grabbed: u16,
+    pub knob_x_offset: u16,
+    pub knob_y_offset: u16,
+    pub jump_width: u16,
+    pub jump_count: u16,
+    pub active_bar_top: u16,
+    pub active_bar_bottom: u16,
+    pub active_bar_left: u16,
+    pub active_bar_right: u16,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_TextEdit
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub background_1: Resref,
+    pub background_2: Resref,
+    pub background_3: Resref,
+    pub cursor: Resref,
+    pub cursor_cycle: u16,
+    pub cursor_frame: u16,
+    pub x_coordinate: u16,
+    pub y_coordinate: u16,
+    pub scroll_bar_id: u32,
+    pub font: Resref,
+    #[serde(skip)]
+    _unknown: u16,
+    pub initial_text: CharArray<32>,
+    pub max_length: u16,
+    // 0 = normal, 1 = upper case, 2 = lower case
+    pub case: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_TextArea
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct TextArea {
+    pub initials_font: Resref,
+    pub main_font: Resref,
+    // RGBA
+    pub colour_1: u32,
+    pub colour_2: u32,
+    pub colour_3: u32,
+    pub scroll_bar_id: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Label
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Label {
+    pub text: u32,
+    pub font: Resref,
+    // RGBA
+    pub colour_1: u32,
+    pub colour_2: u32,
+    pub text_flags: LabelFlags,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_ScrollBar
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct ScrollBar {
+    pub animation: Resref,
+    pub animation_cycle: u16,
+    pub frame_up_unpressed: u16,
+    pub frame_up_pressed: u16,
+    pub frame_down_unpressed: u16,
+    pub frame_down_pressed: u16,
+    pub frame_trough: u16,
+    pub frame_slider: u16,
+    pub text_area_id: u32,
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Window
+    WindowFlags: u16 {
+        HAS_BACKGROUND = 0x1,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Button
+    ButtonFlags: u8 {
+        ALIGN_LEFT = 0x1,
+        ALIGN_RIGHT = 0x2,
+        ALIGN_TOP = 0x4,
+        ALIGN_BOTTOM = 0x8,
+        ANCHOR = 0x10,
+        REDUCE_TEXT_SIZE = 0x20,
+        NO_WORD_WRAP = 0x40,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/chu_v1.htm#chuv1_Control_Label
+    LabelFlags: u16 {
+        USE_COLOURS = 0x1,
+        TRUE_COLOUR = 0x2,
+        ALIGN_CENTER = 0x4,
+        ALIGN_LEFT = 0x8,
+        ALIGN_RIGHT = 0x10,
+        ALIGN_TOP = 0x20,
+        ALIGN_MIDDLE = 0x40,
+        ALIGN_BOTTOM = 0x80,
+        WORD_WRAP = 0x100,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn control(id: u32, x: u16, control_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut buffer = id.to_le_bytes().to_vec();
+        for value in [x, 4, 10, 6] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend([control_type, 0]);
+        buffer.extend(data);
+        buffer
+    }
+
+    // No game chu is checked in to fixtures, so the windows and controls are built by hand
+    fn chu() -> Vec<u8> {
+        let mut button = b"GUIBTBUT".to_vec();
+        button.extend([0, 0x10, 1, 0, 2, 0, 3, 0, 4, 0]);
+        let mut label = 1234_u32.to_le_bytes().to_vec();
+        label.extend(b"NORMAL\0\0");
+        label.extend([0; 8]);
+        label.extend(0x105_u16.to_le_bytes());
+        let controls = [
+            control(0, 2, 0, &button),
+            control(1, 20, 6, &[label, vec![7, 7]].concat()),
+            control(7, 40, 4, &[9, 9, 9, 9]),
+        ];
+
+        let mut buffer = b"CHUIV1  ".to_vec();
+        for value in [2_u32, 0x14 + 2 * 0x1c, 0x14] {
+            buffer.extend(value.to_le_bytes());
+        }
+        for (id, has_background, count, first) in [(0_u16, 1_u16, 2_u16, 0_u16), (5, 0, 1, 2)] {
+            for value in [id, 0, 0, 0, 64, 16, has_background, count] {
+                buffer.extend(value.to_le_bytes());
+            }
+            buffer.extend(if has_background == 1 {
+                b"GUIBACK\0"
+            } else {
+                b"\0\0\0\0\0\0\0\0"
+            });
+            buffer.extend(first.to_le_bytes());
+            buffer.extend(0_u16.to_le_bytes());
+        }
+        let mut offset = buffer.len() as u32 + 3 * 8;
+        for control in &controls {
+            buffer.extend(offset.to_le_bytes());
+            buffer.extend((control.len() as u32).to_le_bytes());
+            offset += control.len() as u32;
+        }
+        for control in controls {
+            buffer.extend(control);
+        }
+        buffer
+    }
+
+    #[test]
+    fn parse() {
+        let buffer = chu();
+        let chu = Chu::new(&buffer);
+        assert_eq!(chu.windows.len(), 2);
+        assert!(chu.windows[0].has_background());
+        assert_eq!(chu.windows[0].background.to_string(), "GUIBACK\0");
+        assert_eq!(chu.windows[0].controls.len(), 2);
+        let ControlKind::Button(button) = &chu.windows[0].controls[0].kind else {
+            panic!("Expected a button");
+        };
+        assert_eq!(button.animation.to_string(), "GUIBTBUT");
+        assert_eq!(button.text_flags, ButtonFlags::ANCHOR);
+        let ControlKind::Label(label) = &chu.windows[0].controls[1].kind else {
+            panic!("Expected a label");
+        };
+        assert_eq!(label.text, 1234);
+        assert_eq!(chu.windows[0].controls[1].trailing, vec![7, 7]);
+        assert!(chu.windows[0].controls[0].trailing.is_empty());
+        assert_eq!(
+            label.text_flags,
+            LabelFlags::USE_COLOURS | LabelFlags::ALIGN_CENTER | LabelFlags::WORD_WRAP
+        );
+        assert_eq!(
+            chu.windows[1].controls[0].kind,
+            ControlKind::Unknown {
+                type_id: 4,
+                data: vec![9, 9, 9, 9]
+            }
+        );
+        assert_eq!(chu.to_bytes(), buffer);
+    }
+
+    #[test]
+    fn edit_through_json() {
+        let chu = Chu::new(&chu());
+        let mut json = serde_json::to_value(&chu).unwrap();
+        assert_eq!(json["windows"][0]["controls"][1]["control_type"], "label");
+        // Moving the label to the second window shifts the control indexes
+        let label = json["windows"][0]["controls"]
+            .as_array_mut()
+            .unwrap()
+            .remove(1);
+        json["windows"][1]["controls"]
+            .as_array_mut()
+            .unwrap()
+            .insert(0, label);
+        let edited: Chu = serde_json::from_value(json).unwrap();
+        let reread = Chu::new(&edited.to_bytes());
+        assert_eq!(reread.windows[0].controls.len(), 1);
+        assert_eq!(reread.windows[1].first_control_index, 1);
+        assert_eq!(reread.windows[1].controls[0].kind.control_type(), 6);
+        assert_eq!(reread.windows[1].controls[1].kind.control_type(), 4);
+    }
+
+    #[test]
+    fn render() {
+        let chu = Chu::new(&chu());
+        let image = chu.windows[0].render(None);
+        assert_eq!((image.width, image.height), (64, 16));
+        // The button's top left corner, then the label's
+        assert_eq!(image.pixel(2, 4), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(20, 4), Some([255, 255, 0, 255]));
+        assert_eq!(image.pixel(5, 7), Some([0, 0, 0, 0]));
+    }
+}
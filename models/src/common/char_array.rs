@@ -10,7 +10,7 @@ use serde::{
     de::{Error, Visitor},
 };
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, BinRead, BinWrite)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, BinRead, BinWrite)]
 pub struct CharArray<const N: usize>(pub(crate) [u8; N]);
 
 impl<const N: usize> Serialize for CharArray<N> {
@@ -30,8 +30,13 @@ impl<const N: usize> Visitor<'_> for CharArrayVistor<N> {
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         formatter.write_str("a string")
     }
+    // Display writes each byte as a char, so reading those chars back as bytes keeps
+    // anything after the null terminator intact
     fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
-        Ok(CharArray::<N>::from(v))
+        match v.chars().map(u8::try_from).collect::<Result<Vec<u8>, _>>() {
+            Ok(bytes) => Ok(CharArray::<N>::from(bytes.as_slice())),
+            Err(_) => Ok(CharArray::<N>::from(v)),
+        }
     }
 }
 
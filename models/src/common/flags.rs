@@ -0,0 +1,196 @@
+use serde::Deserialize;
+
+// Either form is accepted when reading json back
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum NameOrNumber {
+    Number(i64),
+    Name(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum FlagsValue {
+    Number(i64),
+    Names(Vec<NameOrNumber>),
+}
+
+// A bit field stored as a plain integer, written to json as the names of the set bits.
+// Bits without a name are written as their value so nothing is lost.
+macro_rules! flags {
+    ($(#[$meta:meta])* $name:ident: $type:ty { $($flag:ident = $bit:expr,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, binrw::BinRead, binrw::BinWrite)]
+        pub struct $name(pub $type);
+
+        #[allow(dead_code)]
+        impl $name {
+            $(pub const $flag: $name = $name($bit);)*
+            pub const NAMES: &[(&str, $type)] = &[$((stringify!($flag), $bit),)*];
+
+            pub fn contains(self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub fn names(self) -> Vec<&'static str> {
+                Self::NAMES
+                    .iter()
+                    .filter(|(_, bit)| *bit != 0 && self.0 & bit == *bit)
+                    .map(|(name, _)| *name)
+                    .collect()
+            }
+        }
+
+        impl std::ops::BitOr for $name {
+            type Output = $name;
+
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeSeq;
+                let mut sequence = serializer.serialize_seq(None)?;
+                let mut remaining = self.0;
+                for name in self.names() {
+                    sequence.serialize_element(name)?;
+                    remaining &= !Self::NAMES.iter().find(|(other, _)| *other == name).map_or(0, |(_, bit)| *bit);
+                }
+                for shift in 0..<$type>::BITS {
+                    let bit: $type = 1 << shift;
+                    if remaining & bit != 0 {
+                        sequence.serialize_element(&bit)?;
+                    }
+                }
+                sequence.end()
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use crate::common::flags::{FlagsValue, NameOrNumber};
+                use serde::de::Error;
+                let number = |value: i64| {
+                    <$type>::try_from(value).map_err(|_| D::Error::custom(format!("{value} is out of range")))
+                };
+                match FlagsValue::deserialize(deserializer)? {
+                    FlagsValue::Number(value) => Ok($name(number(value)?)),
+                    FlagsValue::Names(names) => {
+                        let mut out = 0;
+                        for name in names {
+                            out |= match name {
+                                NameOrNumber::Number(value) => number(value)?,
+                                NameOrNumber::Name(name) => Self::NAMES
+                                    .iter()
+                                    .find(|(flag, _)| flag.eq_ignore_ascii_case(&name))
+                                    .map(|(_, bit)| *bit)
+                                    .ok_or_else(|| D::Error::custom(format!("Unknown flag {name}")))?,
+                            };
+                        }
+                        Ok($name(out))
+                    }
+                }
+            }
+        }
+    };
+}
+
+// A value from an ids file, written to json as its symbol. Values without a symbol are kept
+// as Unknown and written as a number so they round trip.
+macro_rules! ids_enum {
+    ($(#[$meta:meta])* $name:ident: $type:ty { $($variant:ident = $value:expr => $symbol:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($variant,)*
+            Unknown($type),
+        }
+
+        impl $name {
+            pub fn symbol(self) -> Option<&'static str> {
+                match self {
+                    $($name::$variant => Some($symbol),)*
+                    $name::Unknown(_) => None,
+                }
+            }
+
+            pub fn from_symbol(symbol: &str) -> Option<Self> {
+                $(if symbol.eq_ignore_ascii_case($symbol) {
+                    return Some($name::$variant);
+                })*
+                None
+            }
+        }
+
+        impl From<$type> for $name {
+            fn from(value: $type) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    value => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for $type {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl binrw::BinRead for $name {
+            type Args<'a> = ();
+
+            fn read_options<R: binrw::io::Read + binrw::io::Seek>(
+                reader: &mut R,
+                endian: binrw::Endian,
+                args: Self::Args<'_>,
+            ) -> binrw::BinResult<Self> {
+                <$type>::read_options(reader, endian, args).map($name::from)
+            }
+        }
+
+        impl binrw::BinWrite for $name {
+            type Args<'a> = ();
+
+            fn write_options<W: binrw::io::Write + binrw::io::Seek>(
+                &self,
+                writer: &mut W,
+                endian: binrw::Endian,
+                args: Self::Args<'_>,
+            ) -> binrw::BinResult<()> {
+                <$type>::from(*self).write_options(writer, endian, args)
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.symbol() {
+                    Some(symbol) => serializer.serialize_str(symbol),
+                    None => <$type>::from(*self).serialize(serializer),
+                }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                use crate::common::flags::NameOrNumber;
+                use serde::de::Error;
+                match NameOrNumber::deserialize(deserializer)? {
+                    NameOrNumber::Number(value) => <$type>::try_from(value)
+                        .map($name::from)
+                        .map_err(|_| D::Error::custom(format!("{value} is out of range"))),
+                    NameOrNumber::Name(name) => $name::from_symbol(&name)
+                        .ok_or_else(|| D::Error::custom(format!("Unknown {} {name}", stringify!($name)))),
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use flags;
+pub(crate) use ids_enum;
@@ -0,0 +1,141 @@
+use std::error::Error;
+
+const CHANNELS: usize = 4;
+
+// A plain RGBA image, used when decoding tiles, maps and other graphics
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    // RGBA, row major, top to bottom
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * CHANNELS],
+        }
+    }
+
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        if pixels.len() != width as usize * height as usize * CHANNELS {
+            return Err(format!(
+                "Expected {} bytes for a {width}x{height} image, found {}",
+                width as usize * height as usize * CHANNELS,
+                pixels.len()
+            )
+            .into());
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn position(&self, x: u32, y: u32) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some((y as usize * self.width as usize + x as usize) * CHANNELS)
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        let start = self.position(x, y)?;
+        self.pixels.get(start..start + CHANNELS)?.try_into().ok()
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        if let Some(start) = self.position(x, y) {
+            self.pixels[start..start + CHANNELS].copy_from_slice(&rgba);
+        }
+    }
+
+    // Copies a region of this image, anything outside of the image is left transparent
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        let mut out = Image::new(width, height);
+        for row in 0..height {
+            for column in 0..width {
+                if let Some(rgba) = self.pixel(x + column, y + row) {
+                    out.set_pixel(column, row, rgba);
+                }
+            }
+        }
+        out
+    }
+
+    // Draws another image on top of this one, fully transparent pixels are skipped
+    pub fn blit(&mut self, other: &Image, x: i64, y: i64) {
+        for row in 0..other.height {
+            for column in 0..other.width {
+                let (target_x, target_y) = (x + column as i64, y + row as i64);
+                if target_x < 0 || target_y < 0 {
+                    continue;
+                }
+                match other.pixel(column, row) {
+                    Some(rgba) if rgba[3] != 0 => {
+                        self.set_pixel(target_x as u32, target_y as u32, rgba)
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Bresenham's line algorithm, points outside of the image are clipped
+    pub fn draw_line(&mut self, from: (i64, i64), to: (i64, i64), rgba: [u8; 4]) {
+        let (mut x, mut y) = from;
+        let delta_x = (to.0 - x).abs();
+        let delta_y = -(to.1 - y).abs();
+        let step_x = if x < to.0 { 1 } else { -1 };
+        let step_y = if y < to.1 { 1 } else { -1 };
+        let mut error = delta_x + delta_y;
+        loop {
+            if x >= 0 && y >= 0 {
+                self.set_pixel(x as u32, y as u32, rgba);
+            }
+            if (x, y) == to {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= delta_y {
+                error += delta_y;
+                x += step_x;
+            }
+            if doubled <= delta_x {
+                error += delta_x;
+                y += step_y;
+            }
+        }
+    }
+
+    // Draws the outline of a closed polygon
+    pub fn draw_polygon(&mut self, points: &[(i64, i64)], rgba: [u8; 4]) {
+        for (i, from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            self.draw_line(*from, to, rgba);
+        }
+    }
+
+    // Draws a small cross centred on a point
+    pub fn draw_marker(&mut self, point: (i64, i64), rgba: [u8; 4]) {
+        let (x, y) = point;
+        self.draw_line((x - 4, y - 4), (x + 4, y + 4), rgba);
+        self.draw_line((x - 4, y + 4), (x + 4, y - 4), rgba);
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut out = vec![];
+        {
+            let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+        }
+        Ok(out)
+    }
+}
@@ -1,10 +1,29 @@
+use std::path::{Path, PathBuf};
+
 use char_array::CharArray;
 
 pub mod char_array;
+pub mod encryption;
 pub mod feature_block;
+pub mod flags;
 pub mod header;
+pub mod image;
 pub mod parsers;
+pub mod sections;
 pub mod strref;
 pub mod types;
 
 pub type Resref = CharArray<8>;
+
+// Game files are often upper case, so match file names case insensitively
+pub fn find_file(directory: &Path, file_name: &str) -> Option<PathBuf> {
+    directory
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.file_name()
+                .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+        })
+}
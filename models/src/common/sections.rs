@@ -0,0 +1,26 @@
+// Hands out the offsets of sections written back to back, as the writers work out the
+// offsets and counts a header points at from what they are about to write
+#[derive(Debug, Clone, Copy)]
+pub struct Sections {
+    offset: u32,
+}
+
+impl Sections {
+    pub fn starting_at(offset: u32) -> Self {
+        Self { offset }
+    }
+
+    // Places count entries of the given size, returning their offset and count
+    pub fn place(&mut self, count: usize, size: u32) -> (u32, u32) {
+        let section = (self.offset, count as u32);
+        self.offset += section.1 * size;
+        section
+    }
+
+    // Places a section of the given length in bytes, returning its offset
+    pub fn place_bytes(&mut self, length: u32) -> u32 {
+        let offset = self.offset;
+        self.offset += length;
+        offset
+    }
+}
@@ -81,7 +81,7 @@ impl From<ResourceType> for String {
             ResourceType::FileTypeBam => "bam",
             ResourceType::FileTypeWed => "wed",
             ResourceType::FileTypeChu => "chu",
-            ResourceType::FileTypeTi => "ti",
+            ResourceType::FileTypeTi => "tis",
             ResourceType::FileTypeMos => "mos",
             ResourceType::FileTypeItm => "itm",
             ResourceType::FileTypeSpl => "spl",
@@ -135,7 +135,7 @@ impl From<&str> for ResourceType {
             "bam" => ResourceType::FileTypeBam,
             "wed" => ResourceType::FileTypeWed,
             "chu" => ResourceType::FileTypeChu,
-            "ti" => ResourceType::FileTypeTi,
+            "ti" | "tis" => ResourceType::FileTypeTi,
             "mos" => ResourceType::FileTypeMos,
             "itm" => ResourceType::FileTypeItm,
             "spl" => ResourceType::FileTypeSpl,
@@ -1,3 +1,5 @@
+use std::error::Error;
+
 use binrw::{
     BinRead, BinReaderExt, BinWrite,
     helpers::until_eof,
@@ -5,44 +7,51 @@ use binrw::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::common::{Resref, header::Header, strref::Strref};
+use crate::common::{Resref, header::Header, sections::Sections, strref::Strref};
+use crate::creature_types::{
+    Alignment, Class, CreatureFlags, EnemyAlly, Gender, General, Kit, Race, Specific, StateFlags,
+};
 use crate::effect_v1::EffectV1;
 use crate::item_table::ItemReferenceTable;
-use crate::{common::char_array::CharArray, effect_v2::EffectV2Body};
+use crate::{
+    common::char_array::CharArray,
+    effect_v2::{EffectV2Body, EffectV2BodyWithOutHeader},
+};
 use crate::{
     item_table::ItemSlots,
     model::Model,
     spell_table::{KnownSpells, SpellMemorizationInfo, SpellMemorizationTable},
 };
 
-// TODO: Fix writing this as a binary file
+const HEADER_SIZE: u32 = 0x2d4;
+const KNOWN_SPELL_SIZE: u32 = 0x0c;
+const MEMORIZATION_INFO_SIZE: u32 = 0x10;
+const MEMORIZED_SPELL_SIZE: u32 = 0x0c;
+const ITEM_SLOTS_SIZE: u32 = 0x50;
+const ITEM_SIZE: u32 = 0x14;
+const EFFECT_V1_SIZE: u32 = 0x30;
+const EFFECT_V2_SIZE: u32 = 0x108;
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/cre_v1.htm
-#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, Serialize, Deserialize)]
 pub struct Creature {
     #[serde(skip)]
     #[br(parse_with = until_eof, restore_position)]
     pub original_bytes: Vec<u8>,
     #[serde(flatten)]
     pub header: BGEECreatureHeader,
-    #[bw(ignore)]
     #[br(count=header.count_of_known_spells, seek_before=SeekFrom::Start(header.offset_to_known_spells as u64))]
     pub known_spells: Vec<KnownSpells>,
-    #[bw(ignore)]
     #[br(count=header.count_of_spell_memorization_info, seek_before=SeekFrom::Start(header.offset_to_spell_memorization_info as u64))]
     pub memorized_spell_info: Vec<SpellMemorizationInfo>,
-    #[bw(ignore)]
     #[br(count=header.count_of_memorized_spell_table, seek_before=SeekFrom::Start(header.offset_to_memorized_spell_table as u64))]
     pub memorized_spells: Vec<SpellMemorizationTable>,
-    #[bw(ignore)]
     #[br(if(header.effstructure == 0), count=header.count_of_effects, seek_before=SeekFrom::Start(header.offset_to_effects as u64))]
     pub effects_v1: Vec<EffectV1>,
-    #[bw(ignore)]
     #[br(if(header.effstructure == 1), count=header.count_of_effects, seek_before=SeekFrom::Start(header.offset_to_effects as u64))]
     pub effects_v2: Vec<EffectV2Body>,
-    #[bw(ignore)]
     #[br(count=header.count_of_items, seek_before=SeekFrom::Start(header.offset_to_items as u64))]
     pub item_table: Vec<ItemReferenceTable>,
-    #[bw(ignore)]
     #[br(seek_before=SeekFrom::Start(header.offset_to_item_slots as u64))]
     pub item_slots: Option<ItemSlots>,
 }
@@ -58,26 +67,101 @@ impl Model for Creature {
         }
     }
 
+    // Sections are written in the order saved games use, spells, item slots, items then
+    // effects. Only the effects matching effstructure are written.
     fn to_bytes(&self) -> Vec<u8> {
+        let effects = match self.header.effstructure {
+            0 => (self.effects_v1.len(), EFFECT_V1_SIZE),
+            _ => (self.effects_v2.len(), EFFECT_V2_SIZE),
+        };
+        let mut sections = Sections::starting_at(HEADER_SIZE);
+        let known_spells = sections.place(self.known_spells.len(), KNOWN_SPELL_SIZE);
+        let memorization_info =
+            sections.place(self.memorized_spell_info.len(), MEMORIZATION_INFO_SIZE);
+        let memorized_spells = sections.place(self.memorized_spells.len(), MEMORIZED_SPELL_SIZE);
+        let item_slots =
+            sections.place_bytes(self.item_slots.as_ref().map_or(0, |_| ITEM_SLOTS_SIZE));
+        let items = sections.place(self.item_table.len(), ITEM_SIZE);
+        let effects = sections.place(effects.0, effects.1);
+        // The games leave the offset of an empty section at 0
+        let empty_at_zero = |(offset, count): (u32, u32)| match count {
+            0 => (0, 0),
+            _ => (offset, count),
+        };
+        let layout = CreatureLayout {
+            known_spells: empty_at_zero(known_spells),
+            memorization_info: empty_at_zero(memorization_info),
+            memorized_spells: empty_at_zero(memorized_spells),
+            item_slots,
+            items: empty_at_zero(items),
+            effects: empty_at_zero(effects),
+        };
+
         let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
+        self.header.write_le_args(&mut writer, (layout,)).unwrap();
+        self.known_spells.write_le(&mut writer).unwrap();
+        self.memorized_spell_info.write_le(&mut writer).unwrap();
+        self.memorized_spells.write_le(&mut writer).unwrap();
+        self.item_slots.write_le(&mut writer).unwrap();
+        self.item_table.write_le(&mut writer).unwrap();
+        match self.header.effstructure {
+            0 => self.effects_v1.write_le(&mut writer).unwrap(),
+            _ => self.effects_v2.write_le(&mut writer).unwrap(),
+        }
         writer.into_inner()
     }
 }
 
+// The offset and count of each section, the item slots are a single block so only have an
+// offset
+#[derive(Debug, Clone, Copy)]
+pub struct CreatureLayout {
+    known_spells: (u32, u32),
+    memorization_info: (u32, u32),
+    memorized_spells: (u32, u32),
+    item_slots: u32,
+    items: (u32, u32),
+    effects: (u32, u32),
+}
+
+impl Creature {
+    // Old style creatures (effstructure 0) store V1 effects, this switches them to V2
+    pub fn convert_effects_to_v2(&mut self) {
+        self.effects_v2.extend(
+            self.effects_v1
+                .drain(..)
+                .map(|effect| EffectV2BodyWithOutHeader::from(&effect).into()),
+        );
+        self.header.effstructure = 1;
+    }
+
+    // Fails without changing the creature if an effect doesn't fit the V1 layout
+    pub fn convert_effects_to_v1(&mut self) -> Result<(), Box<dyn Error>> {
+        let effects = self
+            .effects_v2
+            .iter()
+            .map(|effect| EffectV1::try_from(&effect.body))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.effects_v2.clear();
+        self.effects_v1.extend(effects);
+        self.header.effstructure = 0;
+        Ok(())
+    }
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/cre_v1.htm#CREV1_0_Header
 #[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(layout: CreatureLayout))]
 pub struct BGEECreatureHeader {
     #[serde(flatten)]
     pub header: Header,
     pub long_creature_name: u32,
     pub short_creature_name: u32,
-    // see CRE_FLAG_* above for possible flags
-    pub flags: u32,
+    pub flags: CreatureFlags,
     pub exp_for_killing: u32,
     pub exp: u32,
     pub gold: u32,
-    pub state_flags: u32,
+    pub state_flags: StateFlags,
     pub current_hp: u16,
     pub base_hp: u16,
     pub animation_id: u32,
@@ -168,36 +252,47 @@ pub struct BGEECreatureHeader {
     pub morale_break: u8,
     pub racial_enemy: u8,
     pub morale_recovery_time: u16,
-    pub kit: u32,
+    pub kit: Kit,
     pub override_script: Resref,
     pub class_script: Resref,
     pub race_script: Resref,
     pub general_script: Resref,
     pub creature_script_default: Resref,
-    pub enemy_ally: u8,
-    pub general: u8,
-    pub race: u8,
-    pub class: u8,
-    pub specific: u8,
-    pub gender: u8,
+    pub enemy_ally: EnemyAlly,
+    pub general: General,
+    pub race: Race,
+    pub class: Class,
+    pub specific: Specific,
+    pub gender: Gender,
     // object.ids references
     #[br(count = 5)]
     pub object_references: Vec<u8>,
-    pub alignment: u8,
+    pub alignment: Alignment,
     pub global_actor_enumeration: u16,
     pub local_actor_enumeration: u16,
     // death variable: sprite_is_dead on death
     pub death_variable: CharArray<32>,
+    #[bw(map = |_: &u32| layout.known_spells.0)]
     pub offset_to_known_spells: u32,
+    #[bw(map = |_: &u32| layout.known_spells.1)]
     pub count_of_known_spells: u32,
+    #[bw(map = |_: &u32| layout.memorization_info.0)]
     pub offset_to_spell_memorization_info: u32,
+    #[bw(map = |_: &u32| layout.memorization_info.1)]
     pub count_of_spell_memorization_info: u32,
+    #[bw(map = |_: &u32| layout.memorized_spells.0)]
     pub offset_to_memorized_spell_table: u32,
+    #[bw(map = |_: &u32| layout.memorized_spells.1)]
     pub count_of_memorized_spell_table: u32,
+    #[bw(map = |_: &u32| layout.item_slots)]
     pub offset_to_item_slots: u32,
+    #[bw(map = |_: &u32| layout.items.0)]
     pub offset_to_items: u32,
+    #[bw(map = |_: &u32| layout.items.1)]
     pub count_of_items: u32,
+    #[bw(map = |_: &u32| layout.effects.0)]
     pub offset_to_effects: u32,
+    #[bw(map = |_: &u32| layout.effects.1)]
     pub count_of_effects: u32,
     pub dialog_ref: Resref,
 }
@@ -233,4 +328,87 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn typed_fields() -> Result<(), Box<dyn Error>> {
+        let creature = Creature::new(&read_file("fixtures/cutmelis.cre")?);
+        assert_eq!(creature.header.class, Class::ClericMage);
+        assert_eq!(creature.header.kit, Kit::TrueClass);
+        assert_eq!(creature.header.alignment, Alignment::LawfulGood);
+
+        // Names and numbers are both accepted, bits without a name are kept
+        let flags: CreatureFlags = serde_json::from_str(r#"["no_corpse", 4, 1048576]"#)?;
+        assert_eq!(
+            flags,
+            CreatureFlags::NO_CORPSE | CreatureFlags::KEEP_CORPSE | CreatureFlags(0x100000)
+        );
+        assert!(flags.contains(CreatureFlags::NO_CORPSE));
+        assert_eq!(
+            serde_json::to_string(&flags)?,
+            r#"["NO_CORPSE","KEEP_CORPSE",1048576]"#
+        );
+        let state: StateFlags = serde_json::from_str("34")?;
+        assert_eq!(state, StateFlags::BERSERK | StateFlags::HELPLESS);
+        assert_eq!(serde_json::from_str::<Class>(r#""mage""#)?, Class::Mage);
+        assert_eq!(serde_json::from_str::<Class>("1")?, Class::Mage);
+        assert_eq!(serde_json::to_string(&Class::from(200))?, "200");
+        assert!(serde_json::from_str::<Race>(r#""BEHOLDER""#).is_err());
+
+        let mut writer = Cursor::new(vec![]);
+        Kit::Unknown(0x12345678).write_le(&mut writer)?;
+        assert_eq!(writer.get_ref(), &[0x78, 0x56, 0x34, 0x12]);
+        writer.set_position(0);
+        assert_eq!(Kit::read_le(&mut writer)?, Kit::Unknown(0x12345678));
+        Ok(())
+    }
+
+    #[test]
+    fn convert_effects() -> Result<(), Box<dyn Error>> {
+        let mut creature = Creature::new(&read_file("fixtures/dbeggar.cre")?);
+        // A 2d6 fire damage effect
+        let mut buffer = vec![0_u8; 0x30];
+        buffer[0] = 12;
+        buffer[2] = 2;
+        buffer[0x0a] = 8;
+        buffer[0x12] = 100;
+        buffer[0x1c] = 2;
+        buffer[0x20] = 6;
+        let effect = EffectV1::new(&buffer);
+        creature.header.effstructure = 0;
+        creature.effects_v1.push(EffectV1::new(&buffer));
+
+        creature.convert_effects_to_v2();
+        assert_eq!(creature.header.effstructure, 1);
+        assert!(creature.effects_v1.is_empty());
+        assert_eq!(creature.effects_v2[0].body.opcode_number, 12);
+        assert_eq!(creature.effects_v2[0].body.dice_sides, 6);
+
+        creature.convert_effects_to_v1()?;
+        assert_eq!(creature.header.effstructure, 0);
+        assert!(creature.effects_v2.is_empty());
+        assert_eq!(creature.effects_v1, vec![effect]);
+        Ok(())
+    }
+
+    #[test]
+    fn write() -> Result<(), Box<dyn Error>> {
+        for (file_path, _) in FIXTURES {
+            let creature = Creature::new(&read_file(file_path)?);
+            let bytes = creature.to_bytes();
+            let written = Creature::new(&bytes);
+            assert_eq!(written.known_spells, creature.known_spells);
+            assert_eq!(written.memorized_spell_info, creature.memorized_spell_info);
+            assert_eq!(written.memorized_spells, creature.memorized_spells);
+            assert_eq!(written.item_slots, creature.item_slots);
+            assert_eq!(written.item_table, creature.item_table);
+            assert_eq!(written.effects_v1, creature.effects_v1);
+            assert_eq!(written.effects_v2, creature.effects_v2);
+            // Already in the written order, so writing again changes nothing
+            assert_eq!(written.to_bytes(), bytes);
+
+            let from_json: Creature = serde_json::from_value(serde_json::to_value(&creature)?)?;
+            assert_eq!(from_json.to_bytes(), bytes);
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,230 @@
+use crate::common::flags::{flags, ids_enum};
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/cre_v1.htm#CREV1_0_Header
+    CreatureFlags: u32 {
+        SHOW_LONGNAME = 0x1,
+        NO_CORPSE = 0x2,
+        KEEP_CORPSE = 0x4,
+        ORIGINALLY_FIGHTER = 0x8,
+        ORIGINALLY_MAGE = 0x10,
+        ORIGINALLY_CLERIC = 0x20,
+        ORIGINALLY_THIEF = 0x40,
+        ORIGINALLY_DRUID = 0x80,
+        ORIGINALLY_RANGER = 0x100,
+        FALLEN_PALADIN = 0x200,
+        FALLEN_RANGER = 0x400,
+        EXPORTABLE = 0x800,
+        HIDE_INJURY_STATUS = 0x1000,
+        QUEST_CRITICAL = 0x2000,
+        MOVING_BETWEEN_AREAS = 0x4000,
+        BEEN_IN_PARTY = 0x8000,
+        RESTORE_ITEM_IN_HAND = 0x10000,
+        UNSET_RESTORE_ITEM_IN_HAND = 0x20000,
+        NO_EXPLODING_DEATH = 0x1000000,
+        IGNORE_NIGHTMARE_MODE = 0x4000000,
+        NO_TOOLTIP = 0x8000000,
+        RANDOM_WALK_EA = 0x10000000,
+        RANDOM_WALK_GENERAL = 0x20000000,
+        RANDOM_WALK_RACE = 0x40000000,
+        RANDOM_WALK_CLASS = 0x80000000,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/state.htm
+    StateFlags: u32 {
+        SLEEPING = 0x1,
+        BERSERK = 0x2,
+        PANIC = 0x4,
+        STUNNED = 0x8,
+        INVISIBLE = 0x10,
+        HELPLESS = 0x20,
+        FROZEN_DEATH = 0x40,
+        STONE_DEATH = 0x80,
+        EXPLODING_DEATH = 0x100,
+        FLAME_DEATH = 0x200,
+        ACID_DEATH = 0x400,
+        DEAD = 0x800,
+        SILENCED = 0x1000,
+        CHARMED = 0x2000,
+        POISONED = 0x4000,
+        HASTED = 0x8000,
+        SLOWED = 0x10000,
+        INFRAVISION = 0x20000,
+        BLIND = 0x40000,
+        DISEASED = 0x80000,
+        FEEBLEMINDED = 0x100000,
+        NONDETECTION = 0x200000,
+        IMPROVEDINVISIBILITY = 0x400000,
+        BLESS = 0x800000,
+        CHANT = 0x1000000,
+        DRAWUPONHOLYMIGHT = 0x2000000,
+        LUCK = 0x4000000,
+        AID = 0x8000000,
+        CHANTBAD = 0x10000000,
+        BLUR = 0x20000000,
+        MIRRORIMAGE = 0x40000000,
+        CONFUSED = 0x80000000,
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/ea.htm
+    EnemyAlly: u8 {
+        Anyone = 0 => "ANYONE",
+        Inanimate = 1 => "INANIMATE",
+        Pc = 2 => "PC",
+        Familiar = 3 => "FAMILIAR",
+        Ally = 4 => "ALLY",
+        Controlled = 5 => "CONTROLLED",
+        Charmed = 6 => "CHARMED",
+        GoodButRed = 28 => "GOODBUTRED",
+        GoodButBlue = 29 => "GOODBUTBLUE",
+        GoodCutoff = 30 => "GOODCUTOFF",
+        NotGood = 31 => "NOTGOOD",
+        Anything = 126 => "ANYTHING",
+        Neutral = 128 => "NEUTRAL",
+        NotEvil = 199 => "NOTEVIL",
+        EvilCutoff = 200 => "EVILCUTOFF",
+        EvilButGreen = 201 => "EVILBUTGREEN",
+        EvilButBlue = 202 => "EVILBUTBLUE",
+        Enemy = 255 => "ENEMY",
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/general.htm
+    General: u8 {
+        Anyone = 0 => "ANYONE",
+        Humanoid = 1 => "HUMANOID",
+        Animal = 2 => "ANIMAL",
+        Dead = 3 => "DEAD",
+        Undead = 4 => "UNDEAD",
+        GiantHumanoid = 5 => "GIANTHUMANOID",
+        Frozen = 6 => "FROZEN",
+        Monster = 255 => "MONSTER",
+    }
+}
+
+ids_enum! {
+    // Only the playable races, the rest depend on the game
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/race.htm
+    Race: u8 {
+        Human = 1 => "HUMAN",
+        Elf = 2 => "ELF",
+        HalfElf = 3 => "HALF_ELF",
+        Dwarf = 4 => "DWARF",
+        Halfling = 5 => "HALFLING",
+        Gnome = 6 => "GNOME",
+        HalfOrc = 7 => "HALFORC",
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/class.htm
+    Class: u8 {
+        Mage = 1 => "MAGE",
+        Fighter = 2 => "FIGHTER",
+        Cleric = 3 => "CLERIC",
+        Thief = 4 => "THIEF",
+        Bard = 5 => "BARD",
+        Paladin = 6 => "PALADIN",
+        FighterMage = 7 => "FIGHTER_MAGE",
+        FighterCleric = 8 => "FIGHTER_CLERIC",
+        FighterThief = 9 => "FIGHTER_THIEF",
+        FighterMageThief = 10 => "FIGHTER_MAGE_THIEF",
+        Druid = 11 => "DRUID",
+        Ranger = 12 => "RANGER",
+        MageThief = 13 => "MAGE_THIEF",
+        ClericMage = 14 => "CLERIC_MAGE",
+        ClericThief = 15 => "CLERIC_THIEF",
+        FighterDruid = 16 => "FIGHTER_DRUID",
+        FighterMageCleric = 17 => "FIGHTER_MAGE_CLERIC",
+        ClericRanger = 18 => "CLERIC_RANGER",
+        Sorcerer = 19 => "SORCERER",
+        Monk = 20 => "MONK",
+        Shaman = 21 => "SHAMAN",
+        Innocent = 155 => "INNOCENT",
+    }
+}
+
+ids_enum! {
+    // Mostly defined by the game and its mods
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/specific.htm
+    Specific: u8 {
+        None = 0 => "NONE",
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/gender.htm
+    Gender: u8 {
+        Male = 1 => "MALE",
+        Female = 2 => "FEMALE",
+        Other = 3 => "OTHER",
+        // Spelt as in gender.ids
+        Neither = 4 => "NIETHER",
+        Both = 5 => "BOTH",
+        Summoned = 6 => "SUMMONED",
+        Illusionary = 7 => "ILLUSIONARY",
+        Extra = 8 => "EXTRA",
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/align.htm
+    Alignment: u8 {
+        None = 0 => "NONE",
+        LawfulGood = 0x11 => "LAWFUL_GOOD",
+        LawfulNeutral = 0x12 => "LAWFUL_NEUTRAL",
+        LawfulEvil = 0x13 => "LAWFUL_EVIL",
+        NeutralGood = 0x21 => "NEUTRAL_GOOD",
+        Neutral = 0x22 => "NEUTRAL",
+        NeutralEvil = 0x23 => "NEUTRAL_EVIL",
+        ChaoticGood = 0x31 => "CHAOTIC_GOOD",
+        ChaoticNeutral = 0x32 => "CHAOTIC_NEUTRAL",
+        ChaoticEvil = 0x33 => "CHAOTIC_EVIL",
+    }
+}
+
+ids_enum! {
+    // Stored with the words swapped compared to kit.ids
+    // https://gibberlings3.github.io/iesdp/files/ids/bg2/kit.htm
+    Kit: u32 {
+        None = 0 => "NONE",
+        Abjurer = 0x400000 => "ABJURER",
+        Conjurer = 0x800000 => "CONJURER",
+        Diviner = 0x1000000 => "DIVINER",
+        Enchanter = 0x2000000 => "ENCHANTER",
+        Illusionist = 0x4000000 => "ILLUSIONIST",
+        Invoker = 0x8000000 => "INVOKER",
+        Necromancer = 0x10000000 => "NECROMANCER",
+        Transmuter = 0x20000000 => "TRANSMUTER",
+        TrueClass = 0x40000000 => "TRUECLASS",
+        Berserker = 0x40010000 => "BERSERKER",
+        Wizardslayer = 0x40020000 => "WIZARDSLAYER",
+        Kensai = 0x40030000 => "KENSAI",
+        Cavalier = 0x40040000 => "CAVALIER",
+        Inquisitor = 0x40050000 => "INQUISITOR",
+        UndeadHunter = 0x40060000 => "UNDEADHUNTER",
+        Feralan = 0x40070000 => "FERALAN",
+        Stalker = 0x40080000 => "STALKER",
+        Beastmaster = 0x40090000 => "BEASTMASTER",
+        // Spelt as in kit.ids
+        Assassin = 0x400A0000 => "ASSASIN",
+        BountyHunter = 0x400B0000 => "BOUNTYHUNTER",
+        Swashbuckler = 0x400C0000 => "SWASHBUCKLER",
+        Blade = 0x400D0000 => "BLADE",
+        Jester = 0x400E0000 => "JESTER",
+        Skald = 0x400F0000 => "SKALD",
+        GodTalos = 0x40100000 => "GODTALOS",
+        GodHelm = 0x40110000 => "GODHELM",
+        GodLathander = 0x40120000 => "GODLATHANDER",
+        Totemic = 0x40130000 => "TOTEMIC",
+        Shapeshifter = 0x40140000 => "SHAPESHIFTER",
+        Avenger = 0x40150000 => "AVENGER",
+        Barbarian = 0x40160000 => "BARBARIAN",
+        Wildmage = 0x40170000 => "WILDMAGE",
+    }
+}
@@ -1,7 +1,12 @@
+use std::error::Error;
+
 use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
 use serde::{Deserialize, Serialize};
 
 use crate::common::Resref;
+use crate::common::char_array::CharArray;
+use crate::common::feature_block::FeatureBlock;
+use crate::effect_v2::EffectV2BodyWithOutHeader;
 use crate::model::Model;
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/eff_v1.htm#effv1_Header
@@ -23,13 +28,18 @@ pub struct EffectV1 {
     pub saving_throw_type: u32,
     pub saving_throw_bonus: u32,
     #[serde(skip)]
-    _unknown: u32,
+    pub(crate) _unknown: u32,
 }
 
 impl Model for EffectV1 {
     fn new(buffer: &[u8]) -> Self {
         let mut reader = Cursor::new(buffer);
-        reader.read_le().unwrap()
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
     }
 
     fn to_bytes(&self) -> Vec<u8> {
@@ -38,3 +48,155 @@ impl Model for EffectV1 {
         writer.into_inner()
     }
 }
+
+// Standalone V1 effects have no header, where V2 files start with "EFF "
+pub fn is_effect_v1(buffer: &[u8]) -> bool {
+    !buffer.starts_with(b"EFF ")
+}
+
+fn narrow<T: TryFrom<u32>>(value: impl Into<u32>, field: &str) -> Result<T, Box<dyn Error>> {
+    let value = value.into();
+    T::try_from(value).map_err(|_| format!("{field} {value} does not fit an EFF V1 effect").into())
+}
+
+// The feature block shares the V1 layout, so these are lossless
+impl From<&FeatureBlock> for EffectV1 {
+    fn from(value: &FeatureBlock) -> Self {
+        Self {
+            effect_type: value.opcode_number,
+            target_type: value.target_type,
+            power: value.power,
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode,
+            dispel_resistance: value.dispel_resistance,
+            duration: value.duration,
+            probability_1: value.probability_1,
+            probability_2: value.probability_2,
+            resref_key: CharArray(value.resource.0),
+            dice_thrown_maximum_level: value.dice_thrown_max_level,
+            dice_sides_minimum_level: value.dice_sides_min_level,
+            saving_throw_type: u32::from_le_bytes(value.saving_throw_type.0),
+            saving_throw_bonus: value.saving_throw_bonus,
+            _unknown: value.stacking_id,
+        }
+    }
+}
+
+impl From<&EffectV1> for FeatureBlock {
+    fn from(value: &EffectV1) -> Self {
+        Self {
+            opcode_number: value.effect_type,
+            target_type: value.target_type,
+            power: value.power,
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode,
+            dispel_resistance: value.dispel_resistance,
+            duration: value.duration,
+            probability_1: value.probability_1,
+            probability_2: value.probability_2,
+            resource: CharArray(value.resref_key.0),
+            dice_thrown_max_level: value.dice_thrown_maximum_level,
+            dice_sides_min_level: value.dice_sides_minimum_level,
+            saving_throw_type: CharArray(value.saving_throw_type.to_le_bytes()),
+            saving_throw_bonus: value.saving_throw_bonus,
+            stacking_id: value._unknown,
+        }
+    }
+}
+
+// The V2 only fields are dropped, values too wide for the V1 fields are an error
+impl TryFrom<&EffectV2BodyWithOutHeader> for EffectV1 {
+    type Error = Box<dyn Error>;
+
+    fn try_from(value: &EffectV2BodyWithOutHeader) -> Result<Self, Self::Error> {
+        Ok(Self {
+            effect_type: narrow(value.opcode_number, "Opcode")?,
+            target_type: narrow(value.target_type, "Target type")?,
+            power: narrow(value.power, "Power")?,
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: narrow(value.timing_mode, "Timing mode")?,
+            dispel_resistance: narrow(value.dispel_resistance, "Dispel resistance")?,
+            duration: value.duration,
+            probability_1: narrow(value.probability_1, "Probability 1")?,
+            probability_2: narrow(value.probability_2, "Probability 2")?,
+            resref_key: CharArray(value.resource_1.0),
+            dice_thrown_maximum_level: value.dice_thrown,
+            dice_sides_minimum_level: value.dice_sides,
+            saving_throw_type: value.saving_throw_type,
+            saving_throw_bonus: value.saving_throw_bonus,
+            _unknown: value.special,
+        })
+    }
+}
+
+impl TryFrom<&EffectV2BodyWithOutHeader> for FeatureBlock {
+    type Error = Box<dyn Error>;
+
+    fn try_from(value: &EffectV2BodyWithOutHeader) -> Result<Self, Self::Error> {
+        Ok(FeatureBlock::from(&EffectV1::try_from(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::effect_v2::EffectV2;
+    use binrw::io::Read;
+    use pretty_assertions::assert_eq;
+    use std::fs::File;
+
+    fn read_file(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    // Spells store their effects as V1, this is gate's, applying the balor summoning eff
+    fn buffer() -> Result<Vec<u8>, Box<dyn Error>> {
+        let spell = read_file("fixtures/gate1.spl")?;
+        let offset = u32::from_le_bytes(spell[0x6a..0x6e].try_into()?) as usize;
+        Ok(spell[offset..offset + 0x30].to_vec())
+    }
+
+    #[test]
+    fn standalone() -> Result<(), Box<dyn Error>> {
+        let buffer = buffer()?;
+        assert!(is_effect_v1(&buffer));
+        let effect = EffectV1::new(&buffer);
+        assert_eq!(effect.effect_type, 177);
+        assert_eq!(effect.duration, 100000);
+        assert_eq!(effect.resref_key.to_string(), "balorsu\0");
+        assert_eq!(effect.to_bytes(), buffer);
+        Ok(())
+    }
+
+    #[test]
+    fn conversions() -> Result<(), Box<dyn Error>> {
+        let mut effect = EffectV1::new(&buffer()?);
+        effect._unknown = 7;
+        let feature_block = FeatureBlock::from(&effect);
+        assert_eq!(feature_block.stacking_id, 7);
+        assert_eq!(EffectV1::from(&feature_block), effect);
+
+        let v2 = EffectV2BodyWithOutHeader::from(&effect);
+        assert_eq!(v2.opcode_number, 177);
+        assert_eq!(v2.probability_1, 39);
+        assert_eq!(v2.special, 7);
+        assert_eq!(EffectV1::try_from(&v2)?, effect);
+        assert_eq!(FeatureBlock::try_from(&v2)?, feature_block);
+
+        let file = EffectV2::from(v2);
+        assert!(!is_effect_v1(&file.to_bytes()));
+        assert_eq!(EffectV2::new(&file.to_bytes()), file);
+
+        let mut wide = EffectV2BodyWithOutHeader::from(&effect);
+        wide.opcode_number = 0x10000;
+        assert!(EffectV1::try_from(&wide).is_err());
+        Ok(())
+    }
+}
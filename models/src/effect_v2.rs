@@ -3,9 +3,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::common::Resref;
 use crate::common::char_array::CharArray;
+use crate::common::feature_block::FeatureBlock;
 use crate::common::header::Header;
+use crate::effect_v1::EffectV1;
 use crate::model::Model;
 
+// "EFF "
+const SIGNATURE: CharArray<4> = CharArray([69, 70, 70, 32]);
+// "V2.0"
+const VERSION: CharArray<4> = CharArray([86, 50, 46, 48]);
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/eff_v2.htm
 #[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct EffectV2 {
@@ -94,11 +101,101 @@ pub struct EffectV2BodyWithOutHeader {
     pub first_apply: u32,
     // https://gibberlings3.github.io/iesdp/files/2da/2da_bgee/msectype.htm
     pub secondary_type: u32,
-    #[serde(skip)]
+    #[serde(skip, default = "unknown_2")]
     #[br(count = 15)]
     _unknown_2: Vec<u32>,
 }
 
+fn unknown_2() -> Vec<u32> {
+    vec![0; 15]
+}
+
+fn header() -> Header {
+    Header {
+        signature: SIGNATURE,
+        version: VERSION,
+    }
+}
+
+impl From<EffectV2Body> for EffectV2 {
+    fn from(value: EffectV2Body) -> Self {
+        Self {
+            header: header(),
+            effect: value,
+        }
+    }
+}
+
+impl From<EffectV2BodyWithOutHeader> for EffectV2Body {
+    fn from(value: EffectV2BodyWithOutHeader) -> Self {
+        Self {
+            header: header(),
+            body: value,
+        }
+    }
+}
+
+impl From<EffectV2BodyWithOutHeader> for EffectV2 {
+    fn from(value: EffectV2BodyWithOutHeader) -> Self {
+        EffectV2Body::from(value).into()
+    }
+}
+
+// Widens the V1 fields, the V2 only fields are left empty
+impl From<&EffectV1> for EffectV2BodyWithOutHeader {
+    fn from(value: &EffectV1) -> Self {
+        Self {
+            opcode_number: value.effect_type.into(),
+            target_type: value.target_type.into(),
+            power: value.power.into(),
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode.into(),
+            timing: 0,
+            duration: value.duration,
+            probability_1: value.probability_1.into(),
+            probability_2: value.probability_2.into(),
+            resource_1: CharArray(value.resref_key.0),
+            dice_thrown: value.dice_thrown_maximum_level,
+            dice_sides: value.dice_sides_minimum_level,
+            saving_throw_type: value.saving_throw_type,
+            saving_throw_bonus: value.saving_throw_bonus,
+            special: value._unknown,
+            primary_spell_school: 0,
+            _unknown_1: 0,
+            parent_resource_lowest_affected_level: 0,
+            parent_resource_highest_affected_level: 0,
+            dispel_resistance: value.dispel_resistance.into(),
+            parameter_3: 0,
+            parameter_4: 0,
+            parameter_5: 0,
+            time_applied_ticks: 0,
+            resource_2: CharArray([0; 8]),
+            resource_3: CharArray([0; 8]),
+            caster_x_coordinate: 0,
+            caster_y_coordinate: 0,
+            target_x_coordinate: 0,
+            target_y_coordinate: 0,
+            parent_resource_type: 0,
+            parent_resource: CharArray([0; 8]),
+            parent_resource_flags: vec![0; 4],
+            projectile: 0,
+            parent_resource_slot: 0,
+            variable_name: CharArray([0; 32]),
+            caster_level: 0,
+            first_apply: 0,
+            secondary_type: 0,
+            _unknown_2: vec![0; 15],
+        }
+    }
+}
+
+impl From<&FeatureBlock> for EffectV2BodyWithOutHeader {
+    fn from(value: &FeatureBlock) -> Self {
+        Self::from(&EffectV1::from(value))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
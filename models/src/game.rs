@@ -1,92 +1,188 @@
 use binrw::{
-    BinRead, BinReaderExt, BinResult, BinWrite,
+    BinRead, BinResult, BinWrite,
     helpers::until_eof,
-    io::{Cursor, Read, Seek, SeekFrom},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
 };
 use serde::{Deserialize, Serialize};
 
-use crate::common::{char_array::CharArray, header::Header};
+use crate::common::{char_array::CharArray, header::Header, sections::Sections};
 use crate::model::Model;
 use crate::{
     common::{Resref, strref::Strref},
     creature::Creature,
 };
 
+const HEADER_SIZE: u32 = 0xb4;
+const NPC_SIZE: u32 = 0x160;
+const GLOBAL_VARIABLE_SIZE: u32 = 0x54;
+const JOURNAL_ENTRY_SIZE: u32 = 0x0c;
+const FAMILIAR_SIZE: u32 = 0x190;
+const LOCATION_SIZE: u32 = 0x0c;
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/gam_v2.0.htm
-#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, Serialize, Deserialize)]
 pub struct Game {
     #[serde(skip)]
     #[br(parse_with = until_eof, restore_position)]
     pub original_bytes: Vec<u8>,
-    #[bw(ignore)]
     #[serde(flatten)]
     pub header: BGEEGameHeader,
-    #[bw(ignore)]
-    #[br(count=header.count_of_npc_structs_for_party_members)]
+    #[br(count=header.count_of_npc_structs_for_party_members, seek_before=SeekFrom::Start(header.offset_to_npc_structs_for_party_members as u64))]
     pub party_npcs: Vec<GameNPC>,
-    #[bw(ignore)]
-    #[br(parse_with = |reader, _, _:()| parse_creatures(reader, &party_npcs))]
-    pub party_npcs_cres: Vec<Creature>,
-    #[bw(ignore)]
-    #[br(count=header.count_of_npc_structs_for_npcs)]
+    #[br(count=header.count_of_npc_structs_for_npcs, seek_before=SeekFrom::Start(header.offset_to_npc_structs_for_npcs as u64))]
     pub non_party_npcs: Vec<GameNPC>,
-    #[bw(ignore)]
-    #[br(parse_with = |reader, _, _:()| parse_creatures(reader, &non_party_npcs))]
-    pub non_party_npcs_cres: Vec<Creature>,
-    #[bw(ignore)]
-    #[br(count=header.count_of_global_namespace_variables)]
+    #[br(count=header.count_of_global_namespace_variables, seek_before=SeekFrom::Start(header.offset_to_global_namespace_variables as u64))]
     pub global_variables: Vec<GlobalVariables>,
-    #[bw(ignore)]
     #[br(if(header.offset_to_journal_entries != u32::MAX), count=header.count_of_journal_entries, seek_before=SeekFrom::Start(header.offset_to_journal_entries as u64))]
     pub journal_entries: Vec<JournalEntries>,
-    #[bw(ignore)]
     #[br(seek_before=SeekFrom::Start(header.offset_to_familiar as u64))]
     pub familiar: Option<Familiar>,
-    #[bw(ignore)]
     #[br(count=header.count_of_stored_locations, seek_before=SeekFrom::Start(header.offset_to_stored_locations as u64))]
     pub stored_locations: Vec<Location>,
-    #[bw(ignore)]
     #[br(count=header.count_of_pocket_plane_locations, seek_before=SeekFrom::Start(header.offset_to_pocket_plane_locations as u64))]
     pub pocket_plane_locations: Vec<Location>,
-    #[bw(ignore)]
     #[br(if(header.offset_to_familiar_extra != u32::MAX), parse_with=binrw::helpers::until_eof, seek_before=SeekFrom::Start(header.offset_to_familiar_extra as u64))]
     pub familiar_extra: Vec<FamiliarExtra>,
 }
 
-fn parse_creatures<R: Read + Seek>(
+// NPCs without a cre resource (size 0) have no creature
+fn parse_creature<R: Read + Seek>(
     reader: &mut R,
-    npcs: &Vec<GameNPC>,
-) -> BinResult<Vec<Creature>> {
-    let mut buff = vec![];
-
-    let mut creatures = Vec::with_capacity(npcs.len());
-    for npc in npcs {
-        let end = npc.size_of_cre_resource as u64;
-        if end == 0 {
-            continue;
-        }
-        let mut handler = reader.take(end);
-        handler.read_to_end(&mut buff).unwrap();
-        creatures.push(Creature::new(&buff));
+    offset: u32,
+    size: u32,
+) -> BinResult<Option<Creature>> {
+    if size == 0 {
+        return Ok(None);
     }
-    Ok(creatures)
+    let mut buff = vec![0; size as usize];
+    reader.seek(SeekFrom::Start(offset as u64))?;
+    reader.read_exact(&mut buff)?;
+    Ok(Some(Creature::new(&buff)))
 }
 
 impl Model for Game {
     fn new(buffer: &[u8]) -> Self {
         let mut reader = Cursor::new(buffer);
-        reader.read_le().unwrap()
+        match Game::read_le(&mut reader) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
     }
 
+    // Each group of npcs is followed by its creatures, the party first then everyone
+    // else, with the rest of the sections after them in header order
     fn to_bytes(&self) -> Vec<u8> {
+        let party_npcs_cres = creatures(&self.party_npcs);
+        let non_party_npcs_cres = creatures(&self.non_party_npcs);
+
+        let mut sections = Sections::starting_at(HEADER_SIZE);
+        let party_npcs = sections.place(self.party_npcs.len(), NPC_SIZE);
+        let party_npcs_cres_offset = sections.place_bytes(size_of_creatures(&party_npcs_cres));
+        let non_party_npcs = sections.place(self.non_party_npcs.len(), NPC_SIZE);
+        let non_party_npcs_cres_offset =
+            sections.place_bytes(size_of_creatures(&non_party_npcs_cres));
+        let global_variables = sections.place(self.global_variables.len(), GLOBAL_VARIABLE_SIZE);
+        let journal_entries = sections.place(self.journal_entries.len(), JOURNAL_ENTRY_SIZE);
+        let familiar = sections.place_bytes(self.familiar.as_ref().map_or(0, |_| FAMILIAR_SIZE));
+        let stored_locations = sections.place(self.stored_locations.len(), LOCATION_SIZE);
+        let pocket_plane_locations =
+            sections.place(self.pocket_plane_locations.len(), LOCATION_SIZE);
+        let familiar_extra = match self.familiar_extra.is_empty() {
+            true => u32::MAX,
+            false => sections.place_bytes(0),
+        };
+        let layout = GameLayout {
+            party_npcs,
+            non_party_npcs,
+            global_variables,
+            journal_entries,
+            familiar: self.familiar.as_ref().map_or(0, |_| familiar),
+            stored_locations,
+            pocket_plane_locations,
+            familiar_extra,
+        };
+
         let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
+        self.header.write_le_args(&mut writer, (layout,)).unwrap();
+        write_npcs(
+            &mut writer,
+            &self.party_npcs,
+            &party_npcs_cres,
+            party_npcs_cres_offset,
+        );
+        write_npcs(
+            &mut writer,
+            &self.non_party_npcs,
+            &non_party_npcs_cres,
+            non_party_npcs_cres_offset,
+        );
+        self.global_variables.write_le(&mut writer).unwrap();
+        self.journal_entries.write_le(&mut writer).unwrap();
+        self.familiar.write_le(&mut writer).unwrap();
+        self.stored_locations.write_le(&mut writer).unwrap();
+        self.pocket_plane_locations.write_le(&mut writer).unwrap();
+        self.familiar_extra.write_le(&mut writer).unwrap();
         writer.into_inner()
     }
 }
 
+impl Game {
+    pub fn party_member(&self, index: usize) -> Option<(&GameNPC, &Creature)> {
+        let npc = self.party_npcs.get(index)?;
+        Some((npc, npc.creature.as_ref()?))
+    }
+}
+
+// Each npc's cre, empty for the npcs without one
+fn creatures(npcs: &[GameNPC]) -> Vec<Vec<u8>> {
+    npcs.iter()
+        .map(|npc| npc.creature.as_ref().map_or(vec![], Creature::to_bytes))
+        .collect()
+}
+
+fn size_of_creatures(creatures: &[Vec<u8>]) -> u32 {
+    creatures.iter().map(|creature| creature.len() as u32).sum()
+}
+
+// Writes the npcs then their creatures, pointing each npc at its own
+fn write_npcs(
+    writer: &mut Cursor<Vec<u8>>,
+    npcs: &[GameNPC],
+    creatures: &[Vec<u8>],
+    creatures_offset: u32,
+) {
+    let mut sections = Sections::starting_at(creatures_offset);
+    for (npc, creature) in npcs.iter().zip(creatures) {
+        let cre = match creature.len() as u32 {
+            0 => (0, 0),
+            size => (sections.place_bytes(size), size),
+        };
+        npc.write_le_args(writer, cre).unwrap();
+    }
+    for creature in creatures {
+        writer.write_all(creature).unwrap();
+    }
+}
+
+// The header's offsets and counts, the familiar sections have no count and a missing
+// familiar extra is u32::MAX rather than 0
+#[derive(Debug, Clone, Copy)]
+pub struct GameLayout {
+    party_npcs: (u32, u32),
+    non_party_npcs: (u32, u32),
+    global_variables: (u32, u32),
+    journal_entries: (u32, u32),
+    familiar: u32,
+    stored_locations: (u32, u32),
+    pocket_plane_locations: (u32, u32),
+    familiar_extra: u32,
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/gam_v2.0.htm#GAMEV2_0_Header
 #[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(layout: GameLayout))]
 pub struct BGEEGameHeader {
     #[serde(flatten)]
     pub header: Header,
@@ -114,17 +210,26 @@ pub struct BGEEGameHeader {
     */
     #[br(count = 2)]
     pub weather_bitfield: Vec<u8>,
+    #[bw(map = |_: &u32| layout.party_npcs.0)]
     pub offset_to_npc_structs_for_party_members: u32,
+    #[bw(map = |_: &u32| layout.party_npcs.1)]
     pub count_of_npc_structs_for_party_members: u32,
     pub offset_to_party_inventory: u32,
     pub count_of_party_inventory: u32,
+    #[bw(map = |_: &u32| layout.non_party_npcs.0)]
     pub offset_to_npc_structs_for_npcs: u32,
+    #[bw(map = |_: &u32| layout.non_party_npcs.1)]
     pub count_of_npc_structs_for_npcs: u32,
+    #[bw(map = |_: &u32| layout.global_variables.0)]
     pub offset_to_global_namespace_variables: u32,
+    #[bw(map = |_: &u32| layout.global_variables.1)]
     pub count_of_global_namespace_variables: u32,
     pub main_area: Resref,
+    #[bw(map = |_: &u32| layout.familiar_extra)]
     pub offset_to_familiar_extra: u32,
+    #[bw(map = |_: &u32| layout.journal_entries.1)]
     pub count_of_journal_entries: u32,
+    #[bw(map = |_: &u32| layout.journal_entries.0)]
     pub offset_to_journal_entries: u32,
     pub party_reputation: u32,
     pub current_area: Resref,
@@ -132,11 +237,16 @@ pub struct BGEEGameHeader {
     pub gui_flags: Vec<u8>,
     #[br(count = 4)]
     pub loading_progress: Vec<u8>,
+    #[bw(map = |_: &u32| layout.familiar)]
     pub offset_to_familiar: u32,
+    #[bw(map = |_: &u32| layout.stored_locations.0)]
     pub offset_to_stored_locations: u32,
+    #[bw(map = |_: &u32| layout.stored_locations.1)]
     pub count_of_stored_locations: u32,
     pub game_time_real_seconds: u32,
+    #[bw(map = |_: &u32| layout.pocket_plane_locations.0)]
     pub offset_to_pocket_plane_locations: u32,
+    #[bw(map = |_: &u32| layout.pocket_plane_locations.1)]
     pub count_of_pocket_plane_locations: u32,
     // EE fields
     pub zoom_level: u32,
@@ -149,11 +259,14 @@ pub struct BGEEGameHeader {
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/gam_v2.0.htm#GAMEV2_0_NPC
 #[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(cre_offset: u32, cre_size: u32))]
 pub struct GameNPC {
     pub character_selection: u16,
     // x0-0x5 = player_x_fill, 0x_ffff = not in party
     pub party_order: u16,
+    #[bw(map = |_: &u32| cre_offset)]
     pub offset_to_cre_resource: u32,
+    #[bw(map = |_: &u32| cre_size)]
     pub size_of_cre_resource: u32,
     pub character_name: CharArray<8>,
     pub character_orientation: u32,
@@ -204,6 +317,11 @@ pub struct GameNPC {
     // filename prefix for voice set
     #[br(count = 8)]
     pub voice_set: Vec<u8>,
+    // Stored after the npcs, the offset and size above are worked out from it when written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[bw(ignore)]
+    #[br(restore_position, parse_with = |reader, _, _:()| parse_creature(reader, offset_to_cre_resource, size_of_cre_resource))]
+    pub creature: Option<Creature>,
 }
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/gam_v2.0.htm#GAMEV2_0_Stats
@@ -415,4 +533,56 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn write() -> Result<(), Box<dyn Error>> {
+        let buffer = read_file("fixtures/bg2eebaldur.gam")?;
+        let game = Game::new(&buffer);
+        assert_eq!(game.to_bytes(), buffer);
+
+        let from_json: Game = serde_json::from_value(serde_json::to_value(&game)?)?;
+        assert_eq!(from_json.to_bytes(), buffer);
+        Ok(())
+    }
+
+    #[test]
+    fn edit_party() -> Result<(), Box<dyn Error>> {
+        let mut game = Game::new(&read_file("fixtures/bg2eebaldur.gam")?);
+        game.header.party_gold = 500_000;
+        game.header.party_reputation = 200;
+        // Drop the last party member and the first journal entry
+        let removed = game.party_npcs.pop().unwrap().creature.unwrap();
+        game.journal_entries.remove(0);
+        // The creature is written whatever size the npc claims
+        game.party_npcs[0].size_of_cre_resource = 0;
+
+        let written = Game::new(&game.to_bytes());
+        assert_eq!(written.header.party_gold, 500_000);
+        assert_eq!(written.header.party_reputation, 200);
+        assert_eq!(written.party_npcs.len(), 5);
+        assert_eq!(written.journal_entries, game.journal_entries);
+        assert_eq!(written.global_variables, game.global_variables);
+        assert_eq!(written.familiar, game.familiar);
+        assert_eq!(
+            written.non_party_npcs[0].offset_to_cre_resource,
+            game.non_party_npcs[0].offset_to_cre_resource - 0x160 - removed.to_bytes().len() as u32
+        );
+        let creature = game.party_npcs[0].creature.as_ref().unwrap();
+        assert_eq!(
+            written.party_npcs[0].size_of_cre_resource,
+            creature.to_bytes().len() as u32
+        );
+        for (written, npc) in written.non_party_npcs.iter().zip(&game.non_party_npcs) {
+            let (written, creature) = (
+                written.creature.as_ref().unwrap(),
+                npc.creature.as_ref().unwrap(),
+            );
+            assert_eq!(
+                written.header.long_creature_name,
+                creature.header.long_creature_name
+            );
+            assert_eq!(written.item_table, creature.item_table);
+        }
+        Ok(())
+    }
 }
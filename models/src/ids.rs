@@ -1,28 +1,247 @@
-use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use std::{collections::HashMap, error::Error, fs, path::Path};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::parsers::{read_to_end, write_string},
+    common::{
+        encryption::{decrypt, encrypt, is_encrypted},
+        types::ResourceType,
+    },
+    key::Key,
     model::Model,
 };
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/ids.htm
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Ids {
-    #[bw(write_with = write_string)]
-    #[br(parse_with = read_to_end)]
-    pub data: String,
+    #[serde(flatten)]
+    pub header: IdsHeader,
+    pub entries: Vec<IdsEntry>,
+    // Written back encrypted, as some original games shipped them
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+// Both lines are optional, the engine ignores them
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdsHeader {
+    // eg. "IDS V1.0"
+    pub signature: Option<String>,
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IdsEntry {
+    pub value: i64,
+    pub symbol: String,
+    // Written back with a 0x prefix, as trigger.ids and most flag tables are
+    #[serde(default)]
+    pub hexadecimal: bool,
 }
 
 impl Model for Ids {
     fn new(buffer: &[u8]) -> Self {
-        let mut reader = Cursor::new(buffer);
-        reader.read_le().unwrap()
+        let mut ids = Ids::parse(&String::from_utf8_lossy(&decrypt(buffer)));
+        ids.encrypted = is_encrypted(buffer);
+        ids
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
-        writer.into_inner()
+        let mut out = String::new();
+        if let Some(signature) = &self.header.signature {
+            out.push_str(&format!("{signature}\r\n"));
+        }
+        if let Some(count) = self.header.count {
+            out.push_str(&format!("{count}\r\n"));
+        }
+        for entry in &self.entries {
+            let value = match (entry.hexadecimal, entry.value < 0) {
+                (true, true) => format!("-{:#X}", -entry.value),
+                (true, false) => format!("{:#X}", entry.value),
+                (false, _) => entry.value.to_string(),
+            };
+            out.push_str(&format!("{value} {}\r\n", entry.symbol));
+        }
+        if self.encrypted {
+            return encrypt(out.as_bytes());
+        }
+        out.into_bytes()
+    }
+}
+
+impl Ids {
+    // Lines that don't start with a value are skipped, as the engine does
+    pub fn parse(text: &str) -> Self {
+        let mut header = IdsHeader::default();
+        let mut entries = vec![];
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if entries.is_empty() && header.count.is_none() {
+                if header.signature.is_none() && line.to_ascii_uppercase().starts_with("IDS") {
+                    header.signature = Some(line.to_string());
+                    continue;
+                }
+                // A lone number before any entries is the entry count
+                if !line.contains(char::is_whitespace) {
+                    header.count = line.parse().ok();
+                    continue;
+                }
+            }
+            let Some((value, symbol)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some(parsed) = parse_value(value) else {
+                continue;
+            };
+            entries.push(IdsEntry {
+                value: parsed,
+                symbol: symbol.trim().to_string(),
+                hexadecimal: value
+                    .trim_start_matches('-')
+                    .to_ascii_lowercase()
+                    .starts_with("0x"),
+            });
+        }
+        Ids {
+            header,
+            entries,
+            encrypted: false,
+        }
+    }
+
+    // The first symbol for a value, as used by the engine
+    pub fn by_value(&self, value: i64) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.value == value)
+            .map(|entry| entry.symbol.as_str())
+    }
+
+    // Symbols are matched case insensitively
+    pub fn by_name(&self, symbol: &str) -> Option<i64> {
+        self.entries
+            .iter()
+            .find(|entry| entry.symbol.eq_ignore_ascii_case(symbol))
+            .map(|entry| entry.value)
+    }
+}
+
+// Values are either decimal or hexadecimal with a 0x prefix
+pub fn parse_value(value: &str) -> Option<i64> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let value = match value.get(..2) {
+        Some("0x" | "0X") => i64::from_str_radix(&value[2..], 16).ok()?,
+        _ => value.parse::<i64>().ok()?,
+    };
+    Some(if negative { -value } else { value })
+}
+
+// Every ids file of a game, keyed by upper case name without the extension
+#[derive(Debug, Default)]
+pub struct IdsRegistry {
+    tables: HashMap<String, Ids>,
+}
+
+impl IdsRegistry {
+    // Loads every ids file the game knows about, overridden ones take priority
+    pub fn from_game(game_directory: &Path) -> Result<Self, Box<dyn Error>> {
+        let key = Key::new(&fs::read(game_directory.join("chitin.key"))?);
+        let mut registry = IdsRegistry::default();
+        let mut open_biffs = HashMap::new();
+        for name in key.resource_names(ResourceType::FileTypeIds) {
+            let buffer = key.read_resource_with(
+                game_directory,
+                &name,
+                ResourceType::FileTypeIds,
+                &mut open_biffs,
+            )?;
+            registry.insert(&name, Ids::new(&buffer));
+        }
+        // Mods can add ids files the key doesn't list
+        if let Ok(directory) = fs::read_dir(game_directory.join("override")) {
+            for path in directory.flatten().map(|entry| entry.path()) {
+                let is_ids = path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("ids"));
+                let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                if is_ids && registry.get(name).is_none() {
+                    registry.insert(name, Ids::new(&fs::read(&path)?));
+                }
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn insert(&mut self, name: &str, ids: Ids) {
+        self.tables.insert(name.to_ascii_uppercase(), ids);
+    }
+
+    pub fn get(&self, table: &str) -> Option<&Ids> {
+        self.tables.get(&table.to_ascii_uppercase())
+    }
+
+    // eg. symbol("RACE", 2) is "ELF"
+    pub fn symbol(&self, table: &str, value: i64) -> Option<&str> {
+        self.get(table)?.by_value(value)
+    }
+
+    pub fn value(&self, table: &str, symbol: &str) -> Option<i64> {
+        self.get(table)?.by_name(symbol)
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, Ids)> for IdsRegistry {
+    fn from_iter<T: IntoIterator<Item = (S, Ids)>>(iter: T) -> Self {
+        let mut registry = IdsRegistry::default();
+        for (name, ids) in iter {
+            registry.insert(name.as_ref(), ids);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse() {
+        let ids = Ids::new(
+            b"IDS V1.0\r\n3\r\n0x4017 See(O:Object*)\r\n-1 NONE\r\n\r\n2 ELF\r\n2 Elven\r\n",
+        );
+        assert_eq!(
+            ids.header,
+            IdsHeader {
+                signature: Some("IDS V1.0".to_string()),
+                count: Some(3),
+            }
+        );
+        assert_eq!(ids.entries.len(), 4);
+        assert_eq!(ids.by_value(0x4017), Some("See(O:Object*)"));
+        assert_eq!(ids.by_value(2), Some("ELF"));
+        assert_eq!(ids.by_name("elven"), Some(2));
+        assert_eq!(ids.by_name("none"), Some(-1));
+        assert_eq!(ids.by_name("DWARF"), None);
+        assert_eq!(Ids::new(&ids.to_bytes()), ids);
+
+        // Neither header line is required
+        let ids = Ids::new(b"1 HUMAN\n2 ELF");
+        assert_eq!(ids.header, IdsHeader::default());
+        assert_eq!(ids.to_bytes(), b"1 HUMAN\r\n2 ELF\r\n");
+
+        let registry: IdsRegistry = [("race", ids)].into_iter().collect();
+        assert_eq!(registry.symbol("RACE", 2), Some("ELF"));
+        assert_eq!(registry.value("Race", "human"), Some(1));
+        assert_eq!(registry.symbol("CLASS", 2), None);
+
+        let ids = Ids::new(&encrypt(b"0 NONE\r\n1 HUMAN\r\n"));
+        assert!(ids.encrypted);
+        assert_eq!(ids.by_name("HUMAN"), Some(1));
+        assert_eq!(decrypt(&ids.to_bytes()), b"0 NONE\r\n1 HUMAN\r\n");
     }
 }
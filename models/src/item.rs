@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 use crate::common::char_array::CharArray;
 use crate::common::feature_block::FeatureBlock;
 use crate::common::header::Header;
+use crate::item_types::{
+    ItemFlags, KitUsability1, KitUsability2, KitUsability3, KitUsability4, Usability,
+};
 use crate::model::Model;
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/itm_v1.htm
@@ -49,20 +52,20 @@ pub struct ItemHeader {
     identified_item_name: u32,
     replacement_item: CharArray<8>,
     // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/itm_v1.htm#Header_Flags
-    type_flags: u32,
+    type_flags: ItemFlags,
     category: u16,
-    usability: u32,
+    usability: Usability,
     item_animation: CharArray<2>,
     min_level: u16,
     min_strength: u16,
     min_strength_bonus: u8,
-    kit_usability_1: u8,
+    kit_usability_1: KitUsability1,
     min_intelligence: u8,
-    kit_usability_2: u8,
+    kit_usability_2: KitUsability2,
     min_dexterity: u8,
-    kit_usability_3: u8,
+    kit_usability_3: KitUsability3,
     min_wisdom: u8,
-    kit_usability_4: u8,
+    kit_usability_4: KitUsability4,
     min_constitution: u8,
     weapon_proficiency: u8,
     min_charisma: u16,
@@ -154,4 +157,31 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn flags_round_trip() -> Result<(), Box<dyn Error>> {
+        let item = Item::new(&read_file("fixtures/sw1h01.itm")?);
+        assert!(item.header.usability.contains(Usability::UNUSABLE_BY_MAGE));
+        assert!(
+            !item
+                .header
+                .usability
+                .contains(Usability::UNUSABLE_BY_FIGHTER)
+        );
+        assert_eq!(
+            item.header.kit_usability_2,
+            KitUsability2::UNUSABLE_BY_BEASTMASTER
+        );
+        let header: ItemHeader = serde_json::from_str(&serde_json::to_string(&item.header)?)?;
+        assert_eq!(header, item.header);
+
+        // Plain numbers are still accepted
+        let usability: Usability = serde_json::from_str("262145")?;
+        assert_eq!(
+            usability,
+            Usability::UNUSABLE_BY_MAGE | Usability::UNUSABLE_BY_CHAOTIC
+        );
+        assert!(serde_json::from_str::<KitUsability1>("256").is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,112 @@
+use crate::common::flags::flags;
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/itm_v1.htm#Header_Flags
+    ItemFlags: u32 {
+        CRITICAL_ITEM = 0x1,
+        TWO_HANDED = 0x2,
+        DROPPABLE = 0x4,
+        DISPLAYABLE = 0x8,
+        CURSED = 0x10,
+        NOT_COPYABLE = 0x20,
+        MAGICAL = 0x40,
+        LEFT_HANDED = 0x80,
+        SILVER = 0x100,
+        COLD_IRON = 0x200,
+        OFF_HANDED = 0x400,
+        CONVERSABLE = 0x800,
+        FAKE_TWO_HANDED = 0x1000,
+        FORBID_OFF_HAND_ANIMATION = 0x2000,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/itm_v1.htm#Header_Usability
+    Usability: u32 {
+        UNUSABLE_BY_CHAOTIC = 0x1,
+        UNUSABLE_BY_EVIL = 0x2,
+        UNUSABLE_BY_GOOD = 0x4,
+        UNUSABLE_BY_GOOD_EVIL_NEUTRAL = 0x8,
+        UNUSABLE_BY_LAWFUL = 0x10,
+        UNUSABLE_BY_LAWFUL_CHAOTIC_NEUTRAL = 0x20,
+        UNUSABLE_BY_BARD = 0x40,
+        UNUSABLE_BY_CLERIC = 0x80,
+        UNUSABLE_BY_CLERIC_MAGE = 0x100,
+        UNUSABLE_BY_CLERIC_THIEF = 0x200,
+        UNUSABLE_BY_CLERIC_RANGER = 0x400,
+        UNUSABLE_BY_FIGHTER = 0x800,
+        UNUSABLE_BY_FIGHTER_DRUID = 0x1000,
+        UNUSABLE_BY_FIGHTER_MAGE = 0x2000,
+        UNUSABLE_BY_FIGHTER_CLERIC = 0x4000,
+        UNUSABLE_BY_FIGHTER_MAGE_CLERIC = 0x8000,
+        UNUSABLE_BY_FIGHTER_MAGE_THIEF = 0x10000,
+        UNUSABLE_BY_FIGHTER_THIEF = 0x20000,
+        UNUSABLE_BY_MAGE = 0x40000,
+        UNUSABLE_BY_MAGE_THIEF = 0x80000,
+        UNUSABLE_BY_PALADIN = 0x100000,
+        UNUSABLE_BY_RANGER = 0x200000,
+        UNUSABLE_BY_THIEF = 0x400000,
+        UNUSABLE_BY_ELF = 0x800000,
+        UNUSABLE_BY_DWARF = 0x1000000,
+        UNUSABLE_BY_HALF_ELF = 0x2000000,
+        UNUSABLE_BY_HALFLING = 0x4000000,
+        UNUSABLE_BY_HUMAN = 0x8000000,
+        UNUSABLE_BY_GNOME = 0x10000000,
+        UNUSABLE_BY_MONK = 0x20000000,
+        UNUSABLE_BY_DRUID = 0x40000000,
+        UNUSABLE_BY_HALF_ORC = 0x80000000,
+    }
+}
+
+// The four kit usability bytes are stored apart, each with its own kits
+flags! {
+    KitUsability1: u8 {
+        UNUSABLE_BY_CLERIC_OF_TALOS = 0x1,
+        UNUSABLE_BY_CLERIC_OF_HELM = 0x2,
+        UNUSABLE_BY_CLERIC_OF_LATHANDER = 0x4,
+        UNUSABLE_BY_TOTEMIC_DRUID = 0x8,
+        UNUSABLE_BY_SHAPESHIFTER = 0x10,
+        UNUSABLE_BY_AVENGER = 0x20,
+        UNUSABLE_BY_BARBARIAN = 0x40,
+        UNUSABLE_BY_WILDMAGE = 0x80,
+    }
+}
+
+flags! {
+    KitUsability2: u8 {
+        UNUSABLE_BY_STALKER = 0x1,
+        UNUSABLE_BY_BEASTMASTER = 0x2,
+        UNUSABLE_BY_ASSASSIN = 0x4,
+        UNUSABLE_BY_BOUNTY_HUNTER = 0x8,
+        UNUSABLE_BY_SWASHBUCKLER = 0x10,
+        UNUSABLE_BY_BLADE = 0x20,
+        UNUSABLE_BY_JESTER = 0x40,
+        UNUSABLE_BY_SKALD = 0x80,
+    }
+}
+
+flags! {
+    KitUsability3: u8 {
+        UNUSABLE_BY_DIVINER = 0x1,
+        UNUSABLE_BY_ENCHANTER = 0x2,
+        UNUSABLE_BY_ILLUSIONIST = 0x4,
+        UNUSABLE_BY_INVOKER = 0x8,
+        UNUSABLE_BY_NECROMANCER = 0x10,
+        UNUSABLE_BY_TRANSMUTER = 0x20,
+        UNUSABLE_BY_NO_KIT = 0x40,
+        UNUSABLE_BY_ARCHER = 0x80,
+    }
+}
+
+flags! {
+    KitUsability4: u8 {
+        UNUSABLE_BY_BERSERKER = 0x1,
+        UNUSABLE_BY_WIZARDSLAYER = 0x2,
+        UNUSABLE_BY_KENSAI = 0x4,
+        UNUSABLE_BY_CAVALIER = 0x8,
+        UNUSABLE_BY_INQUISITOR = 0x10,
+        UNUSABLE_BY_UNDEAD_HUNTER = 0x20,
+        UNUSABLE_BY_ABJURER = 0x40,
+        UNUSABLE_BY_CONJURER = 0x80,
+    }
+}
@@ -1,4 +1,8 @@
-use std::{fmt::Debug, path::Path};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
 
 use std::error::Error;
 
@@ -6,8 +10,8 @@ use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    biff::Biff,
-    common::{Resref, header::Header},
+    biff::{Biff, BiffFile},
+    common::{Resref, find_file, header::Header, types::ResourceType},
     model::Model,
 };
 
@@ -75,6 +79,67 @@ impl Key {
         self.biffs = out;
         Ok(())
     }
+
+    // Reads a single resource, preferring the override directory over the biffs
+    pub fn read_resource(
+        &self,
+        game_directory: &Path,
+        name: &str,
+        resource_type: ResourceType,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.read_resource_with(game_directory, name, resource_type, &mut HashMap::new())
+    }
+
+    // As read_resource, keeping the biffs it opens in `open_biffs` so reading many
+    // resources parses each biff's file tables once
+    pub fn read_resource_with(
+        &self,
+        game_directory: &Path,
+        name: &str,
+        resource_type: ResourceType,
+        open_biffs: &mut HashMap<PathBuf, BiffFile>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let file_name = format!("{name}.{}", String::from(resource_type));
+        if let Some(path) = find_file(&game_directory.join("override"), &file_name) {
+            return Ok(std::fs::read(path)?);
+        }
+        let entry = self
+            .resource_entries
+            .iter()
+            .find(|entry| {
+                entry.resource_type == resource_type as u16
+                    && entry
+                        .name
+                        .to_string()
+                        .trim_end_matches('\0')
+                        .eq_ignore_ascii_case(name)
+            })
+            .ok_or_else(|| format!("Could not find {file_name}"))?;
+        let bif_file_name = self
+            .bif_file_names
+            .get((entry.locator >> 20) as usize)
+            .ok_or_else(|| format!("No biff found for {file_name}"))?
+            .replace('\0', "")
+            .replace('\\', "/");
+        let path = game_directory.join(bif_file_name);
+        let biff = match open_biffs.entry(path) {
+            Entry::Occupied(open) => open.into_mut(),
+            Entry::Vacant(slot) => {
+                let biff = BiffFile::open(slot.key())?;
+                slot.insert(biff)
+            }
+        };
+        biff.read_resource(entry.locator)
+    }
+
+    // The names of every resource of a type, in key order
+    pub fn resource_names(&self, resource_type: ResourceType) -> Vec<String> {
+        self.resource_entries
+            .iter()
+            .filter(|entry| entry.resource_type == resource_type as u16)
+            .map(|entry| entry.name.to_string().replace('\0', ""))
+            .collect()
+    }
 }
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/key_v1.htm#keyv1_Header
@@ -1,24 +1,41 @@
 use std::error::Error;
 
 use bam::Bam;
+use bmp::Bmp;
+use chu::Chu;
 use common::types::ResourceType;
 use model::Model;
+use mos::Mos;
+use plt::Plt;
+use projectile::Projectile;
+use pvrz::Pvrz;
+use script::Script;
 use serde_json::Value;
+use sound::Sound;
+use spell_animation::SpellAnimation;
+use src::Src;
 use tileset::Tileset;
+use visual_effect::VisualEffect;
+use wed::Wed;
+use wfx::Wfx;
 
 use crate::{
     area::Area, bio::Biography, character::ExpandedCharacter, creature::Creature,
-    dialogue::Dialogue, effect_v2::EffectV2, game::Game, ids::Ids, item::Item, key::Key,
-    save::Save, spell::Spell, store::Store, twoda::TwoDA, world_map::WorldMap,
+    dialogue::Dialogue, effect_v1::EffectV1, effect_v2::EffectV2, game::Game, ids::Ids, item::Item,
+    key::Key, save::Save, spell::Spell, store::Store, twoda::TwoDA, world_map::WorldMap,
 };
 
+pub mod acm;
 pub mod area;
 pub mod bam;
 pub mod biff;
 pub mod bio;
+pub mod bmp;
 pub mod character;
+pub mod chu;
 pub mod common;
 pub mod creature;
+pub mod creature_types;
 pub mod dialogue;
 pub mod effect_v1;
 pub mod effect_v2;
@@ -26,15 +43,29 @@ pub mod game;
 pub mod ids;
 pub mod item;
 pub mod item_table;
+pub mod item_types;
 pub mod key;
 pub mod model;
+pub mod mos;
+pub mod opcode;
+pub mod plt;
+pub mod projectile;
+pub mod pvrz;
 pub mod save;
+pub mod script;
+pub mod sound;
 pub mod spell;
+pub mod spell_animation;
 pub mod spell_table;
+pub mod spell_types;
+pub mod src;
 pub mod store;
 pub mod tileset;
 pub mod tlk;
 pub mod twoda;
+pub mod visual_effect;
+pub mod wed;
+pub mod wfx;
 pub mod world_map;
 
 const NOT_IMPLIMENTED: &str = "Not implimented yet";
@@ -43,19 +74,33 @@ const NOT_IMPLIMENTED: &str = "Not implimented yet";
 pub enum IEModels {
     Area(Area),
     Biography(Biography),
+    Bmp(Bmp),
+    Chu(Chu),
     Creature(Creature),
     Dialogue(Dialogue),
+    EffectV1(EffectV1),
     EffectV2(EffectV2),
     ExpandedCharacter(ExpandedCharacter),
     Game(Game),
     Ids(Ids),
     Item(Item),
     Key(Key),
+    Mos(Mos),
+    Plt(Plt),
+    Projectile(Projectile),
+    Pvrz(Pvrz),
     Save(Save),
+    Script(Script),
+    Sound(Sound),
     Spell(Spell),
+    SpellAnimation(SpellAnimation),
+    Src(Src),
     Store(Store),
     Tileset(Tileset),
     TwoDA(TwoDA),
+    VisualEffect(VisualEffect),
+    Wed(Wed),
+    Wfx(Wfx),
     WorldMap(WorldMap),
 }
 
@@ -64,19 +109,33 @@ impl IEModels {
         match self {
             IEModels::Area(area) => Ok(area.to_bytes()),
             IEModels::Biography(biography) => Ok(biography.to_bytes()),
+            IEModels::Bmp(bmp) => Ok(bmp.to_bytes()),
+            IEModels::Chu(chu) => Ok(chu.to_bytes()),
             IEModels::Creature(creature) => Ok(creature.to_bytes()),
             IEModels::Dialogue(dialogue) => Ok(dialogue.to_bytes()),
+            IEModels::EffectV1(effect_v1) => Ok(effect_v1.to_bytes()),
             IEModels::EffectV2(effect_v2) => Ok(effect_v2.to_bytes()),
             IEModels::ExpandedCharacter(expanded_character) => Ok(expanded_character.to_bytes()),
             IEModels::Game(game) => Ok(game.to_bytes()),
             IEModels::Ids(ids) => Ok(ids.to_bytes()),
             IEModels::Item(item) => Ok(item.to_bytes()),
             IEModels::Key(key) => Ok(key.to_bytes()),
+            IEModels::Mos(mos) => Ok(mos.to_bytes()),
+            IEModels::Plt(plt) => Ok(plt.to_bytes()),
+            IEModels::Projectile(projectile) => Ok(projectile.to_bytes()),
+            IEModels::Pvrz(pvrz) => Ok(pvrz.to_bytes()),
             IEModels::Save(save) => Ok(save.to_bytes()),
+            IEModels::Script(script) => Ok(script.to_bytes()),
+            IEModels::Sound(sound) => Ok(sound.to_bytes()),
             IEModels::Spell(spell) => Ok(spell.to_bytes()),
+            IEModels::SpellAnimation(spell_animation) => Ok(spell_animation.to_bytes()),
+            IEModels::Src(src) => Ok(src.to_bytes()),
             IEModels::Store(store) => Ok(store.to_bytes()),
             IEModels::Tileset(tileset) => Ok(tileset.to_bytes()),
             IEModels::TwoDA(two_da) => Ok(two_da.to_bytes()),
+            IEModels::VisualEffect(visual_effect) => Ok(visual_effect.to_bytes()),
+            IEModels::Wed(wed) => Ok(wed.to_bytes()),
+            IEModels::Wfx(wfx) => Ok(wfx.to_bytes()),
             IEModels::WorldMap(world_map) => Ok(world_map.to_bytes()),
         }
     }
@@ -84,8 +143,11 @@ impl IEModels {
         Ok(match self {
             IEModels::Area(area) => serde_json::to_value(area),
             IEModels::Biography(biography) => serde_json::to_value(biography),
+            IEModels::Bmp(bmp) => serde_json::to_value(bmp),
+            IEModels::Chu(chu) => serde_json::to_value(chu),
             IEModels::Creature(creature) => serde_json::to_value(creature),
             IEModels::Dialogue(dialogue) => serde_json::to_value(dialogue),
+            IEModels::EffectV1(effect_v1) => serde_json::to_value(effect_v1),
             IEModels::EffectV2(effect_v2) => serde_json::to_value(effect_v2),
             IEModels::ExpandedCharacter(expanded_character) => {
                 serde_json::to_value(expanded_character)
@@ -94,39 +156,49 @@ impl IEModels {
             IEModels::Ids(ids) => serde_json::to_value(ids),
             IEModels::Item(item) => serde_json::to_value(item),
             IEModels::Key(key) => serde_json::to_value(key),
+            IEModels::Mos(mos) => serde_json::to_value(mos),
+            IEModels::Plt(plt) => serde_json::to_value(plt),
+            IEModels::Projectile(projectile) => serde_json::to_value(projectile),
+            IEModels::Pvrz(pvrz) => serde_json::to_value(pvrz),
             IEModels::Save(save) => serde_json::to_value(save),
+            IEModels::Script(script) => serde_json::to_value(script),
+            IEModels::Sound(sound) => serde_json::to_value(sound),
             IEModels::Spell(spell) => serde_json::to_value(spell),
+            IEModels::SpellAnimation(spell_animation) => serde_json::to_value(spell_animation),
+            IEModels::Src(src) => serde_json::to_value(src),
             IEModels::Store(store) => serde_json::to_value(store),
             IEModels::Tileset(tileset) => serde_json::to_value(tileset),
             IEModels::TwoDA(two_da) => serde_json::to_value(two_da),
+            IEModels::VisualEffect(visual_effect) => serde_json::to_value(visual_effect),
+            IEModels::Wed(wed) => serde_json::to_value(wed),
+            IEModels::Wfx(wfx) => serde_json::to_value(wfx),
             IEModels::WorldMap(world_map) => serde_json::to_value(world_map),
         }?)
     }
+
+    // The json view with a description added to every effect
+    pub fn to_annotated_json(&self) -> Result<Value, Box<dyn Error>> {
+        let mut value = self.to_json()?;
+        opcode::annotate(self, &mut value);
+        Ok(value)
+    }
 }
 
 pub fn from_buffer(buffer: &[u8], resource_type: ResourceType) -> Result<IEModels, Box<dyn Error>> {
     match resource_type {
-        // I am skipping image files
-        ResourceType::FileTypeBmp => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeBmp => Ok(IEModels::Bmp(Bmp::new(buffer))),
         ResourceType::FileTypeMve => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping music files
-        ResourceType::FileTypeWav => Err(NOT_IMPLIMENTED.into()),
-        // Skipping play back sounds
-        ResourceType::FileTypeWfx => Err(NOT_IMPLIMENTED.into()),
-        // Skipping
-        ResourceType::FileTypePlt => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeWav => Ok(IEModels::Sound(Sound::parse(buffer)?)),
+        ResourceType::FileTypeWfx => Ok(IEModels::Wfx(Wfx::new(buffer))),
+        ResourceType::FileTypePlt => Ok(IEModels::Plt(Plt::new(buffer))),
         ResourceType::FileTypeBam => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping texture files
-        ResourceType::FileTypeWed => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping GUI defs
-        ResourceType::FileTypeChu => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeWed => Ok(IEModels::Wed(Wed::new(buffer))),
+        ResourceType::FileTypeChu => Ok(IEModels::Chu(Chu::new(buffer))),
         ResourceType::FileTypeTi => Ok(IEModels::Tileset(Tileset::new(buffer))),
-        // I am skipping compress graphic files
-        ResourceType::FileTypeMos => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeMos => Ok(IEModels::Mos(Mos::new(buffer))),
         ResourceType::FileTypeItm => Ok(IEModels::Item(Item::new(buffer))),
         ResourceType::FileTypeSpl => Ok(IEModels::Spell(Spell::new(buffer))),
-        // I am ignoring scripting files
-        ResourceType::FileTypeBcs => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeBcs => Ok(IEModels::Script(Script::parse(buffer)?)),
         ResourceType::FileTypeIds => Ok(IEModels::Ids(Ids::new(buffer))),
         ResourceType::FileTypeCre => Ok(IEModels::Creature(Creature::new(buffer))),
         ResourceType::FileTypeAre => Ok(IEModels::Area(Area::new(buffer))),
@@ -136,24 +208,23 @@ pub fn from_buffer(buffer: &[u8], resource_type: ResourceType) -> Result<IEModel
         ResourceType::FileTypeGam => Ok(IEModels::Game(Game::new(buffer))),
         ResourceType::FileTypeSto => Ok(IEModels::Store(Store::new(buffer))),
         ResourceType::FileTypeWmap => Ok(IEModels::WorldMap(WorldMap::new(buffer))),
+        ResourceType::FileTypeEff if effect_v1::is_effect_v1(buffer) => {
+            Ok(IEModels::EffectV1(EffectV1::new(buffer)))
+        }
         ResourceType::FileTypeEff => Ok(IEModels::EffectV2(EffectV2::new(buffer))),
-        ResourceType::FileTypeBs => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeBs => Ok(IEModels::Script(Script::parse(buffer)?)),
         ResourceType::FileTypeChr => {
             Ok(IEModels::ExpandedCharacter(ExpandedCharacter::new(buffer)))
         }
-        // I am skipping spell casting graphics
-        ResourceType::FileTypeVvc => Err(NOT_IMPLIMENTED.into()),
-        // Skip visual effects
-        ResourceType::FileTypeVef => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping projectiles
-        ResourceType::FileTypePro => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeVvc => Ok(IEModels::SpellAnimation(SpellAnimation::new(buffer))),
+        ResourceType::FileTypeVef => Ok(IEModels::VisualEffect(VisualEffect::new(buffer))),
+        ResourceType::FileTypePro => Ok(IEModels::Projectile(Projectile::new(buffer))),
         ResourceType::FileTypeBio => Ok(IEModels::Biography(Biography::new(buffer))),
         ResourceType::FileTypeWbm => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeFnt => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeGui => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeSql => Err(NOT_IMPLIMENTED.into()),
-        // Skipping graphic data
-        ResourceType::FileTypePvrz => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypePvrz => Ok(IEModels::Pvrz(Pvrz::new(buffer))),
         ResourceType::FileTypeGlsl => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeTlk => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeMenu => Err(NOT_IMPLIMENTED.into()),
@@ -161,8 +232,7 @@ pub fn from_buffer(buffer: &[u8], resource_type: ResourceType) -> Result<IEModel
         ResourceType::FileTypePng => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeBah => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeIni => Err(NOT_IMPLIMENTED.into()),
-        // Skipping sounds/ out of dialog text
-        ResourceType::FileTypeSrc => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeSrc => Ok(IEModels::Src(Src::new(buffer))),
         ResourceType::NotFound => Err(NOT_IMPLIMENTED.into()),
         // Our invented file types:
         ResourceType::FileTypeSave => Ok(IEModels::Save(Save::new(buffer))),
@@ -172,27 +242,24 @@ pub fn from_buffer(buffer: &[u8], resource_type: ResourceType) -> Result<IEModel
 
 pub fn from_json(buffer: &[u8], resource_type: ResourceType) -> Result<Vec<u8>, Box<dyn Error>> {
     match resource_type {
-        // I am skipping image files
+        // Bmp is read only for now
         ResourceType::FileTypeBmp => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeMve => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping music files
+        // Sounds are encoded from a wav, not from json
         ResourceType::FileTypeWav => Err(NOT_IMPLIMENTED.into()),
-        // Skipping play back sounds
-        ResourceType::FileTypeWfx => Err(NOT_IMPLIMENTED.into()),
-        // Skipping
+        ResourceType::FileTypeWfx => Ok(serde_json::from_slice::<Wfx>(buffer)?.to_bytes()),
+        // Plt is read only for now
         ResourceType::FileTypePlt => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeBam => Ok(serde_json::from_slice::<Bam>(buffer)?.to_bytes()),
-        // I am skipping texture files
+        // Wed is read only for now
         ResourceType::FileTypeWed => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping GUI defs
-        ResourceType::FileTypeChu => Err(NOT_IMPLIMENTED.into()),
-        ResourceType::FileTypeTi => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping compress graphic files
+        ResourceType::FileTypeChu => Ok(serde_json::from_slice::<Chu>(buffer)?.to_bytes()),
+        ResourceType::FileTypeTi => Ok(serde_json::from_slice::<Tileset>(buffer)?.to_bytes()),
+        // Mos is read only for now
         ResourceType::FileTypeMos => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeItm => Ok(serde_json::from_slice::<Item>(buffer)?.to_bytes()),
         ResourceType::FileTypeSpl => Ok(serde_json::from_slice::<Spell>(buffer)?.to_bytes()),
-        // I am ignoring scripting files
-        ResourceType::FileTypeBcs => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeBcs => Ok(serde_json::from_slice::<Script>(buffer)?.to_bytes()),
         ResourceType::FileTypeIds => Ok(serde_json::from_slice::<Ids>(buffer)?.to_bytes()),
         ResourceType::FileTypeCre => Ok(serde_json::from_slice::<Creature>(buffer)?.to_bytes()),
         ResourceType::FileTypeAre => Ok(serde_json::from_slice::<Area>(buffer)?.to_bytes()),
@@ -202,17 +269,24 @@ pub fn from_json(buffer: &[u8], resource_type: ResourceType) -> Result<Vec<u8>,
         ResourceType::FileTypeGam => Ok(serde_json::from_slice::<Game>(buffer)?.to_bytes()),
         ResourceType::FileTypeSto => Ok(serde_json::from_slice::<Store>(buffer)?.to_bytes()),
         ResourceType::FileTypeWmap => Ok(serde_json::from_slice::<WorldMap>(buffer)?.to_bytes()),
-        ResourceType::FileTypeEff => Ok(serde_json::from_slice::<EffectV2>(buffer)?.to_bytes()),
-        ResourceType::FileTypeBs => Err(NOT_IMPLIMENTED.into()),
+        // Only V2 effects have a signature
+        ResourceType::FileTypeEff => {
+            let value: Value = serde_json::from_slice(buffer)?;
+            if value.get("signature").is_some() {
+                Ok(serde_json::from_value::<EffectV2>(value)?.to_bytes())
+            } else {
+                Ok(serde_json::from_value::<EffectV1>(value)?.to_bytes())
+            }
+        }
+        ResourceType::FileTypeBs => Ok(serde_json::from_slice::<Script>(buffer)?.to_bytes()),
         ResourceType::FileTypeChr => {
             Ok(serde_json::from_slice::<ExpandedCharacter>(buffer)?.to_bytes())
         }
-        // I am skipping spell casting graphics
-        ResourceType::FileTypeVvc => Err(NOT_IMPLIMENTED.into()),
-        // Skip visual effects
-        ResourceType::FileTypeVef => Err(NOT_IMPLIMENTED.into()),
-        // I am skipping projectiles
-        ResourceType::FileTypePro => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeVvc => {
+            Ok(serde_json::from_slice::<SpellAnimation>(buffer)?.to_bytes())
+        }
+        ResourceType::FileTypeVef => Ok(serde_json::from_slice::<VisualEffect>(buffer)?.to_bytes()),
+        ResourceType::FileTypePro => Ok(serde_json::from_slice::<Projectile>(buffer)?.to_bytes()),
         ResourceType::FileTypeBio => Ok(serde_json::from_slice::<Biography>(buffer)?.to_bytes()),
         ResourceType::FileTypeWbm => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeFnt => Err(NOT_IMPLIMENTED.into()),
@@ -227,8 +301,7 @@ pub fn from_json(buffer: &[u8], resource_type: ResourceType) -> Result<Vec<u8>,
         ResourceType::FileTypePng => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeBah => Err(NOT_IMPLIMENTED.into()),
         ResourceType::FileTypeIni => Err(NOT_IMPLIMENTED.into()),
-        // Skipping sounds/ out of dialog text
-        ResourceType::FileTypeSrc => Err(NOT_IMPLIMENTED.into()),
+        ResourceType::FileTypeSrc => Ok(serde_json::from_slice::<Src>(buffer)?.to_bytes()),
         ResourceType::NotFound => Err(NOT_IMPLIMENTED.into()),
         // Our invented file types:
         ResourceType::FileTypeSave => Ok(serde_json::from_slice::<Save>(buffer)?.to_bytes()),
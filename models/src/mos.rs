@@ -0,0 +1,246 @@
+use std::{collections::HashMap, error::Error};
+
+use binrw::{
+    BinRead, BinWrite,
+    helpers::until_eof,
+    io::{Cursor, Read, SeekFrom},
+};
+use flate2::bufread::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{char_array::CharArray, header::Header, image::Image},
+    model::Model,
+};
+
+// "MOSC"
+const MOSC_SIGNATURE: CharArray<4> = CharArray([77, 79, 83, 67]);
+// "V1  "
+const VERSION1: CharArray<4> = CharArray([86, 49, 32, 32]);
+// "V2  "
+const VERSION2: CharArray<4> = CharArray([86, 50, 32, 32]);
+const PALETTE_SIZE: usize = 1024;
+const MOSC_HEADER_SIZE: usize = 12;
+
+// Compressed mos files are decompressed on read, so they are written back uncompressed
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v1.htm
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v2.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Mos {
+    #[serde(skip)]
+    #[br(parse_with = until_eof, restore_position)]
+    pub original_bytes: Vec<u8>,
+    #[bw(ignore)]
+    #[serde(flatten)]
+    pub header: Header,
+    // If MOS v1
+    #[bw(ignore)]
+    #[br(if(header.version == VERSION1))]
+    pub palette_header: MosV1Header,
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v1.htm#mosv1_Palettes
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(if(header.version == VERSION1))]
+    #[br(count=palette_header.block_count() * PALETTE_SIZE, seek_before=SeekFrom::Start(palette_header.offset_to_palettes as u64))]
+    pub palettes: Vec<u8>,
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v1.htm#mosv1_TileOffsets
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(if(header.version == VERSION1))]
+    #[br(count=palette_header.block_count())]
+    pub tile_offsets: Vec<u32>,
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v1.htm#mosv1_TileData
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(if(header.version == VERSION1))]
+    #[br(parse_with = until_eof)]
+    pub tile_data: Vec<u8>,
+    // If MOS v2
+    #[bw(ignore)]
+    #[br(if(header.version == VERSION2))]
+    pub pvrz_header: MosV2Header,
+    #[bw(ignore)]
+    #[br(if(header.version == VERSION2))]
+    #[br(count=pvrz_header.count_of_data_blocks, seek_before=SeekFrom::Start(pvrz_header.offset_to_data_blocks as u64))]
+    pub data_blocks: Vec<DataBlock>,
+}
+
+impl Model for Mos {
+    fn new(buffer: &[u8]) -> Self {
+        let buffer = if buffer.starts_with(&MOSC_SIGNATURE.0) {
+            let mut out = vec![];
+            if let Err(err) = ZlibDecoder::new(&buffer[MOSC_HEADER_SIZE..]).read_to_end(&mut out) {
+                log::error!("{err}");
+            }
+            out
+        } else {
+            buffer.to_vec()
+        };
+        let mut reader = Cursor::new(&buffer);
+        match Mos::read_le(&mut reader) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Mos {
+    // The pvrz pages referenced by a v2 mos, in ascending order
+    pub fn pvrz_pages(&self) -> Vec<u32> {
+        let mut pages: Vec<u32> = self.data_blocks.iter().map(|block| block.page).collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+
+    // eg. page 12 => MOS0012.PVRZ
+    pub fn pvrz_page_name(page: u32) -> String {
+        format!("MOS{page:04}")
+    }
+
+    pub fn to_image(&self, pvrz_pages: &HashMap<u32, Image>) -> Result<Image, Box<dyn Error>> {
+        if self.header.version == VERSION2 {
+            let mut image = Image::new(self.pvrz_header.width, self.pvrz_header.height);
+            for block in &self.data_blocks {
+                let page = pvrz_pages
+                    .get(&block.page)
+                    .ok_or(format!("Missing pvrz page {}", block.page))?;
+                let part = page.crop(block.source_x, block.source_y, block.width, block.height);
+                image.blit(&part, block.target_x as i64, block.target_y as i64);
+            }
+            return Ok(image);
+        }
+        let header = &self.palette_header;
+        let block_size = header.block_size.max(1);
+        let mut image = Image::new(header.width.into(), header.height.into());
+        for (index, offset) in self.tile_offsets.iter().enumerate() {
+            let column = index as u32 % u32::from(header.columns);
+            let row = index as u32 / u32::from(header.columns);
+            let (x, y) = (column * block_size, row * block_size);
+            let width = block_size.min(u32::from(header.width).saturating_sub(x));
+            let height = block_size.min(u32::from(header.height).saturating_sub(y));
+            let palette = self
+                .palettes
+                .get(index * PALETTE_SIZE..(index + 1) * PALETTE_SIZE)
+                .ok_or(format!("Missing palette for block {index}"))?;
+            let start = *offset as usize;
+            let pixels = self
+                .tile_data
+                .get(start..start + (width * height) as usize)
+                .ok_or(format!("Missing pixels for block {index}"))?;
+            for (i, palette_index) in pixels.iter().enumerate() {
+                let entry = *palette_index as usize * 4;
+                // Pure green is treated as transparent
+                let colour = match &palette[entry..entry + 3] {
+                    [0, 255, 0] => [0, 0, 0, 0],
+                    bgr => [bgr[2], bgr[1], bgr[0], 255],
+                };
+                image.set_pixel(x + i as u32 % width, y + i as u32 / width, colour);
+            }
+        }
+        Ok(image)
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v1.htm#mosv1_Header
+#[derive(Debug, Default, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct MosV1Header {
+    pub width: u16,
+    pub height: u16,
+    pub columns: u16,
+    pub rows: u16,
+    // Always 64
+    pub block_size: u32,
+    pub offset_to_palettes: u32,
+}
+
+impl MosV1Header {
+    fn block_count(&self) -> usize {
+        self.columns as usize * self.rows as usize
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v2.htm#mosv2_Header
+#[derive(Debug, Default, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct MosV2Header {
+    pub width: u32,
+    pub height: u32,
+    pub count_of_data_blocks: u32,
+    pub offset_to_data_blocks: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/mos_v2.htm#mosv2_DataBlock
+#[derive(Debug, Default, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct DataBlock {
+    pub page: u32,
+    pub source_x: u32,
+    pub source_y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub target_x: u32,
+    pub target_y: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // A 70x2 image, split into a 64 wide block and a 6 wide block, built by hand as no game mos
+    // is checked in to fixtures
+    fn palette_mos() -> Vec<u8> {
+        let mut buffer = b"MOS V1  ".to_vec();
+        for value in [70_u16, 2, 2, 1] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend(64_u32.to_le_bytes());
+        buffer.extend(0x18_u32.to_le_bytes());
+        let mut palettes = vec![0_u8; PALETTE_SIZE * 2];
+        // Block 0 index 1 is red, block 1 index 1 is the transparent green
+        palettes[4..8].copy_from_slice(&[0, 0, 255, 0]);
+        palettes[PALETTE_SIZE + 4..PALETTE_SIZE + 8].copy_from_slice(&[0, 255, 0, 0]);
+        buffer.extend(palettes);
+        buffer.extend(0_u32.to_le_bytes());
+        buffer.extend(128_u32.to_le_bytes());
+        buffer.extend(vec![1_u8; 128 + 12]);
+        buffer
+    }
+
+    #[test]
+    fn palette_image() -> Result<(), Box<dyn Error>> {
+        let buffer = palette_mos();
+        let mos = Mos::new(&buffer);
+        assert_eq!(mos.palette_header.width, 70);
+        assert_eq!(mos.tile_offsets, vec![0, 128]);
+        let image = mos.to_image(&HashMap::new())?;
+        assert_eq!((image.width, image.height), (70, 2));
+        assert_eq!(image.pixel(63, 1), Some([255, 0, 0, 255]));
+        assert_eq!(image.pixel(64, 0), Some([0, 0, 0, 0]));
+        assert_eq!(mos.to_bytes(), buffer);
+        Ok(())
+    }
+
+    #[test]
+    fn pvrz_image() -> Result<(), Box<dyn Error>> {
+        let mut buffer = b"MOS V2  ".to_vec();
+        for value in [4_u32, 2, 1, 0x18, 7, 2, 0, 2, 2, 1, 0] {
+            buffer.extend(value.to_le_bytes());
+        }
+        let mos = Mos::new(&buffer);
+        assert_eq!(mos.pvrz_pages(), vec![7]);
+        assert_eq!(Mos::pvrz_page_name(7), "MOS0007");
+        let mut page = Image::new(4, 4);
+        page.set_pixel(3, 1, [1, 2, 3, 255]);
+        let image = mos.to_image(&HashMap::from([(7, page)]))?;
+        assert_eq!(image.pixel(2, 1), Some([1, 2, 3, 255]));
+        Ok(())
+    }
+}
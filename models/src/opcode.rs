@@ -0,0 +1,775 @@
+use serde_json::Value;
+
+use crate::IEModels;
+use crate::common::feature_block::FeatureBlock;
+use crate::creature::Creature;
+use crate::effect_v1::EffectV1;
+use crate::effect_v2::EffectV2BodyWithOutHeader;
+
+// Names of the EE opcodes, indexed by opcode number, empty where unused
+// https://gibberlings3.github.io/iesdp/opcodes/bgee.htm
+const OPCODE_NAMES: [&str; 347] = [
+    "AC bonus",
+    "Modify attacks per round",
+    "Cure sleep",
+    "Berserk",
+    "Cure berserk",
+    "Charm creature",
+    "Charisma bonus",
+    "Set color",
+    "Set color glow solid",
+    "Set color glow pulse",
+    "Constitution bonus",
+    "Cure poison",
+    "Damage",
+    "Kill target",
+    "Defrost",
+    "Dexterity bonus",
+    "Haste",
+    "Current HP bonus",
+    "Maximum HP bonus",
+    "Intelligence bonus",
+    "Invisibility",
+    "Lore bonus",
+    "Luck bonus",
+    "Reset morale",
+    "Panic",
+    "Poison",
+    "Remove curse",
+    "Acid resistance bonus",
+    "Cold resistance bonus",
+    "Electricity resistance bonus",
+    "Fire resistance bonus",
+    "Magic damage resistance bonus",
+    "Raise dead",
+    "Save vs death bonus",
+    "Save vs wand bonus",
+    "Save vs polymorph bonus",
+    "Save vs breath bonus",
+    "Save vs spell bonus",
+    "Silence",
+    "Sleep",
+    "Slow",
+    "Sparkle",
+    "Bonus wizard spells",
+    "Stone to flesh",
+    "Strength bonus",
+    "Stun",
+    "Cure stun",
+    "Remove invisibility",
+    "Vocalize",
+    "Wisdom bonus",
+    "Character color pulse",
+    "Character tint solid",
+    "Character tint bright",
+    "Animation change",
+    "Base THAC0 bonus",
+    "Slay",
+    "Alignment reversal",
+    "Alignment change",
+    "Dispel effects",
+    "Move silently bonus",
+    "Casting failure",
+    "Creature RGB color fade",
+    "Bonus priest spells",
+    "Infravision",
+    "Remove infravision",
+    "Blur",
+    "Translucency",
+    "Summon creature",
+    "Unsummon creature",
+    "Nondetection",
+    "Remove nondetection",
+    "Change gender",
+    "Change AI type",
+    "Attack damage bonus",
+    "Blindness",
+    "Cure blindness",
+    "Feeblemindedness",
+    "Cure feeblemindedness",
+    "Disease",
+    "Cure disease",
+    "Deafness",
+    "Cure deafness",
+    "Set AI script",
+    "Immunity to projectile",
+    "Magical fire resistance bonus",
+    "Magical cold resistance bonus",
+    "Slashing resistance bonus",
+    "Crushing resistance bonus",
+    "Piercing resistance bonus",
+    "Missile resistance bonus",
+    "Open locks bonus",
+    "Find traps bonus",
+    "Pick pockets bonus",
+    "Fatigue bonus",
+    "Intoxication bonus",
+    "Tracking bonus",
+    "Change level",
+    "Exceptional strength bonus",
+    "Regeneration",
+    "Modify duration",
+    "Protection from creature type",
+    "Immunity to effect",
+    "Immunity to spell level",
+    "Change name",
+    "XP bonus",
+    "Remove gold",
+    "Morale break",
+    "Change portrait",
+    "Reputation bonus",
+    "Paralyze",
+    "Retreat from",
+    "Create magical weapon",
+    "Remove item",
+    "Equip weapon",
+    "Dither",
+    "Detect alignment",
+    "Cure invisibility",
+    "Reveal area",
+    "Show creatures",
+    "Mirror image",
+    "Immunity to weapons",
+    "Visual animation effect",
+    "Create inventory item",
+    "Remove inventory item",
+    "Teleport",
+    "Unlock",
+    "Movement rate bonus",
+    "Summon monsters",
+    "Confusion",
+    "Aid (non-cumulative)",
+    "Bless (non-cumulative)",
+    "Chant (non-cumulative)",
+    "Draw upon holy might (non-cumulative)",
+    "Luck (non-cumulative)",
+    "Petrification",
+    "Polymorph",
+    "Force visible",
+    "Bad chant (non-cumulative)",
+    "Set animation sequence",
+    "Display string",
+    "Casting glow",
+    "Lighting effects",
+    "Display portrait icon",
+    "Create item in slot",
+    "Disable button",
+    "Disable spellcasting",
+    "Cast spell",
+    "Learn spell",
+    "Cast spell at point",
+    "Identify",
+    "Find traps",
+    "Replace self",
+    "Play movie",
+    "Sanctuary",
+    "Entangle overlay",
+    "Minor globe overlay",
+    "Protection from normal missiles overlay",
+    "Web effect",
+    "Grease overlay",
+    "Mirror image effect",
+    "Remove sanctuary",
+    "Remove fear",
+    "Remove paralysis",
+    "Free action",
+    "Remove intoxication",
+    "Pause target",
+    "Magic resistance bonus",
+    "Missile THAC0 bonus",
+    "Remove creature",
+    "Prevent portrait icon",
+    "Play damage animation",
+    "Give innate ability",
+    "Remove spell",
+    "Poison resistance bonus",
+    "Play sound",
+    "Hold creature",
+    "Movement rate bonus 2",
+    "Use EFF file",
+    "THAC0 vs type bonus",
+    "Damage vs type bonus",
+    "Disallow item",
+    "Disallow item type",
+    "Apply effect on equip item",
+    "Apply effect on equip type",
+    "No collision detection",
+    "Hold creature 2",
+    "Move creature",
+    "Set local variable",
+    "Increase spells cast per round",
+    "Increase casting speed factor",
+    "Increase attack speed factor",
+    "Casting level bonus",
+    "Find familiar",
+    "Invisible detection",
+    "Ignore dialogue pause",
+    "Drain CON and HP on death",
+    "Disable familiar",
+    "Physical mirror",
+    "Reflect specified effect",
+    "Reflect spell level",
+    "Spell turning",
+    "Spell deflection",
+    "Reflect spell school",
+    "Reflect spell type",
+    "Protection from spell school",
+    "Protection from spell type",
+    "Protection from spell",
+    "Reflect specified spell",
+    "Minimum HP",
+    "Power word, kill",
+    "Power word, stun",
+    "Imprisonment",
+    "Freedom",
+    "Maze",
+    "Select spell",
+    "Play visual effect",
+    "Level drain",
+    "Power word, sleep",
+    "Stoneskin effect",
+    "Attack and saving throw roll penalties",
+    "Remove spell school protections",
+    "Remove spell type protections",
+    "Teleport field",
+    "Spell school deflection",
+    "Restoration",
+    "Detect magic",
+    "Spell type deflection",
+    "Spell school turning",
+    "Spell type turning",
+    "Remove protection by school",
+    "Remove protection by type",
+    "Time stop",
+    "Cast spell on condition",
+    "Modify proficiencies",
+    "Create contingency",
+    "Wing buffet",
+    "Project image",
+    "Set image type",
+    "Disintegrate",
+    "Farsight",
+    "Remove portrait icon",
+    "Control creature",
+    "Cure confusion",
+    "Drain item charges",
+    "Drain wizard spells",
+    "Check for berserk",
+    "Berserk effect",
+    "Attack nearest creature",
+    "Melee hit effect",
+    "Ranged hit effect",
+    "Maximum damage each hit",
+    "Change bard song",
+    "Set trap",
+    "Set automap note",
+    "Remove automap note",
+    "Create item (days)",
+    "Spell sequencer",
+    "Create spell sequencer",
+    "Activate spell sequencer",
+    "Spell trap",
+    "Activate spell sequencer at point",
+    "Restore lost spells",
+    "Visual range bonus",
+    "Backstab bonus",
+    "Drop item",
+    "Set global variable",
+    "Remove protection from spell",
+    "Disable display string",
+    "Clear fog of war",
+    "Shake screen",
+    "Unpause target",
+    "Disable creature",
+    "Use EFF file on condition",
+    "Zone of sweet air",
+    "Teleport to target",
+    "Hide in shadows bonus",
+    "Detect illusions bonus",
+    "Set traps bonus",
+    "THAC0 bonus",
+    "Enable button",
+    "Wild magic",
+    "Wild surge bonus",
+    "Modify script state",
+    "Use EFF file as curse",
+    "Melee THAC0 bonus",
+    "Melee weapon damage bonus",
+    "Missile weapon damage bonus",
+    "Remove feet circle",
+    "Fist THAC0 bonus",
+    "Fist damage bonus",
+    "Change title",
+    "Disable visual effects",
+    "Immunity to backstab",
+    "Set persistent AI",
+    "Set existence delay",
+    "Disable permanent death",
+    "Immunity to specific animation",
+    "Immunity to turn undead",
+    "Pocket plane",
+    "Chaos shield effect",
+    "Modify collision behavior",
+    "Critical hit bonus",
+    "Can use any item",
+    "Backstab every hit",
+    "Mass raise dead",
+    "Off-hand THAC0 bonus",
+    "Main hand THAC0 bonus",
+    "Tracking",
+    "Immunity to tracking",
+    "Modify local variable",
+    "Immunity to time stop",
+    "Wish",
+    "Immunity to sequester",
+    "High-level ability",
+    "Stoneskin protection",
+    "Remove animation",
+    "Rest",
+    "Haste 2",
+    "Protection from resource",
+    "Restrict item",
+    "Change weather",
+    "Remove effects by resource",
+    "",
+    "Turn undead level",
+    "Immunity to resource and message",
+    "All saving throws bonus",
+    "Apply effects list",
+    "Show visual effect",
+    "Set state",
+    "Slow poison",
+    "Float text",
+    "Summon creatures 2",
+    "Specific damage bonus",
+    "Static charge",
+    "Turn undead",
+    "Seven eyes",
+    "Seven eyes overlay",
+    "Remove opcode",
+    "Disable rest or save",
+    "Alter visual animation effect",
+    "Backstab hit effect",
+    "Critical hit effect",
+    "Override creature data",
+    "HP swap",
+    "Enchantment vs creature type",
+    "Enchantment bonus",
+    "Save vs school bonus",
+];
+
+// Opcodes where parameter 1 is the amount and parameter 2 says how it is applied
+const STAT_MODIFIERS: &[u32] = &[
+    6, 10, 15, 17, 18, 19, 21, 22, 27, 28, 29, 30, 31, 33, 34, 35, 36, 37, 44, 49, 54, 59, 73, 84,
+    85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 97, 104, 108, 126, 166, 167, 173, 262, 263, 275,
+    276, 277, 278, 284, 285, 286, 288, 289, 305, 306, 325,
+];
+
+// Opcodes whose resource is the interesting part
+const RESOURCE_OPCODES: &[u32] = &[
+    67, 82, 112, 122, 123, 143, 146, 147, 148, 151, 171, 172, 174, 177, 206, 207, 215, 232, 266,
+    272, 283, 318, 321, 324, 326,
+];
+
+// Damage types are stored in the high word of parameter 2 of opcode 12
+const DAMAGE_TYPES: &[(u32, &str)] = &[
+    (0x10000, "acid"),
+    (0x20000, "cold"),
+    (0x40000, "electricity"),
+    (0x80000, "fire"),
+    (0x100000, "piercing"),
+    (0x200000, "poison"),
+    (0x400000, "magic"),
+    (0x800000, "missile"),
+    (0x1000000, "slashing"),
+    (0x2000000, "magic fire"),
+    (0x4000000, "magic cold"),
+    (0x8000000, "stunning"),
+];
+
+const SAVING_THROWS: &[(u32, &str)] = &[
+    (0x1, "spell"),
+    (0x2, "breath"),
+    (0x4, "death"),
+    (0x8, "wands"),
+    (0x10, "polymorph"),
+];
+
+// Set in the special field of opcode 12 when a successful save halves the damage
+const SAVE_FOR_HALF: u32 = 0x100;
+
+pub fn opcode_name(opcode: u32) -> Option<&'static str> {
+    OPCODE_NAMES
+        .get(opcode as usize)
+        .copied()
+        .filter(|name| !name.is_empty())
+}
+
+// The fields shared by the effect layouts, enough to describe what an effect does
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EffectParameters {
+    pub opcode: u32,
+    pub parameter_1: u32,
+    pub parameter_2: u32,
+    pub timing_mode: u32,
+    pub duration: u32,
+    pub probability_1: u32,
+    pub probability_2: u32,
+    pub resource: String,
+    pub dice_thrown: u32,
+    pub dice_sides: u32,
+    pub saving_throw_type: u32,
+    pub saving_throw_bonus: u32,
+    pub special: u32,
+}
+
+impl From<&FeatureBlock> for EffectParameters {
+    fn from(value: &FeatureBlock) -> Self {
+        Self {
+            opcode: value.opcode_number.into(),
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode.into(),
+            duration: value.duration,
+            probability_1: value.probability_1.into(),
+            probability_2: value.probability_2.into(),
+            resource: value.resource.to_string(),
+            dice_thrown: value.dice_thrown_max_level,
+            dice_sides: value.dice_sides_min_level,
+            saving_throw_type: u32::from_le_bytes(value.saving_throw_type.0),
+            saving_throw_bonus: value.saving_throw_bonus,
+            // The feature block's 0x2c field, which becomes the effect's special field when
+            // the engine applies it
+            special: value.stacking_id,
+        }
+    }
+}
+
+impl From<&EffectV1> for EffectParameters {
+    fn from(value: &EffectV1) -> Self {
+        Self {
+            opcode: value.effect_type.into(),
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode.into(),
+            duration: value.duration,
+            probability_1: value.probability_1.into(),
+            probability_2: value.probability_2.into(),
+            resource: value.resref_key.to_string(),
+            dice_thrown: value.dice_thrown_maximum_level,
+            dice_sides: value.dice_sides_minimum_level,
+            saving_throw_type: value.saving_throw_type,
+            saving_throw_bonus: value.saving_throw_bonus,
+            special: 0,
+        }
+    }
+}
+
+impl From<&EffectV2BodyWithOutHeader> for EffectParameters {
+    fn from(value: &EffectV2BodyWithOutHeader) -> Self {
+        Self {
+            opcode: value.opcode_number,
+            parameter_1: value.parameter_1,
+            parameter_2: value.parameter_2,
+            timing_mode: value.timing_mode.into(),
+            duration: value.duration,
+            probability_1: value.probability_1.into(),
+            probability_2: value.probability_2.into(),
+            resource: value.resource_1.to_string(),
+            dice_thrown: value.dice_thrown,
+            dice_sides: value.dice_sides,
+            saving_throw_type: value.saving_throw_type,
+            saving_throw_bonus: value.saving_throw_bonus,
+            special: value.special,
+        }
+    }
+}
+
+impl EffectParameters {
+    pub fn description(&self) -> String {
+        let Some(name) = opcode_name(self.opcode) else {
+            return format!("Unknown opcode {}", self.opcode);
+        };
+        let resource = self.resource.trim_end_matches('\0');
+        let mut out = match self.opcode {
+            12 => format!("{name}: {}", self.damage()),
+            101 => match opcode_name(self.parameter_2) {
+                Some(effect) => format!("{name}: {effect}"),
+                None => format!("{name}: opcode {}", self.parameter_2),
+            },
+            139 | 267 => format!("{name}: strref {}", self.parameter_1),
+            opcode if STAT_MODIFIERS.contains(&opcode) => {
+                format!("{name}: {}", modifier(self.parameter_1, self.parameter_2))
+            }
+            opcode if RESOURCE_OPCODES.contains(&opcode) && !resource.is_empty() => {
+                format!("{name}: {resource}")
+            }
+            _ => name.to_string(),
+        };
+        if let Some(save) = self.saving_throw() {
+            out.push_str(&format!(", {save}"));
+            if self.opcode == 12 && self.special & SAVE_FOR_HALF != 0 {
+                out.push_str(" for half");
+            }
+        }
+        if let Some(timing) = self.timing() {
+            out.push_str(&format!(", {timing}"));
+        }
+        let chance = (self.probability_1 + 1).saturating_sub(self.probability_2);
+        if chance < 100 {
+            out.push_str(&format!(", {chance}% chance"));
+        }
+        out
+    }
+
+    fn damage(&self) -> String {
+        let amount = match (self.dice_thrown, self.dice_sides, self.parameter_1) {
+            (0, _, amount) | (_, 0, amount) => amount.to_string(),
+            (thrown, sides, 0) => format!("{thrown}d{sides}"),
+            (thrown, sides, amount) => format!("{thrown}d{sides}+{amount}"),
+        };
+        let amount = match self.parameter_2 & 0xffff {
+            1 => format!("set to {amount}"),
+            2 => format!("set to {amount}%"),
+            3 => format!("{amount}%"),
+            _ => amount,
+        };
+        let damage_type = DAMAGE_TYPES
+            .iter()
+            .find(|(bit, _)| self.parameter_2 & 0xffff0000 == *bit)
+            .map_or("crushing", |(_, name)| name);
+        format!("{amount} {damage_type}")
+    }
+
+    fn saving_throw(&self) -> Option<String> {
+        let saves: Vec<&str> = SAVING_THROWS
+            .iter()
+            .filter(|(bit, _)| self.saving_throw_type & bit != 0)
+            .map(|(_, name)| *name)
+            .collect();
+        if saves.is_empty() {
+            return None;
+        }
+        let mut out = format!("save vs {}", saves.join(" or "));
+        match self.saving_throw_bonus as i32 {
+            0 => {}
+            bonus => out.push_str(&format!(" at {bonus:+}")),
+        }
+        Some(out)
+    }
+
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/eff_v2.htm#effv2_Body_0x1c
+    fn timing(&self) -> Option<String> {
+        match (self.timing_mode, self.duration) {
+            (0, 0) => None,
+            (0, duration) => Some(format!("for {duration} seconds")),
+            (1 | 9, _) => Some("permanent".to_string()),
+            (2, _) => Some("while equipped".to_string()),
+            (3, duration) => Some(format!("after {duration} seconds")),
+            (10, duration) => Some(format!("for {duration} ticks")),
+            _ => None,
+        }
+    }
+}
+
+// Most stat opcodes use parameter 2 as the modifier type: increment, set or percentage
+fn modifier(amount: u32, kind: u32) -> String {
+    match kind {
+        1 => format!("set to {}", amount as i32),
+        2 => format!("{}%", amount as i32),
+        _ => format!("{:+}", amount as i32),
+    }
+}
+
+impl FeatureBlock {
+    pub fn description(&self) -> String {
+        EffectParameters::from(self).description()
+    }
+}
+
+impl EffectV1 {
+    pub fn description(&self) -> String {
+        EffectParameters::from(self).description()
+    }
+}
+
+impl EffectV2BodyWithOutHeader {
+    pub fn description(&self) -> String {
+        EffectParameters::from(self).description()
+    }
+}
+
+// Adds a description next to every effect in a model's json, going through the model's
+// own effect lists
+pub fn annotate(model: &IEModels, value: &mut Value) {
+    match model {
+        IEModels::Item(item) => describe_each(
+            value.get_mut("equipping_feature_blocks"),
+            item.equipping_feature_blocks
+                .iter()
+                .map(FeatureBlock::description),
+        ),
+        IEModels::Spell(spell) => describe_each(
+            value.get_mut("equipping_feature_blocks"),
+            spell
+                .equipping_feature_blocks
+                .iter()
+                .map(FeatureBlock::description),
+        ),
+        IEModels::Creature(creature) => annotate_creature(creature, value),
+        IEModels::ExpandedCharacter(character) => {
+            if let Some(value) = value.get_mut("creature") {
+                annotate_creature(&character.creature, value);
+            }
+        }
+        IEModels::Game(game) => {
+            for (key, npcs) in [
+                ("party_npcs", &game.party_npcs),
+                ("non_party_npcs", &game.non_party_npcs),
+            ] {
+                let Some(values) = value.get_mut(key).and_then(Value::as_array_mut) else {
+                    continue;
+                };
+                for (npc, value) in npcs.iter().zip(values) {
+                    if let (Some(creature), Some(value)) =
+                        (&npc.creature, value.get_mut("creature"))
+                    {
+                        annotate_creature(creature, value);
+                    }
+                }
+            }
+        }
+        IEModels::EffectV1(effect) => describe(Some(value), effect.description()),
+        IEModels::EffectV2(effect) => {
+            describe(value.get_mut("effect"), effect.effect.body.description())
+        }
+        _ => {}
+    }
+}
+
+fn annotate_creature(creature: &Creature, value: &mut Value) {
+    describe_each(
+        value.get_mut("effects_v1"),
+        creature.effects_v1.iter().map(EffectV1::description),
+    );
+    describe_each(
+        value.get_mut("effects_v2"),
+        creature
+            .effects_v2
+            .iter()
+            .map(|effect| effect.body.description()),
+    );
+}
+
+fn describe_each(values: Option<&mut Value>, descriptions: impl Iterator<Item = String>) {
+    let Some(values) = values.and_then(Value::as_array_mut) else {
+        return;
+    };
+    for (value, description) in values.iter_mut().zip(descriptions) {
+        describe(Some(value), description);
+    }
+}
+
+fn describe(value: Option<&mut Value>, description: String) {
+    if let Some(Value::Object(map)) = value {
+        map.insert("description".to_string(), Value::String(description));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::game::Game;
+    use crate::item::Item;
+    use crate::model::Model;
+    use pretty_assertions::assert_eq;
+    use std::{fs::File, io::Read};
+
+    #[test]
+    fn describe() {
+        let damage = EffectParameters {
+            opcode: 12,
+            parameter_2: 0x80000,
+            dice_thrown: 2,
+            dice_sides: 6,
+            saving_throw_type: 0x1,
+            special: SAVE_FOR_HALF,
+            probability_1: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            damage.description(),
+            "Damage: 2d6 fire, save vs spell for half"
+        );
+
+        let strength = EffectParameters {
+            opcode: 44,
+            parameter_1: 2,
+            timing_mode: 2,
+            probability_1: 100,
+            ..Default::default()
+        };
+        assert_eq!(strength.description(), "Strength bonus: +2, while equipped");
+
+        let immunity = EffectParameters {
+            opcode: 101,
+            parameter_2: 24,
+            duration: 60,
+            probability_1: 49,
+            ..Default::default()
+        };
+        assert_eq!(
+            immunity.description(),
+            "Immunity to effect: Panic, for 60 seconds, 50% chance"
+        );
+        assert_eq!(
+            EffectParameters {
+                opcode: 9999,
+                ..Default::default()
+            }
+            .description(),
+            "Unknown opcode 9999"
+        );
+    }
+
+    #[test]
+    fn annotate_item() {
+        let mut file = File::open("fixtures/sw1h01.itm").expect("Fixture missing");
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)
+            .expect("Could not read fixture");
+        let item = IEModels::Item(Item::new(&buffer));
+        let mut value = item.to_json().unwrap();
+        annotate(&item, &mut value);
+        let text = value.to_string();
+        assert_eq!(
+            text.matches("\"description\":\"Set color, while equipped\"")
+                .count(),
+            3
+        );
+        assert!(text.contains("\"description\":\"Modify proficiencies, while equipped\""));
+    }
+
+    #[test]
+    fn annotate_game() {
+        let mut file = File::open("fixtures/bg2eebaldur.gam").expect("Fixture missing");
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)
+            .expect("Could not read fixture");
+        let game = Game::new(&buffer);
+        let effects: usize = game
+            .party_npcs
+            .iter()
+            .chain(&game.non_party_npcs)
+            .filter_map(|npc| npc.creature.as_ref())
+            .map(|creature| creature.effects_v2.len())
+            .sum();
+        let game = IEModels::Game(game);
+        let mut value = game.to_json().unwrap();
+        annotate(&game, &mut value);
+        let text = value.to_string();
+        assert!(effects > 0);
+        assert_eq!(text.matches("\"description\":").count(), effects);
+    }
+}
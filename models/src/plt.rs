@@ -0,0 +1,155 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{header::Header, image::Image},
+    creature::BGEECreatureHeader,
+    model::Model,
+};
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/plt_v1.htm
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Plt {
+    #[serde(skip)]
+    #[br(parse_with = until_eof, restore_position)]
+    pub original_bytes: Vec<u8>,
+    #[bw(ignore)]
+    #[serde(flatten)]
+    pub header: PltHeader,
+    #[bw(ignore)]
+    #[serde(skip)]
+    #[br(count=header.width as usize * header.height as usize)]
+    pub pixels: Vec<PltPixel>,
+}
+
+impl Model for Plt {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Plt {
+    // Each pixel looks up its intensity along the gradient row picked by the creature's colour,
+    // gradients usually come from MPALETTE.BMP (see Bmp::to_image) where each row is one colour
+    pub fn to_image(&self, colours: &PltColours, gradients: &Image) -> Image {
+        let (width, height) = (self.header.width, self.header.height);
+        let mut image = Image::new(width, height);
+        for (i, pixel) in self.pixels.iter().enumerate() {
+            let Some(row) = colours.gradient_row(pixel.colour_index) else {
+                continue;
+            };
+            let column = pixel.intensity as u32 * gradients.width / 256;
+            if let Some(rgba) = gradients.pixel(column, row as u32) {
+                // Rows are stored bottom to top
+                let (x, y) = (i as u32 % width, height - 1 - i as u32 / width);
+                image.set_pixel(x, y, [rgba[0], rgba[1], rgba[2], 255]);
+            }
+        }
+        image
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/plt_v1.htm#Header
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct PltHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    #[serde(skip)]
+    #[br(count = 8)]
+    _unknown: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/plt_v1.htm#Data
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct PltPixel {
+    pub intensity: u8,
+    // 0 = skin, 1 = hair, 2 = metal, 3 = leather, 4 = armor, 5 = minor, 6 = major
+    pub colour_index: u8,
+}
+
+// The gradient (row) to use for each colour range of a paperdoll
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PltColours {
+    pub metal: u8,
+    pub minor: u8,
+    pub major: u8,
+    pub skin: u8,
+    pub leather: u8,
+    pub armor: u8,
+    pub hair: u8,
+}
+
+impl PltColours {
+    fn gradient_row(&self, colour_index: u8) -> Option<u8> {
+        match colour_index {
+            0 => Some(self.skin),
+            1 => Some(self.hair),
+            2 => Some(self.metal),
+            3 => Some(self.leather),
+            4 => Some(self.armor),
+            5 => Some(self.minor),
+            6 => Some(self.major),
+            // Anything else is left transparent
+            _ => None,
+        }
+    }
+}
+
+impl From<&BGEECreatureHeader> for PltColours {
+    fn from(header: &BGEECreatureHeader) -> Self {
+        Self {
+            metal: header.metal_color,
+            minor: header.minor_color,
+            major: header.major_color,
+            skin: header.skin_color,
+            leather: header.leather_color,
+            armor: header.armor_color,
+            hair: header.hair_color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse() {
+        let mut buffer = b"PLT V1  ".to_vec();
+        buffer.extend([0; 8]);
+        buffer.extend(2_u32.to_le_bytes());
+        buffer.extend(2_u32.to_le_bytes());
+        // Bottom row is full intensity skin then hair, top row is transparent
+        buffer.extend([255, 0, 255, 1, 0, 0xff, 0, 0xff]);
+        let plt = Plt::new(&buffer);
+        assert_eq!((plt.header.width, plt.header.height), (2, 2));
+        assert_eq!(plt.to_bytes(), buffer);
+
+        let mut gradients = Image::new(12, 2);
+        gradients.set_pixel(11, 0, [10, 20, 30, 255]);
+        gradients.set_pixel(11, 1, [40, 50, 60, 255]);
+        let colours = PltColours {
+            skin: 1,
+            ..Default::default()
+        };
+        let image = plt.to_image(&colours, &gradients);
+        assert_eq!(image.pixel(0, 1), Some([40, 50, 60, 255]));
+        assert_eq!(image.pixel(1, 1), Some([10, 20, 30, 255]));
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 0, 0]));
+    }
+}
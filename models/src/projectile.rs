@@ -0,0 +1,303 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::common::Resref;
+use crate::common::flags::{flags, ids_enum};
+use crate::common::header::Header;
+use crate::model::Model;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Projectile {
+    #[serde(flatten)]
+    pub header: ProjectileHeader,
+    pub animation: ProjectileAnimation,
+    // Only area of effect projectiles have the extension
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[br(if(header.projectile_type == ProjectileType::AreaOfEffect))]
+    pub area_of_effect: Option<AreaOfEffect>,
+}
+
+impl Model for Projectile {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#Header
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct ProjectileHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub projectile_type: ProjectileType,
+    pub speed: u16,
+    pub sparking_flags: SparkingFlags,
+    pub travel_sound: Resref,
+    pub arrival_sound: Resref,
+    pub source_animation: Resref,
+    pub spark_colour: u16,
+    // The rest of the header is ee only
+    pub projectile_width: u16,
+    pub travel_flags: TravelFlags,
+    pub string: u32,
+    pub colour: u32,
+    pub colour_speed: u16,
+    pub screen_shake_amount: u16,
+    pub ids_value_1: u16,
+    pub ids_type_1: u16,
+    pub ids_value_2: u16,
+    pub ids_type_2: u16,
+    pub default_spell: Resref,
+    pub success_spell: Resref,
+    pub angle_increase_minimum: u16,
+    pub angle_increase_maximum: u16,
+    pub curve_minimum: u16,
+    pub curve_maximum: u16,
+    pub thac0_bonus: u16,
+    pub thac0_bonus_non_actor: u16,
+    pub radius_minimum: u16,
+    #[brw(pad_after = 156)]
+    pub radius_maximum: u16,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#BAM
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct ProjectileAnimation {
+    pub flags: AnimationFlags,
+    pub projectile_animation: Resref,
+    pub shadow_animation: Resref,
+    pub projectile_sequence: u8,
+    pub shadow_sequence: u8,
+    pub light_spot_intensity: u16,
+    pub light_spot_width: u16,
+    pub light_spot_height: u16,
+    pub palette: Resref,
+    pub projectile_colours: [u8; 7],
+    pub smoke_puff_period: u8,
+    pub smoke_colours: [u8; 7],
+    pub face_target_granularity: u8,
+    pub smoke_animation: u16,
+    pub trailing_animation_1: Resref,
+    pub trailing_animation_2: Resref,
+    pub trailing_animation_3: Resref,
+    pub trailing_animation_delay_1: u16,
+    pub trailing_animation_delay_2: u16,
+    pub trailing_animation_delay_3: u16,
+    #[brw(pad_after = 168)]
+    pub trail_flags: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#AreaEffect
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct AreaOfEffect {
+    pub flags: AreaFlags,
+    pub trigger_radius: u16,
+    pub explosion_radius: u16,
+    pub explosion_sound: Resref,
+    pub explosion_delay: u16,
+    // An animate.ids entry
+    pub fragment_animation: u16,
+    // A projectl.ids entry minus one
+    pub secondary_projectile: u16,
+    pub trigger_count: u8,
+    // A fireball.ids entry
+    pub explosion_effect: u8,
+    pub explosion_colour: u8,
+    #[serde(skip)]
+    _unused_1: u8,
+    // A projectl.ids entry
+    pub explosion_projectile: u16,
+    pub explosion_animation: Resref,
+    pub cone_width: u16,
+    #[serde(skip)]
+    _unused_2: u16,
+    // The rest of the extension is ee only
+    pub spread_animation: Resref,
+    pub ring_animation: Resref,
+    pub area_sound: Resref,
+    pub extended_flags: u32,
+    pub dice_thrown: u16,
+    pub dice_sides: u16,
+    pub animation_granularity: u16,
+    #[brw(pad_after = 180)]
+    pub animation_granularity_divider: u16,
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#Header_0x08
+    ProjectileType: u16 {
+        NoBam = 1 => "NO_BAM",
+        SingleTarget = 2 => "SINGLE_TARGET",
+        AreaOfEffect = 3 => "AREA_OF_EFFECT",
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#Header_0x0c
+    SparkingFlags: u32 {
+        SHOW_SPARKS = 0x1,
+        USE_HEIGHT = 0x2,
+        LOOP_TRAVEL_SOUND = 0x4,
+        LOOP_ARRIVAL_SOUND = 0x8,
+        IGNORE_CENTER = 0x10,
+        DRAW_AS_BACKGROUND = 0x20,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#Header_0x2c
+    TravelFlags: u32 {
+        BOUNCE_FROM_WALLS = 0x1,
+        PASS_TARGET = 0x2,
+        DRAW_CENTER_VVC_ONCE = 0x4,
+        HIT_IMMEDIATELY = 0x8,
+        FACE_TARGET = 0x10,
+        CURVED_PATH = 0x20,
+        START_RANDOM_FRAME = 0x40,
+        PILLAR = 0x80,
+        SEMI_TRANSPARENT_TRAIL = 0x100,
+        TINTED_TRAIL = 0x200,
+        MULTIPLE_PROJECTILES = 0x400,
+        DEFAULT_SPELL_ON_MISS = 0x800,
+        FALLING_PATH = 0x1000,
+        COMET = 0x2000,
+        LINED_UP_AREA = 0x4000,
+        RECTANGULAR_AREA = 0x8000,
+        DRAW_BEHIND_TARGET = 0x10000,
+        CASTING_GLOW = 0x20000,
+        TRAVEL_DOOR = 0x40000,
+        FADE_AFTER_HIT = 0x80000,
+        DISPLAY_MESSAGE = 0x100000,
+        RANDOM_PATH = 0x200000,
+        START_RANDOM_SEQUENCE = 0x400000,
+        COLOUR_PULSE_ON_HIT = 0x800000,
+        TOUCH_PROJECTILE = 0x1000000,
+        NEGATE_IDS_1 = 0x2000000,
+        NEGATE_IDS_2 = 0x4000000,
+        EITHER_IDS = 0x8000000,
+        DELAYED_PAYLOAD = 0x10000000,
+        LIMITED_PATH_COUNT = 0x20000000,
+        IWD_STYLE_CHECK = 0x40000000,
+        CASTER_AFFECTED = 0x80000000,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#BAM_0x100
+    AnimationFlags: u32 {
+        BAM_COLOURING = 0x1,
+        SMOKE = 0x2,
+        COLOUR_SMOKE = 0x4,
+        NOT_LIGHT = 0x8,
+        AREA_HEIGHT = 0x10,
+        SHADOW = 0x20,
+        LIGHT_SPOT = 0x40,
+        BRIGHTEN = 0x80,
+        LOW_LEVEL_BRIGHTEN = 0x100,
+        HIGH_LEVEL_BRIGHTEN = 0x200,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pro_v1.htm#AreaEffect_0x200
+    AreaFlags: u32 {
+        VISIBLE_TRAP = 0x1,
+        TRIGGERED_BY_INANIMATE = 0x2,
+        TRIGGERED_ON_CONDITION = 0x4,
+        TRIGGER_DURING_DELAY = 0x8,
+        SECONDARY_PROJECTILE = 0x10,
+        FRAGMENTS = 0x20,
+        NOT_ALLIES = 0x40,
+        NOT_ENEMIES = 0x80,
+        MAGE_LEVEL_TRIGGERS = 0x100,
+        CLERIC_LEVEL_TRIGGERS = 0x200,
+        EXPLOSION_VVC = 0x400,
+        CONE = 0x800,
+        IGNORE_LINE_OF_SIGHT = 0x1000,
+        DELAYED_EXPLOSION = 0x2000,
+        SKIP_FIRST_CONDITION = 0x4000,
+        SINGLE_TARGET = 0x8000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // Fireball's layout, built by hand as no game projectile is checked in to fixtures
+    fn fireball() -> Vec<u8> {
+        let mut buffer = vec![0_u8; 0x300];
+        buffer[..8].copy_from_slice(b"PRO V1.0");
+        buffer[0x08] = 3;
+        buffer[0x0a] = 20;
+        buffer[0x10..0x18].copy_from_slice(b"EFF_P02\0");
+        buffer[0x100] = 0x22;
+        buffer[0x104..0x10c].copy_from_slice(b"SPFIREPR");
+        buffer[0x200] = 0x20;
+        buffer[0x201] = 0x04;
+        buffer[0x204] = 30;
+        buffer[0x206] = 0x80;
+        buffer[0x208..0x210].copy_from_slice(b"EFF_E02\0");
+        buffer[0x212] = 3;
+        buffer[0x216] = 1;
+        buffer[0x21c..0x224].copy_from_slice(b"SPFIREBA");
+        buffer[0x224] = 90;
+        buffer
+    }
+
+    #[test]
+    fn area_of_effect() {
+        let buffer = fireball();
+        let projectile = Projectile::new(&buffer);
+        assert_eq!(
+            projectile.header.projectile_type,
+            ProjectileType::AreaOfEffect
+        );
+        assert_eq!(projectile.header.speed, 20);
+        assert_eq!(
+            projectile.animation.flags,
+            AnimationFlags::SMOKE | AnimationFlags::SHADOW
+        );
+        let area = projectile
+            .area_of_effect
+            .as_ref()
+            .expect("Missing extension");
+        assert_eq!(area.flags, AreaFlags::FRAGMENTS | AreaFlags::EXPLOSION_VVC);
+        assert_eq!(area.trigger_radius, 30);
+        assert_eq!(area.explosion_radius, 0x80);
+        assert_eq!(area.explosion_animation.to_string(), "SPFIREBA");
+        assert_eq!(area.cone_width, 90);
+        assert_eq!(projectile.to_bytes(), buffer);
+
+        let json = serde_json::to_string(&projectile).unwrap();
+        let from_json: Projectile = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.to_bytes(), buffer);
+    }
+
+    #[test]
+    fn single_target() {
+        let mut buffer = fireball();
+        buffer[0x08] = 2;
+        buffer.truncate(0x200);
+        let projectile = Projectile::new(&buffer);
+        assert_eq!(projectile.area_of_effect, None);
+        assert_eq!(projectile.to_bytes(), buffer);
+        let json = serde_json::to_value(&projectile).unwrap();
+        assert_eq!(json["projectile_type"], "SINGLE_TARGET");
+        assert!(json.get("area_of_effect").is_none());
+    }
+}
@@ -0,0 +1,237 @@
+use std::error::Error;
+
+use binrw::{BinRead, BinReaderExt, BinResult, BinWrite, helpers::until_eof, io::Cursor};
+use flate2::bufread::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+
+use crate::{common::image::Image, model::Model};
+
+// Pvr v3 pixel formats used by the enhanced editions
+const DXT1: u64 = 7;
+const DXT5: u64 = 11;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/pvrz.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Pvrz {
+    #[serde(skip)]
+    #[br(parse_with = until_eof, restore_position)]
+    pub original_bytes: Vec<u8>,
+    #[bw(ignore)]
+    pub uncompressed_length: u32,
+    #[bw(ignore)]
+    #[serde(flatten)]
+    #[br(parse_with = parse_compressed_texture)]
+    pub texture: PvrTexture,
+}
+
+#[binrw::parser(reader, endian)]
+fn parse_compressed_texture() -> BinResult<PvrTexture> {
+    use std::io::Read;
+
+    let mut compressed = vec![];
+    reader.read_to_end(&mut compressed)?;
+    let mut buffer = vec![];
+    ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut buffer)?;
+    PvrTexture::read_options(&mut Cursor::new(buffer), endian, ())
+}
+
+impl Model for Pvrz {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Pvrz {
+    pub fn to_image(&self) -> Result<Image, Box<dyn Error>> {
+        self.texture.to_image()
+    }
+}
+
+// The decompressed contents of a pvrz file, a pvr v3 texture
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct PvrTexture {
+    pub version: u32,
+    pub flags: u32,
+    // 7 = DXT1 (BC1), 11 = DXT5 (BC3)
+    pub pixel_format: u64,
+    pub colour_space: u32,
+    pub channel_type: u32,
+    pub height: u32,
+    pub width: u32,
+    pub depth: u32,
+    pub count_of_surfaces: u32,
+    pub count_of_faces: u32,
+    pub count_of_mipmaps: u32,
+    pub size_of_metadata: u32,
+    #[serde(skip)]
+    #[br(count = size_of_metadata)]
+    pub metadata: Vec<u8>,
+    #[serde(skip)]
+    #[br(parse_with = until_eof)]
+    pub data: Vec<u8>,
+}
+
+impl PvrTexture {
+    pub fn to_image(&self) -> Result<Image, Box<dyn Error>> {
+        let block_size = match self.pixel_format {
+            DXT1 => 8,
+            DXT5 => 16,
+            format => return Err(format!("Unsupported pvr pixel format: {format}").into()),
+        };
+        let blocks_wide = self.width.div_ceil(4);
+        let blocks_high = self.height.div_ceil(4);
+        let mut image = Image::new(self.width, self.height);
+        for block_y in 0..blocks_high {
+            for block_x in 0..blocks_wide {
+                let start = (block_y * blocks_wide + block_x) as usize * block_size;
+                let block = self
+                    .data
+                    .get(start..start + block_size)
+                    .ok_or("Pvr texture data is truncated")?;
+                let texels = match self.pixel_format {
+                    DXT1 => decode_dxt1_block(block),
+                    _ => decode_dxt5_block(block),
+                };
+                for (i, texel) in texels.iter().enumerate() {
+                    let x = block_x * 4 + i as u32 % 4;
+                    let y = block_y * 4 + i as u32 / 4;
+                    image.set_pixel(x, y, *texel);
+                }
+            }
+        }
+        Ok(image)
+    }
+}
+
+fn rgb565(colour: u16) -> [u8; 4] {
+    let red = ((colour >> 11) & 0x1f) as u32;
+    let green = ((colour >> 5) & 0x3f) as u32;
+    let blue = (colour & 0x1f) as u32;
+    [
+        (red * 255 / 31) as u8,
+        (green * 255 / 63) as u8,
+        (blue * 255 / 31) as u8,
+        255,
+    ]
+}
+
+fn mix(first: [u8; 4], second: [u8; 4], first_weight: u32, second_weight: u32) -> [u8; 4] {
+    let total = first_weight + second_weight;
+    let mut out = [255; 4];
+    for channel in 0..3 {
+        out[channel] = ((first[channel] as u32 * first_weight
+            + second[channel] as u32 * second_weight)
+            / total) as u8;
+    }
+    out
+}
+
+fn decode_colours(block: &[u8], always_four_colours: bool) -> [[u8; 4]; 16] {
+    let colour_0 = u16::from_le_bytes([block[0], block[1]]);
+    let colour_1 = u16::from_le_bytes([block[2], block[3]]);
+    let (first, second) = (rgb565(colour_0), rgb565(colour_1));
+    let palette = if colour_0 > colour_1 || always_four_colours {
+        [
+            first,
+            second,
+            mix(first, second, 2, 1),
+            mix(first, second, 1, 2),
+        ]
+    } else {
+        [first, second, mix(first, second, 1, 1), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let mut out = [[0; 4]; 16];
+    for (i, texel) in out.iter_mut().enumerate() {
+        *texel = palette[((indices >> (i * 2)) & 0b11) as usize];
+    }
+    out
+}
+
+fn decode_dxt1_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_colours(block, false)
+}
+
+fn decode_dxt5_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let (alpha_0, alpha_1) = (block[0] as u32, block[1] as u32);
+    let mut alphas = [alpha_0, alpha_1, 0, 0, 0, 0, 0, 255];
+    if alpha_0 > alpha_1 {
+        for (i, alpha) in alphas.iter_mut().enumerate().skip(2) {
+            *alpha = ((8 - i as u32) * alpha_0 + (i as u32 - 1) * alpha_1) / 7;
+        }
+    } else {
+        for (i, alpha) in alphas.iter_mut().enumerate().take(6).skip(2) {
+            *alpha = ((6 - i as u32) * alpha_0 + (i as u32 - 1) * alpha_1) / 5;
+        }
+    }
+    let indices = block[2..8]
+        .iter()
+        .rev()
+        .fold(0_u64, |accum, byte| (accum << 8) | *byte as u64);
+    let mut out = decode_colours(&block[8..16], true);
+    for (i, texel) in out.iter_mut().enumerate() {
+        texel[3] = alphas[((indices >> (i * 3)) & 0b111) as usize] as u8;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::ZlibEncoder};
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    fn pvrz(pixel_format: u64, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut texture = vec![];
+        for value in [0x03525650_u32, 0] {
+            texture.extend(value.to_le_bytes());
+        }
+        texture.extend(pixel_format.to_le_bytes());
+        for value in [0_u32, 0, 4, 4, 1, 1, 1, 1, 0] {
+            texture.extend(value.to_le_bytes());
+        }
+        texture.extend(data);
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&texture)?;
+        let mut out = (texture.len() as u32).to_le_bytes().to_vec();
+        out.extend(encoder.finish()?);
+        Ok(out)
+    }
+
+    #[test]
+    fn decode_dxt1() -> Result<(), Box<dyn Error>> {
+        // Pure red and pure blue, only the first texel uses the second colour
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0x01, 0x00, 0x00, 0x00];
+        let buffer = pvrz(DXT1, &block)?;
+        let pvrz = Pvrz::new(&buffer);
+        assert_eq!(pvrz.texture.width, 4);
+        assert_eq!(pvrz.to_bytes(), buffer);
+
+        let image = pvrz.to_image()?;
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(image.pixel(3, 3), Some([255, 0, 0, 255]));
+        Ok(())
+    }
+
+    #[test]
+    fn decode_dxt5_alpha() -> Result<(), Box<dyn Error>> {
+        let mut block = vec![255, 0, 0, 0, 0, 0, 0, 0];
+        block.extend([0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+        let image = Pvrz::new(&pvrz(DXT5, &block)?).to_image()?;
+        assert_eq!(image.pixel(2, 2), Some([255, 255, 255, 255]));
+        Ok(())
+    }
+}
@@ -0,0 +1,913 @@
+use std::{error::Error, fmt::Display};
+
+use super::{
+    Action, ConditionResponse, NO_RECTANGLE, Object, Response, Script, Trigger,
+    symbols::{AREA_LENGTH, OBJECT_TABLES, ParameterKind, Signature, StringSlot, Symbols},
+};
+
+// Source only triggers, expanded into NextTriggerObject followed by the trigger itself
+pub(crate) const TRIGGER_OVERRIDE: &str = "TriggerOverride";
+pub(crate) const NEXT_TRIGGER_OBJECT: &str = "NextTriggerObject";
+pub(crate) const ACTION_OVERRIDE: &str = "ActionOverride";
+
+#[derive(Debug, PartialEq)]
+pub struct CompileError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for CompileError {}
+
+impl Script {
+    // Compiles baf source, every name is checked against the trigger and action signatures
+    pub fn compile(source: &str, symbols: &Symbols) -> Result<Self, CompileError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            symbols,
+        };
+        let mut blocks = vec![];
+        while parser.peek().is_some() {
+            blocks.push(parser.block()?);
+        }
+        Ok(Script { blocks })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Integer(i64),
+    String(String),
+    Symbol(char),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, CompileError> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let (mut position, mut line, mut line_start) = (0, 1, 0);
+    while let Some(character) = characters.get(position).copied() {
+        let column = position - line_start + 1;
+        let error = move |message: &str| CompileError {
+            line,
+            column,
+            message: message.to_string(),
+        };
+        let next = characters.get(position + 1).copied();
+        let kind = match character {
+            '\n' => {
+                position += 1;
+                line += 1;
+                line_start = position;
+                continue;
+            }
+            character if character.is_whitespace() => {
+                position += 1;
+                continue;
+            }
+            '/' if next == Some('/') => {
+                while characters.get(position).is_some_and(|c| *c != '\n') {
+                    position += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                position += 2;
+                while characters.get(position..position + 2) != Some(&['*', '/']) {
+                    match characters.get(position) {
+                        Some('\n') => {
+                            line += 1;
+                            line_start = position + 1;
+                        }
+                        Some(_) => {}
+                        None => return Err(error("Unterminated comment")),
+                    }
+                    position += 1;
+                }
+                position += 2;
+                continue;
+            }
+            '"' | '~' => {
+                let end = characters[position + 1..]
+                    .iter()
+                    .position(|c| *c == character)
+                    .ok_or_else(|| error("Unterminated string"))?;
+                let string = characters[position + 1..position + 1 + end]
+                    .iter()
+                    .collect();
+                position += end + 2;
+                TokenKind::String(string)
+            }
+            '-' | '0'..='9' => {
+                let start = position;
+                position += 1;
+                while characters
+                    .get(position)
+                    .is_some_and(|c| c.is_ascii_alphanumeric())
+                {
+                    position += 1;
+                }
+                let number: String = characters[start..position].iter().collect();
+                let value = match number.strip_prefix("0x") {
+                    Some(hex) => i64::from_str_radix(hex, 16),
+                    None => number.parse::<i64>(),
+                };
+                TokenKind::Integer(value.map_err(|_| error(&format!("Invalid number {number}")))?)
+            }
+            character if character.is_ascii_alphabetic() || character == '_' => {
+                let start = position;
+                while characters
+                    .get(position)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    position += 1;
+                }
+                TokenKind::Identifier(characters[start..position].iter().collect())
+            }
+            '(' | ')' | ',' | '[' | ']' | '.' | '!' | '#' | '|' => {
+                position += 1;
+                TokenKind::Symbol(character)
+            }
+            character => return Err(error(&format!("Unexpected character {character:?}"))),
+        };
+        tokens.push(Token { kind, line, column });
+    }
+    Ok(tokens)
+}
+
+// Arguments are parsed before the signature tells us how to interpret them
+#[derive(Debug)]
+enum ArgumentKind {
+    Integer(i64),
+    String(String),
+    // Symbols joined with |
+    Symbols(Vec<String>),
+    // eg. [ENEMY] or [100.200]
+    Bracket(Vec<Argument>),
+    Call(String, Vec<Argument>),
+}
+
+#[derive(Debug)]
+struct Argument {
+    kind: ArgumentKind,
+    line: usize,
+    column: usize,
+}
+
+impl Argument {
+    fn error(&self, message: String) -> CompileError {
+        CompileError {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+}
+
+// A source trigger and how many compiled triggers it became
+struct Condition {
+    triggers: Vec<Trigger>,
+    or_count: Option<(usize, Token)>,
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    symbols: &'a Symbols,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<Token, CompileError> {
+        let token = self.tokens.get(self.position).cloned().ok_or_else(|| {
+            let last = self.tokens.last();
+            CompileError {
+                line: last.map_or(1, |token| token.line),
+                column: last.map_or(1, |token| token.column),
+                message: "Unexpected end of script".to_string(),
+            }
+        })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn error(token: &Token, message: String) -> CompileError {
+        CompileError {
+            line: token.line,
+            column: token.column,
+            message,
+        }
+    }
+
+    fn peek_identifier(&self, expected: &str) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Identifier(name), .. }) if name.eq_ignore_ascii_case(expected))
+    }
+
+    fn peek_symbol(&self, expected: char) -> bool {
+        matches!(self.peek(), Some(Token { kind: TokenKind::Symbol(symbol), .. }) if *symbol == expected)
+    }
+
+    fn keyword(&mut self, expected: &str) -> Result<Token, CompileError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Identifier(name) if name.eq_ignore_ascii_case(expected) => Ok(token),
+            kind => Err(Self::error(
+                &token,
+                format!("Expected {expected}, found {kind:?}"),
+            )),
+        }
+    }
+
+    fn symbol(&mut self, expected: char) -> Result<Token, CompileError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Symbol(symbol) if *symbol == expected => Ok(token),
+            kind => Err(Self::error(
+                &token,
+                format!("Expected '{expected}', found {kind:?}"),
+            )),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<(String, Token), CompileError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Identifier(name) => Ok((name.clone(), token)),
+            kind => Err(Self::error(
+                &token,
+                format!("Expected a name, found {kind:?}"),
+            )),
+        }
+    }
+
+    fn block(&mut self) -> Result<ConditionResponse, CompileError> {
+        self.keyword("IF")?;
+        let mut conditions = vec![];
+        while !self.peek_identifier("THEN") {
+            conditions.push(self.condition()?);
+        }
+        self.keyword("THEN")?;
+        let mut responses = vec![];
+        while !self.peek_identifier("END") {
+            responses.push(self.response()?);
+        }
+        self.keyword("END")?;
+        Ok(ConditionResponse {
+            conditions: flatten_conditions(conditions)?,
+            responses,
+        })
+    }
+
+    fn condition(&mut self) -> Result<Condition, CompileError> {
+        let negated = self.peek_symbol('!');
+        if negated {
+            self.next()?;
+        }
+        let (name, token) = self.identifier()?;
+        let arguments = self.arguments()?;
+        if name.eq_ignore_ascii_case(TRIGGER_OVERRIDE) {
+            return self.trigger_override(negated, arguments, &token);
+        }
+        let trigger = self.trigger(&name, negated, arguments, &token)?;
+        let or_count = if name.eq_ignore_ascii_case("OR") {
+            Some((trigger.integer_1 as usize, token))
+        } else {
+            None
+        };
+        Ok(Condition {
+            triggers: vec![trigger],
+            or_count,
+        })
+    }
+
+    // TriggerOverride(Object,Trigger) checks the trigger against another object
+    fn trigger_override(
+        &self,
+        negated: bool,
+        arguments: Vec<Argument>,
+        token: &Token,
+    ) -> Result<Condition, CompileError> {
+        let [
+            object,
+            Argument {
+                kind: ArgumentKind::Call(name, arguments),
+                line,
+                column,
+            },
+        ] = <[Argument; 2]>::try_from(arguments).map_err(|_| {
+            Self::error(
+                token,
+                format!("{TRIGGER_OVERRIDE} expects an object and a trigger"),
+            )
+        })?
+        else {
+            return Err(Self::error(
+                token,
+                format!("{TRIGGER_OVERRIDE} expects an object and a trigger"),
+            ));
+        };
+        let next_object = self.trigger(NEXT_TRIGGER_OBJECT, false, vec![object], token)?;
+        let inner = Token {
+            kind: TokenKind::Identifier(name.clone()),
+            line,
+            column,
+        };
+        let trigger = self.trigger(&name, negated, arguments, &inner)?;
+        Ok(Condition {
+            triggers: vec![next_object, trigger],
+            or_count: None,
+        })
+    }
+
+    fn trigger(
+        &self,
+        name: &str,
+        negated: bool,
+        arguments: Vec<Argument>,
+        token: &Token,
+    ) -> Result<Trigger, CompileError> {
+        let signature = find_signature(&self.symbols.triggers, name, arguments.len())
+            .ok_or_else(|| Self::error(token, format!("Unknown trigger {name}")))?;
+        let mut values = self.resolve(signature, arguments, token)?;
+        Ok(Trigger {
+            id: signature.id as u32,
+            integer_1: values.integers[0],
+            flags: negated as u32,
+            integer_2: values.integers[1],
+            integer_3: values.integers[2],
+            // The enhanced editions store a point on every trigger
+            point: match self.symbols.enhanced_edition {
+                true => Some(values.point.unwrap_or_default()),
+                false => values.point,
+            },
+            string_1: std::mem::take(&mut values.strings[0]),
+            string_2: std::mem::take(&mut values.strings[1]),
+            object: values
+                .objects
+                .into_iter()
+                .next()
+                .unwrap_or_else(|| self.empty_object()),
+        })
+    }
+
+    fn response(&mut self) -> Result<Response, CompileError> {
+        self.keyword("RESPONSE")?;
+        self.symbol('#')?;
+        let token = self.next()?;
+        let TokenKind::Integer(weight) = token.kind else {
+            return Err(Self::error(&token, "Expected a weight".to_string()));
+        };
+        let weight = u32::try_from(weight)
+            .map_err(|_| Self::error(&token, format!("Weight {weight} does not fit in a u32")))?;
+        let mut actions = vec![];
+        while !self.peek_identifier("RESPONSE") && !self.peek_identifier("END") {
+            let (name, token) = self.identifier()?;
+            let arguments = self.arguments()?;
+            actions.push(self.action(&name, arguments, &token)?);
+        }
+        Ok(Response { weight, actions })
+    }
+
+    fn action(
+        &self,
+        name: &str,
+        arguments: Vec<Argument>,
+        token: &Token,
+    ) -> Result<Action, CompileError> {
+        // ActionOverride(Actor,Action) stores the actor on the action itself
+        if name.eq_ignore_ascii_case(ACTION_OVERRIDE) {
+            let error = || {
+                Self::error(
+                    token,
+                    format!("{ACTION_OVERRIDE} expects an object and an action"),
+                )
+            };
+            let [actor, action] = <[Argument; 2]>::try_from(arguments).map_err(|_| error())?;
+            let ArgumentKind::Call(name, arguments) = action.kind else {
+                return Err(error());
+            };
+            let inner = Token {
+                kind: TokenKind::Identifier(name.clone()),
+                line: action.line,
+                column: action.column,
+            };
+            let mut action = self.action(&name, arguments, &inner)?;
+            action.actor = self.object(actor)?;
+            return Ok(action);
+        }
+        let signature = find_signature(&self.symbols.actions, name, arguments.len())
+            .ok_or_else(|| Self::error(token, format!("Unknown action {name}")))?;
+        let mut values = self.resolve(signature, arguments, token)?;
+        let mut objects = values.objects.into_iter();
+        Ok(Action {
+            id: signature.id as u32,
+            actor: self.empty_object(),
+            object_1: objects.next().unwrap_or_else(|| self.empty_object()),
+            object_2: objects.next().unwrap_or_else(|| self.empty_object()),
+            integer_1: values.integers[0],
+            point: values.point.unwrap_or_default(),
+            integer_2: values.integers[1],
+            integer_3: values.integers[2],
+            string_1: std::mem::take(&mut values.strings[0]),
+            string_2: std::mem::take(&mut values.strings[1]),
+        })
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Argument>, CompileError> {
+        self.symbol('(')?;
+        let mut arguments = vec![];
+        while !self.peek_symbol(')') {
+            if !arguments.is_empty() {
+                self.symbol(',')?;
+            }
+            arguments.push(self.argument()?);
+        }
+        self.symbol(')')?;
+        Ok(arguments)
+    }
+
+    fn argument(&mut self) -> Result<Argument, CompileError> {
+        let token = self.next()?;
+        let kind = match token.kind {
+            TokenKind::Integer(value) => ArgumentKind::Integer(value),
+            TokenKind::String(ref value) => ArgumentKind::String(value.clone()),
+            TokenKind::Symbol('[') => {
+                let mut elements = vec![];
+                while !self.peek_symbol(']') {
+                    if !elements.is_empty() {
+                        self.symbol('.')?;
+                    }
+                    elements.push(self.argument()?);
+                }
+                self.symbol(']')?;
+                ArgumentKind::Bracket(elements)
+            }
+            TokenKind::Identifier(ref name) if self.peek_symbol('(') => {
+                ArgumentKind::Call(name.clone(), self.arguments()?)
+            }
+            TokenKind::Identifier(ref name) => {
+                let mut names = vec![name.clone()];
+                while self.peek_symbol('|') {
+                    self.next()?;
+                    names.push(self.identifier()?.0);
+                }
+                ArgumentKind::Symbols(names)
+            }
+            ref kind => {
+                return Err(Self::error(
+                    &token,
+                    format!("Unexpected {kind:?} in arguments"),
+                ));
+            }
+        };
+        Ok(Argument {
+            kind,
+            line: token.line,
+            column: token.column,
+        })
+    }
+
+    fn resolve(
+        &self,
+        signature: &Signature,
+        arguments: Vec<Argument>,
+        token: &Token,
+    ) -> Result<Values, CompileError> {
+        let parameters: Vec<_> = signature
+            .parameters
+            .iter()
+            .filter(|parameter| parameter.kind != ParameterKind::Action)
+            .collect();
+        if parameters.len() != arguments.len() {
+            return Err(Self::error(
+                token,
+                format!(
+                    "{} expects {} arguments, found {}",
+                    signature.name,
+                    parameters.len(),
+                    arguments.len()
+                ),
+            ));
+        }
+        let mut values = Values::default();
+        let mut string_slots = signature.string_slots().into_iter();
+        let mut integers = values.integers.iter_mut();
+        let (mut names, mut areas) = (
+            [String::new(), String::new()],
+            [String::new(), String::new()],
+        );
+        for (parameter, argument) in parameters.into_iter().zip(arguments) {
+            match parameter.kind {
+                ParameterKind::Integer => {
+                    let value = self.integer(&argument, parameter.table.as_deref())?;
+                    let slot = integers
+                        .next()
+                        .ok_or_else(|| argument.error("Too many numbers".to_string()))?;
+                    *slot = value;
+                }
+                ParameterKind::String => {
+                    let ArgumentKind::String(value) = &argument.kind else {
+                        return Err(
+                            argument.error(format!("Expected a string for {}", parameter.name))
+                        );
+                    };
+                    match string_slots.next() {
+                        Some(StringSlot::Whole(slot) | StringSlot::Name(slot)) if slot < 2 => {
+                            names[slot] = value.clone()
+                        }
+                        Some(StringSlot::Area(slot)) if slot < 2 => {
+                            if value.len() > AREA_LENGTH {
+                                return Err(argument.error(format!(
+                                    "Area {value:?} is longer than {AREA_LENGTH} characters"
+                                )));
+                            }
+                            areas[slot] = value.clone()
+                        }
+                        _ => return Err(argument.error("Too many strings".to_string())),
+                    }
+                }
+                ParameterKind::Object => values.objects.push(self.object(argument)?),
+                ParameterKind::Point => {
+                    let ArgumentKind::Bracket(elements) = &argument.kind else {
+                        return Err(argument.error("Expected a point [x.y]".to_string()));
+                    };
+                    let [x, y] = elements.as_slice() else {
+                        return Err(argument.error("Expected a point [x.y]".to_string()));
+                    };
+                    values.point = Some([self.integer(x, None)?, self.integer(y, None)?]);
+                }
+                ParameterKind::Action => {}
+            }
+        }
+        for slot in 0..2 {
+            values.strings[slot] = format!("{}{}", areas[slot], names[slot]);
+        }
+        Ok(values)
+    }
+
+    fn integer(&self, argument: &Argument, table: Option<&str>) -> Result<i32, CompileError> {
+        match &argument.kind {
+            ArgumentKind::Integer(value) => i32::try_from(*value)
+                .map_err(|_| argument.error(format!("Number {value} does not fit in an i32"))),
+            ArgumentKind::Symbols(names) => {
+                let mut out = 0;
+                for name in names {
+                    let value = table
+                        .and_then(|table| self.symbols.value(table, name))
+                        .ok_or_else(|| argument.error(format!("Unknown symbol {name}")))?;
+                    out |= value;
+                }
+                // Flag tables may use the top bit, which is stored as a negative number
+                i32::try_from(out)
+                    .or_else(|_| u32::try_from(out).map(|value| value as i32))
+                    .map_err(|_| argument.error(format!("Value {out} does not fit in an i32")))
+            }
+            kind => Err(argument.error(format!("Expected a number, found {kind:?}"))),
+        }
+    }
+
+    fn object(&self, argument: Argument) -> Result<Object, CompileError> {
+        let error = |message: String| CompileError {
+            line: argument.line,
+            column: argument.column,
+            message,
+        };
+        match argument.kind {
+            ArgumentKind::String(name) => Ok(Object {
+                name,
+                ..self.empty_object()
+            }),
+            ArgumentKind::Bracket(elements) => {
+                let mut fields = [0; 7];
+                let anyone = matches!(elements.as_slice(), [Argument { kind: ArgumentKind::Symbols(names), .. }] if names.len() == 1 && names[0].eq_ignore_ascii_case("ANYONE"));
+                if !anyone {
+                    if elements.len() > fields.len() {
+                        return Err(error("Too many object fields".to_string()));
+                    }
+                    for ((field, element), table) in
+                        fields.iter_mut().zip(elements.iter()).zip(OBJECT_TABLES)
+                    {
+                        *field = self.integer(element, Some(table))?;
+                    }
+                }
+                Ok(Object {
+                    enemy_ally: fields[0],
+                    general: fields[1],
+                    race: fields[2],
+                    class: fields[3],
+                    specific: fields[4],
+                    gender: fields[5],
+                    alignment: fields[6],
+                    ..self.empty_object()
+                })
+            }
+            // Identifiers such as NearestEnemyOf(Myself), stored innermost first
+            ArgumentKind::Symbols(names) if names.len() == 1 => {
+                let mut object = self.empty_object();
+                object.identifiers[0] = self
+                    .identifier_value(&names[0])
+                    .ok_or_else(|| error(format!("Unknown object {}", names[0])))?;
+                Ok(object)
+            }
+            ArgumentKind::Call(name, arguments) => {
+                let value = self
+                    .identifier_value(&name)
+                    .ok_or_else(|| error(format!("Unknown object {name}")))?;
+                let [inner] = <[Argument; 1]>::try_from(arguments)
+                    .map_err(|_| error(format!("{name} expects a single object")))?;
+                let mut object = self.object(inner)?;
+                let slot = object
+                    .identifiers
+                    .iter_mut()
+                    .find(|identifier| **identifier == 0)
+                    .ok_or_else(|| error("Objects can only be nested five deep".to_string()))?;
+                *slot = value;
+                Ok(object)
+            }
+            kind => Err(error(format!("Expected an object, found {kind:?}"))),
+        }
+    }
+
+    // [ANYONE], the enhanced editions also store an empty rectangle
+    fn empty_object(&self) -> Object {
+        Object {
+            rectangle: self.symbols.enhanced_edition.then_some(NO_RECTANGLE),
+            ..Default::default()
+        }
+    }
+
+    fn identifier_value(&self, name: &str) -> Option<i32> {
+        self.symbols.value("OBJECT", name).map(|value| value as i32)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Values {
+    integers: [i32; 3],
+    strings: [String; 2],
+    objects: Vec<Object>,
+    point: Option<[i32; 2]>,
+}
+
+// Overloaded names are told apart by their number of arguments
+fn find_signature<'a>(
+    signatures: &'a [Signature],
+    name: &str,
+    argument_count: usize,
+) -> Option<&'a Signature> {
+    let mut matching = signatures
+        .iter()
+        .filter(|signature| signature.name.eq_ignore_ascii_case(name))
+        .peekable();
+    let first = *matching.peek()?;
+    Some(
+        matching
+            .find(|signature| {
+                signature
+                    .parameters
+                    .iter()
+                    .filter(|parameter| parameter.kind != ParameterKind::Action)
+                    .count()
+                    == argument_count
+            })
+            .unwrap_or(first),
+    )
+}
+
+// OR counts in source count source triggers, compiled counts include expanded overrides
+fn flatten_conditions(conditions: Vec<Condition>) -> Result<Vec<Trigger>, CompileError> {
+    let expanded: Vec<usize> = conditions
+        .iter()
+        .map(|condition| condition.triggers.len())
+        .collect();
+    let mut out = vec![];
+    for (i, condition) in conditions.into_iter().enumerate() {
+        let mut triggers = condition.triggers;
+        if let Some((count, token)) = condition.or_count {
+            let covered = expanded.get(i + 1..i + 1 + count).ok_or_else(|| {
+                Parser::error(&token, format!("OR({count}) is longer than the conditions"))
+            })?;
+            triggers[0].integer_1 = covered.iter().sum::<usize>() as i32;
+        }
+        out.extend(triggers);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ids::{Ids, IdsRegistry},
+        model::Model,
+    };
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    fn symbols() -> Symbols {
+        let tables = [
+            (
+                "TRIGGER",
+                "0x400F Global(S:Name*,S:Area*,I:Value*)
+0x4010 GlobalLT(S:Name*,S:Area*,I:Value*)
+0x401C HappinessGT(O:Object*,I:Value*)
+0x401D HappinessLT(O:Object*,I:Value*)
+0x402F Name(S:Name*,O:Object*)
+0x4040 Range(O:Object*,I:Range*)
+0x4043 InParty(O:Object*)
+0x404D AreaCheck(S:ResRef*)
+0x4089 OR(I:OrCount*)
+0x40A1 Class(O:Object*,I:Class*Class)
+0x40E0 NextTriggerObject(O:Object*)
+0x40E6 Switch(S:Global*,S:Area*)
+0x4106 IsOverMe(O:Object*)",
+            ),
+            (
+                "ACTION",
+                "1 ActionOverride(O:Actor*,A:Action*)
+3 Attack(O:Target*)
+30 SetGlobal(S:Name*,S:Area*,I:Value*)
+63 Wait(I:Time*)
+82 ChangeAIScript(S:ScriptFile*,I:Level*ScrLev)
+107 ChangeEnemyAlly(O:Object*,I:Value*EA)
+108 ChangeSpecifics(O:Object*,I:Value*Specific)
+137 Dialogue(O:Object*)
+145 EscapeArea()
+168 VerbalConstant(O:Object*,I:Constant*SoundOff)
+237 SaveLocation(S:Area*,S:Global*,P:Point*)
+238 SaveObjectLocation(S:Area*,S:Global*,O:Object*)
+255 StartDialogueNoSet(O:Object*)
+262 EscapeAreaMove(S:Area*,I:X*,I:Y*,I:Face*DIR)",
+            ),
+            ("OBJECT", "1 Myself\n2 Player1\n14 NearestEnemyOf"),
+            ("EA", "IDS V1.0\n2 PC\n128 NEUTRAL\n255 ENEMY"),
+            ("SPECIFIC", "4 ALLIES"),
+            (
+                "CLASS",
+                "20 MONK\n202 MAGE_ALL\n203 FIGHTER_ALL\n204 CLERIC_ALL\n205 THIEF_ALL\n206 BARD_ALL\n208 DRUID_ALL\n14 CLERIC_MAGE\n17 FIGHTER_MAGE_CLERIC",
+            ),
+            (
+                "SCRLEV",
+                "0 OVERRIDE\n1 AREA\n2 SPECIFICS\n4 CLASS\n5 RACE\n6 GENERAL\n7 DEFAULT",
+            ),
+            ("SOUNDOFF", "36 UNHAPPY_BREAKING_POINT"),
+            ("DIR", "0 S\n2 SW\n4 W\n6 NW\n8 N\n10 NE\n12 E\n14 SE"),
+        ];
+        let ids = tables
+            .into_iter()
+            .map(|(name, data)| (name, Ids::new(data.as_bytes())))
+            .collect::<IdsRegistry>();
+        Symbols::new(ids)
+    }
+
+    #[test]
+    fn compile() -> Result<(), Box<dyn Error>> {
+        let symbols = symbols();
+        let source = fs::read_to_string("fixtures/bdparty.baf")?;
+        let script = Script::compile(&source, &symbols)?;
+        assert_eq!(script.blocks.len(), 43);
+
+        // !InParty(Myself) Global("bd_joined","locals",1) AreaCheck("bd4700") ...
+        let first = &script.blocks[0];
+        assert!(first.conditions[0].is_negated());
+        assert_eq!(first.conditions[0].object.identifiers, [1, 0, 0, 0, 0]);
+        assert_eq!(first.conditions[1].string_1, "localsbd_joined");
+        assert_eq!(first.conditions[1].integer_1, 1);
+        assert_eq!(first.responses[0].weight, 100);
+
+        // OR(2) !Range("ff_camp",999) !TriggerOverride("ff_camp",IsOverMe("baeloth"))
+        let block = &script.blocks[4];
+        let or = &block.conditions[3];
+        assert_eq!((or.id, or.integer_1), (0x4089, 3));
+        assert_eq!(block.conditions[5].id, 0x40E0);
+        assert_eq!(block.conditions[5].object.name, "ff_camp");
+        assert!(block.conditions[6].is_negated());
+        let save_location = &script.blocks[5].responses[0].actions[1];
+        assert_eq!(save_location.string_1, "LOCALSbd_default_loc");
+        assert_eq!(save_location.point, [135, 3575]);
+
+        // Compiled scripts survive a trip through bcs and back through source
+        let bytes = script.to_bytes();
+        assert_eq!(Script::parse(&bytes)?, script);
+        let source = script.decompile(&symbols)?;
+        assert_eq!(Script::compile(&source, &symbols)?, script);
+        Ok(())
+    }
+
+    // The same block as compiled by the enhanced edition games, every trigger carries a point
+    // and every object the empty rectangle
+    const ENHANCED_EDITION_SCRIPT: &str = "SC
+CR
+CO
+TR
+16399 1 1 0 0 [0,0] \"LOCALSSEEN\" \"\" OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+TR
+CO
+RS
+RE
+100AC
+30OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+1 0 0 0 0\"LOCALSSEEN\" \"\" AC
+AC
+3OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"Imoen\"OB
+OB
+0 0 0 0 0 0 0 1 14 0 0 0 [-1.-1.-1.-1] \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+0 0 0 0 0\"\" \"\" AC
+RE
+RS
+CR
+SC
+";
+
+    #[test]
+    fn compile_enhanced_edition() -> Result<(), Box<dyn Error>> {
+        let symbols = Symbols {
+            enhanced_edition: true,
+            ..symbols()
+        };
+        let source = "IF
+\t!Global(\"SEEN\",\"LOCALS\",1)
+THEN
+\tRESPONSE #100
+\t\tSetGlobal(\"SEEN\",\"LOCALS\",1)
+\t\tActionOverride(\"Imoen\",Attack(NearestEnemyOf(Myself)))
+END
+";
+        let script = Script::compile(source, &symbols)?;
+        assert_eq!(
+            String::from_utf8(script.to_bytes())?,
+            ENHANCED_EDITION_SCRIPT
+        );
+
+        let game_script = Script::parse(ENHANCED_EDITION_SCRIPT.as_bytes())?;
+        assert_eq!(script, game_script);
+        assert_eq!(
+            game_script.decompile(&symbols)?.trim_end(),
+            source.trim_end()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compile_errors() {
+        let symbols = symbols();
+        let error = Script::compile("IF\n\tSee([ENEMY])\nTHEN\nEND\n", &symbols).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+        assert_eq!(error.message, "Unknown trigger See");
+
+        let source = "IF\n\tTrue()\nTHEN\n\tRESPONSE #100\n\t\tChangeEnemyAlly(Myself,FRIEND)\nEND";
+        let error = Script::compile(source, &symbols).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 2: Unknown trigger True");
+
+        let source = "IF\nTHEN\n\tRESPONSE #100\n\t\tChangeEnemyAlly(Myself,FRIEND)\nEND";
+        let error = Script::compile(source, &symbols).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 26));
+
+        let error = Script::compile("IF\nTHEN\n\tRESPONSE #100\n\t\tWait(\"5\")\nEND", &symbols)
+            .unwrap_err();
+        assert_eq!(error.message, "Expected a number, found String(\"5\")");
+
+        // Numbers that do not fit their field are rejected rather than wrapped
+        let error = Script::compile("IF\nTHEN\n\tRESPONSE #-1\n\t\tEscapeArea()\nEND", &symbols)
+            .unwrap_err();
+        assert_eq!((error.line, error.column), (3, 12));
+        assert_eq!(error.message, "Weight -1 does not fit in a u32");
+        let source = "IF\nTHEN\n\tRESPONSE #100\n\t\tWait(2147483648)\nEND";
+        let error = Script::compile(source, &symbols).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 8));
+        assert_eq!(error.message, "Number 2147483648 does not fit in an i32");
+        let source = "IF\nTHEN\n\tRESPONSE #100\n\t\tSaveLocation(\"\",\"a\",[0.-4294967296])\nEND";
+        let error = Script::compile(source, &symbols).unwrap_err();
+        assert_eq!((error.line, error.column), (4, 26));
+    }
+}
@@ -0,0 +1,369 @@
+use std::{error::Error, fmt::Write};
+
+use super::{
+    Action, Object, Script, Trigger,
+    compile::{ACTION_OVERRIDE, NEXT_TRIGGER_OBJECT, TRIGGER_OVERRIDE},
+    symbols::{AREA_LENGTH, OBJECT_TABLES, ParameterKind, Signature, StringSlot, Symbols},
+};
+
+impl Script {
+    // Renders the script as source in the same layout WeiDU uses, one tab per indent
+    pub fn decompile(&self, symbols: &Symbols) -> Result<String, Box<dyn Error>> {
+        let mut out = String::new();
+        for block in &self.blocks {
+            out.push_str("IF\n");
+            let lines = condition_lines(&block.conditions, symbols)?;
+            // OR counts compiled triggers, which TriggerOverride takes two of
+            let mut or_remaining: usize = 0;
+            for (i, line) in lines.iter().enumerate() {
+                let indent = if or_remaining > 0 { "\t\t" } else { "\t" };
+                or_remaining = or_remaining.saturating_sub(line.triggers);
+                match line.or_count {
+                    Some(count) => {
+                        or_remaining = count;
+                        let mut covered = 0;
+                        let lines = lines[i + 1..]
+                            .iter()
+                            .take_while(|line| {
+                                covered += line.triggers;
+                                covered <= count
+                            })
+                            .count();
+                        writeln!(out, "{indent}{}({lines})", line.text)?;
+                    }
+                    None => writeln!(out, "{indent}{}", line.text)?,
+                }
+            }
+            out.push_str("THEN\n");
+            for response in &block.responses {
+                writeln!(out, "\tRESPONSE #{}", response.weight)?;
+                for action in &response.actions {
+                    writeln!(out, "\t\t{}", decompile_action(action, symbols)?)?;
+                }
+            }
+            out.push_str("END\n\n");
+        }
+        Ok(out)
+    }
+}
+
+struct ConditionLine {
+    text: String,
+    // How many compiled triggers the line stands for
+    triggers: usize,
+    or_count: Option<usize>,
+}
+
+fn condition_lines(
+    conditions: &[Trigger],
+    symbols: &Symbols,
+) -> Result<Vec<ConditionLine>, Box<dyn Error>> {
+    let signature = |trigger: &Trigger| {
+        symbols
+            .trigger(trigger.id as i64)
+            .ok_or(format!("Unknown trigger {:#x}", trigger.id))
+    };
+    let mut lines = vec![];
+    let mut triggers = conditions.iter().peekable();
+    while let Some(trigger) = triggers.next() {
+        let first = signature(trigger)?;
+        if first.name.eq_ignore_ascii_case("OR") {
+            lines.push(ConditionLine {
+                text: first.name.clone(),
+                triggers: 1,
+                or_count: Some(trigger.integer_1.max(0) as usize),
+            });
+            continue;
+        }
+        // NextTriggerObject followed by a trigger is written as TriggerOverride
+        if first.name.eq_ignore_ascii_case(NEXT_TRIGGER_OBJECT)
+            && let Some(next) = triggers.next_if(|next| {
+                signature(next).is_ok_and(|next| !next.name.eq_ignore_ascii_case("OR"))
+            })
+        {
+            let inner = decompile_trigger(next, signature(next)?, symbols);
+            let (negation, inner) = inner
+                .strip_prefix('!')
+                .map_or(("", inner.as_str()), |inner| ("!", inner));
+            lines.push(ConditionLine {
+                text: format!(
+                    "{negation}{TRIGGER_OVERRIDE}({},{inner})",
+                    decompile_object(&trigger.object, symbols)
+                ),
+                triggers: 2,
+                or_count: None,
+            });
+            continue;
+        }
+        lines.push(ConditionLine {
+            text: decompile_trigger(trigger, first, symbols),
+            triggers: 1,
+            or_count: None,
+        });
+    }
+    Ok(lines)
+}
+
+fn decompile_trigger(trigger: &Trigger, signature: &Signature, symbols: &Symbols) -> String {
+    let integers = [trigger.integer_1, trigger.integer_2, trigger.integer_3];
+    let strings = [trigger.string_1.as_str(), trigger.string_2.as_str()];
+    let arguments = Arguments {
+        integers: &integers,
+        strings: &strings,
+        objects: &[&trigger.object],
+        point: trigger.point.unwrap_or_default(),
+    };
+    let negation = if trigger.is_negated() { "!" } else { "" };
+    format!(
+        "{negation}{}({})",
+        signature.name,
+        arguments.render(signature, symbols)
+    )
+}
+
+fn decompile_action(action: &Action, symbols: &Symbols) -> Result<String, Box<dyn Error>> {
+    let signature = symbols
+        .action(action.id as i64)
+        .ok_or(format!("Unknown action {}", action.id))?;
+    let integers = [action.integer_1, action.integer_2, action.integer_3];
+    let strings = [action.string_1.as_str(), action.string_2.as_str()];
+    let arguments = Arguments {
+        integers: &integers,
+        strings: &strings,
+        objects: &[&action.object_1, &action.object_2],
+        point: action.point,
+    };
+    let out = format!(
+        "{}({})",
+        signature.name,
+        arguments.render(signature, symbols)
+    );
+    // The actor is only set when another creature is told to perform the action
+    if action.actor.is_empty() {
+        return Ok(out);
+    }
+    Ok(format!(
+        "{ACTION_OVERRIDE}({},{out})",
+        decompile_object(&action.actor, symbols)
+    ))
+}
+
+struct Arguments<'a> {
+    integers: &'a [i32],
+    strings: &'a [&'a str],
+    objects: &'a [&'a Object],
+    point: [i32; 2],
+}
+
+impl Arguments<'_> {
+    fn render(&self, signature: &Signature, symbols: &Symbols) -> String {
+        let mut string_slots = signature.string_slots().into_iter();
+        let (mut integers, mut objects) = (self.integers.iter(), self.objects.iter());
+        let mut out = vec![];
+        for parameter in &signature.parameters {
+            let argument = match parameter.kind {
+                ParameterKind::Integer => {
+                    let value = *integers.next().unwrap_or(&0);
+                    match &parameter.table {
+                        Some(table) => symbols
+                            .symbol(table, value as i64)
+                            .map(str::to_string)
+                            .unwrap_or(value.to_string()),
+                        None => value.to_string(),
+                    }
+                }
+                ParameterKind::String => {
+                    let string = |slot: usize| self.strings.get(slot).copied().unwrap_or("");
+                    let split = |slot: usize| {
+                        let string = string(slot);
+                        // Scopes cut through a character are not valid, so keep it whole
+                        string.split_at_checked(AREA_LENGTH).unwrap_or((string, ""))
+                    };
+                    let value = match string_slots.next() {
+                        Some(StringSlot::Whole(slot)) => string(slot),
+                        Some(StringSlot::Name(slot)) => split(slot).1,
+                        Some(StringSlot::Area(slot)) => split(slot).0,
+                        None => "",
+                    };
+                    format!("\"{value}\"")
+                }
+                ParameterKind::Object => objects
+                    .next()
+                    .map(|object| decompile_object(object, symbols))
+                    .unwrap_or("[ANYONE]".to_string()),
+                ParameterKind::Point => format!("[{}.{}]", self.point[0], self.point[1]),
+                // Only used by ActionOverride, which is rebuilt from the actor
+                ParameterKind::Action => continue,
+            };
+            out.push(argument);
+        }
+        out.join(",")
+    }
+}
+
+fn decompile_object(object: &Object, symbols: &Symbols) -> String {
+    if !object.name.is_empty() {
+        return format!("\"{}\"", object.name);
+    }
+    let fields = [
+        object.enemy_ally,
+        object.general,
+        object.race,
+        object.class,
+        object.specific,
+        object.gender,
+        object.alignment,
+    ];
+    let mut out = match fields.iter().rposition(|field| *field != 0) {
+        Some(last) => {
+            let names: Vec<String> = fields[..=last]
+                .iter()
+                .zip(OBJECT_TABLES)
+                .map(|(value, table)| match value {
+                    0 => "0".to_string(),
+                    value => symbols
+                        .symbol(table, *value as i64)
+                        .map(str::to_string)
+                        .unwrap_or(value.to_string()),
+                })
+                .collect();
+            Some(format!("[{}]", names.join(".")))
+        }
+        None => None,
+    };
+    // Identifiers such as LastSeenBy wrap the object, innermost first
+    for identifier in object.identifiers.iter().filter(|value| **value != 0) {
+        let name = symbols
+            .symbol("OBJECT", *identifier as i64)
+            .map(str::to_string)
+            .unwrap_or(identifier.to_string());
+        out = Some(match out {
+            Some(inner) => format!("{name}({inner})"),
+            None => name,
+        });
+    }
+    out.unwrap_or("[ANYONE]".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ids::{Ids, IdsRegistry},
+        model::Model,
+        script::{ConditionResponse, Response},
+    };
+    use pretty_assertions::assert_eq;
+
+    fn symbols() -> Symbols {
+        let tables = [
+            (
+                "TRIGGER",
+                "0x4017 See(O:Object*)\n0x4039 Global(S:Name*,S:Area*,I:Value*)\n\
+                 0x4089 OR(I:OrCount*)\n0x403C Class(O:Object*,I:Class*Class)\n\
+                 0x40E0 NextTriggerObject(O:Object*)",
+            ),
+            (
+                "ACTION",
+                "1 ActionOverride(O:Actor*,A:Action*)\n3 Attack(O:Target*)\n\
+                 30 SetGlobal(S:Name*,S:Area*,I:Value*)\n49 MoveViewPoint(P:Target*,I:ScrollSpeed*Scroll)\n\
+                 285 SaveLocation(S:Area*,S:Global*,P:Point*)",
+            ),
+            ("OBJECT", "1 Myself\n14 NearestEnemyOf"),
+            ("EA", "IDS V1.0\n2\n2 PC\n255 ENEMY"),
+            ("CLASS", "1 MAGE\n2 FIGHTER"),
+            ("SCROLL", "0 INSTANT\n4 NORMAL"),
+        ];
+        let ids = tables
+            .into_iter()
+            .map(|(name, data)| (name, Ids::new(data.as_bytes())))
+            .collect::<IdsRegistry>();
+        Symbols::new(ids)
+    }
+
+    #[test]
+    fn decompile() -> Result<(), Box<dyn Error>> {
+        let object = |enemy_ally, identifiers, name: &str| Object {
+            enemy_ally,
+            identifiers,
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let trigger = |id, integer_1, flags, string_1: &str, object| Trigger {
+            id,
+            integer_1,
+            flags,
+            string_1: string_1.to_string(),
+            object,
+            ..Default::default()
+        };
+        let script = Script {
+            blocks: vec![ConditionResponse {
+                conditions: vec![
+                    trigger(0x4017, 0, 0, "", object(255, [0; 5], "")),
+                    trigger(0x4039, 1, 1, "LOCALSSEEN", Object::default()),
+                    // Counts the two triggers TriggerOverride is compiled to
+                    trigger(0x4089, 4, 0, "", Object::default()),
+                    trigger(0x403C, 1, 0, "", object(0, [1, 0, 0, 0, 0], "")),
+                    trigger(0x40E0, 0, 0, "", object(0, [0; 5], "Imoen")),
+                    trigger(0x4017, 0, 1, "", object(255, [0; 5], "")),
+                    trigger(0x403C, 7, 0, "", object(0, [0; 5], "Imoen")),
+                    trigger(0x4039, 0, 0, "LOCAL\u{e9}X", Object::default()),
+                ],
+                responses: vec![Response {
+                    weight: 100,
+                    actions: vec![
+                        Action {
+                            id: 30,
+                            integer_1: 1,
+                            string_1: "LOCALSSEEN".to_string(),
+                            ..Default::default()
+                        },
+                        Action {
+                            id: 3,
+                            actor: object(0, [0; 5], "Imoen"),
+                            object_1: object(0, [1, 14, 0, 0, 0], ""),
+                            ..Default::default()
+                        },
+                        Action {
+                            id: 49,
+                            point: [100, -20],
+                            integer_1: 4,
+                            ..Default::default()
+                        },
+                        Action {
+                            id: 3,
+                            ..Default::default()
+                        },
+                        Action {
+                            id: 285,
+                            point: [10, 20],
+                            string_1: "LOCALSSPOT".to_string(),
+                            ..Default::default()
+                        },
+                    ],
+                }],
+            }],
+        };
+        let expected = "IF
+\tSee([ENEMY])
+\t!Global(\"SEEN\",\"LOCALS\",1)
+\tOR(3)
+\t\tClass(Myself,MAGE)
+\t\t!TriggerOverride(\"Imoen\",See([ENEMY]))
+\t\tClass(\"Imoen\",7)
+\tGlobal(\"\",\"LOCAL\u{e9}X\",0)
+THEN
+\tRESPONSE #100
+\t\tSetGlobal(\"SEEN\",\"LOCALS\",1)
+\t\tActionOverride(\"Imoen\",Attack(NearestEnemyOf(Myself)))
+\t\tMoveViewPoint([100.-20],NORMAL)
+\t\tAttack([ANYONE])
+\t\tSaveLocation(\"LOCALS\",\"SPOT\",[10.20])
+END
+
+";
+        assert_eq!(script.decompile(&symbols())?, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,576 @@
+use std::{error::Error, fmt::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::Model;
+
+mod compile;
+mod decompile;
+pub mod symbols;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Script {
+    pub blocks: Vec<ConditionResponse>,
+}
+
+impl Model for Script {
+    fn new(buffer: &[u8]) -> Self {
+        match Script::parse(buffer) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    // Written in the same layout as the original compiler, so unmodified scripts round trip
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = String::from("SC\n");
+        for block in &self.blocks {
+            block.write(&mut out);
+        }
+        out.push_str("SC\n");
+        encode_latin1(&out)
+    }
+}
+
+impl Script {
+    pub fn parse(buffer: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut parser = Parser {
+            tokens: tokenize(buffer)?,
+            position: 0,
+        };
+        parser.keyword("SC")?;
+        let mut blocks = vec![];
+        while !parser.peek_keyword("SC") {
+            blocks.push(ConditionResponse::parse(&mut parser)?);
+        }
+        parser.keyword("SC")?;
+        if parser.position != parser.tokens.len() {
+            return Err("Unexpected data after the end of the script".into());
+        }
+        Ok(Script { blocks })
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm#bcs_CR
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConditionResponse {
+    pub conditions: Vec<Trigger>,
+    pub responses: Vec<Response>,
+}
+
+impl ConditionResponse {
+    fn parse(parser: &mut Parser) -> Result<Self, Box<dyn Error>> {
+        parser.keyword("CR")?;
+        parser.keyword("CO")?;
+        let mut conditions = vec![];
+        while !parser.peek_keyword("CO") {
+            conditions.push(Trigger::parse(parser)?);
+        }
+        parser.keyword("CO")?;
+        parser.keyword("RS")?;
+        let mut responses = vec![];
+        while !parser.peek_keyword("RS") {
+            responses.push(Response::parse(parser)?);
+        }
+        parser.keyword("RS")?;
+        parser.keyword("CR")?;
+        Ok(ConditionResponse {
+            conditions,
+            responses,
+        })
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str("CR\nCO\n");
+        for trigger in &self.conditions {
+            trigger.write(out);
+        }
+        out.push_str("CO\nRS\n");
+        for response in &self.responses {
+            response.write(out);
+        }
+        out.push_str("RS\nCR\n");
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm#bcs_TR
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trigger {
+    // The value from TRIGGER.IDS
+    pub id: u32,
+    pub integer_1: i32,
+    // bit 0 = negated
+    pub flags: u32,
+    pub integer_2: i32,
+    pub integer_3: i32,
+    // Only present in the enhanced editions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub point: Option<[i32; 2]>,
+    pub string_1: String,
+    pub string_2: String,
+    pub object: Object,
+}
+
+impl Trigger {
+    fn parse(parser: &mut Parser) -> Result<Self, Box<dyn Error>> {
+        parser.keyword("TR")?;
+        let id = parser.unsigned()?;
+        let integer_1 = parser.integer()?;
+        let flags = parser.unsigned()?;
+        let integer_2 = parser.integer()?;
+        let integer_3 = parser.integer()?;
+        let point = match parser.peek() {
+            Some(Token::Point(x, y)) => {
+                let point = [*x, *y];
+                parser.position += 1;
+                Some(point)
+            }
+            _ => None,
+        };
+        let string_1 = parser.string()?;
+        let string_2 = parser.string()?;
+        let object = Object::parse(parser)?;
+        parser.keyword("TR")?;
+        Ok(Trigger {
+            id,
+            integer_1,
+            flags,
+            integer_2,
+            integer_3,
+            point,
+            string_1,
+            string_2,
+            object,
+        })
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.flags & 1 == 1
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "TR\n{} {} {} {} {} ",
+            self.id, self.integer_1, self.flags, self.integer_2, self.integer_3
+        );
+        if let Some([x, y]) = self.point {
+            let _ = write!(out, "[{x},{y}] ");
+        }
+        let _ = write!(out, "\"{}\" \"{}\" ", self.string_1, self.string_2);
+        self.object.write(out);
+        out.push_str("TR\n");
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm#bcs_RE
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    // Chance of this response being picked, relative to the others in the block
+    pub weight: u32,
+    pub actions: Vec<Action>,
+}
+
+impl Response {
+    fn parse(parser: &mut Parser) -> Result<Self, Box<dyn Error>> {
+        parser.keyword("RE")?;
+        let weight = parser.unsigned()?;
+        let mut actions = vec![];
+        while !parser.peek_keyword("RE") {
+            actions.push(Action::parse(parser)?);
+        }
+        parser.keyword("RE")?;
+        Ok(Response { weight, actions })
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = write!(out, "RE\n{}", self.weight);
+        for action in &self.actions {
+            action.write(out);
+        }
+        out.push_str("RE\n");
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm#bcs_AC
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    // The value from ACTION.IDS
+    pub id: u32,
+    // The actor performing the action, used by ActionOverride
+    pub actor: Object,
+    pub object_1: Object,
+    pub object_2: Object,
+    pub integer_1: i32,
+    pub point: [i32; 2],
+    pub integer_2: i32,
+    pub integer_3: i32,
+    pub string_1: String,
+    pub string_2: String,
+}
+
+impl Action {
+    fn parse(parser: &mut Parser) -> Result<Self, Box<dyn Error>> {
+        parser.keyword("AC")?;
+        let id = parser.unsigned()?;
+        let actor = Object::parse(parser)?;
+        let object_1 = Object::parse(parser)?;
+        let object_2 = Object::parse(parser)?;
+        let integer_1 = parser.integer()?;
+        let point = [parser.integer()?, parser.integer()?];
+        let integer_2 = parser.integer()?;
+        let integer_3 = parser.integer()?;
+        let string_1 = parser.string()?;
+        let string_2 = parser.string()?;
+        parser.keyword("AC")?;
+        Ok(Action {
+            id,
+            actor,
+            object_1,
+            object_2,
+            integer_1,
+            point,
+            integer_2,
+            integer_3,
+            string_1,
+            string_2,
+        })
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = write!(out, "AC\n{}", self.id);
+        self.actor.write(out);
+        self.object_1.write(out);
+        self.object_2.write(out);
+        let _ = writeln!(
+            out,
+            "{} {} {} {} {}\"{}\" \"{}\" AC",
+            self.integer_1,
+            self.point[0],
+            self.point[1],
+            self.integer_2,
+            self.integer_3,
+            self.string_1,
+            self.string_2
+        );
+    }
+}
+
+// The rectangle the enhanced editions store for objects that are not bound to a region
+pub const NO_RECTANGLE: [i32; 4] = [-1, -1, -1, -1];
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/bcs.htm#bcs_OB
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    pub enemy_ally: i32,
+    pub general: i32,
+    pub race: i32,
+    pub class: i32,
+    pub specific: i32,
+    pub gender: i32,
+    pub alignment: i32,
+    // Values from OBJECT.IDS such as LastSeenBy or Myself, innermost first
+    pub identifiers: [i32; 5],
+    // Only present in the enhanced editions
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub rectangle: Option<[i32; 4]>,
+    pub name: String,
+}
+
+impl Object {
+    fn parse(parser: &mut Parser) -> Result<Self, Box<dyn Error>> {
+        parser.keyword("OB")?;
+        let mut values = [0; 12];
+        for value in values.iter_mut() {
+            *value = parser.integer()?;
+        }
+        let rectangle = match parser.peek() {
+            Some(Token::Rectangle(rectangle)) => {
+                let rectangle = *rectangle;
+                parser.position += 1;
+                Some(rectangle)
+            }
+            _ => None,
+        };
+        let name = parser.string()?;
+        parser.keyword("OB")?;
+        Ok(Object {
+            enemy_ally: values[0],
+            general: values[1],
+            race: values[2],
+            class: values[3],
+            specific: values[4],
+            gender: values[5],
+            alignment: values[6],
+            identifiers: [values[7], values[8], values[9], values[10], values[11]],
+            rectangle,
+            name,
+        })
+    }
+
+    // True when nothing identifies the object, written as [ANYONE] in source
+    pub fn is_empty(&self) -> bool {
+        let rectangle = self
+            .rectangle
+            .filter(|rectangle| *rectangle == NO_RECTANGLE);
+        *self
+            == Object {
+                rectangle,
+                ..Default::default()
+            }
+    }
+
+    fn write(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "OB\n{} {} {} {} {} {} {} ",
+            self.enemy_ally,
+            self.general,
+            self.race,
+            self.class,
+            self.specific,
+            self.gender,
+            self.alignment
+        );
+        for identifier in self.identifiers {
+            let _ = write!(out, "{identifier} ");
+        }
+        if let Some([left, top, right, bottom]) = self.rectangle {
+            let _ = write!(out, "[{left}.{top}.{right}.{bottom}] ");
+        }
+        let _ = writeln!(out, "\"{}\"OB", self.name);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Keyword(String),
+    Integer(i64),
+    String(String),
+    Point(i32, i32),
+    Rectangle([i32; 4]),
+}
+
+fn tokenize(buffer: &[u8]) -> Result<Vec<Token>, Box<dyn Error>> {
+    let mut tokens = vec![];
+    let mut position = 0;
+    while let Some(byte) = buffer.get(position) {
+        let start = position;
+        match byte {
+            byte if byte.is_ascii_whitespace() => {
+                position += 1;
+            }
+            b'"' => {
+                let end = buffer[start + 1..]
+                    .iter()
+                    .position(|byte| *byte == b'"')
+                    .ok_or(format!("Unterminated string at byte {start}"))?;
+                position = start + end + 2;
+                let string = decode_latin1(&buffer[start + 1..position - 1]);
+                tokens.push(Token::String(string));
+            }
+            b'[' => {
+                let end = buffer[start..]
+                    .iter()
+                    .position(|byte| *byte == b']')
+                    .ok_or(format!("Unterminated point at byte {start}"))?;
+                position = start + end + 1;
+                let contents = decode_latin1(&buffer[start + 1..position - 1]);
+                let values = contents
+                    .split([',', '.'])
+                    .map(|value| value.trim().parse::<i32>())
+                    .collect::<Result<Vec<i32>, _>>()
+                    .map_err(|err| format!("Invalid point at byte {start}: {err}"))?;
+                tokens.push(match values.as_slice() {
+                    [x, y] => Token::Point(*x, *y),
+                    [left, top, right, bottom] => Token::Rectangle([*left, *top, *right, *bottom]),
+                    _ => return Err(format!("Invalid point at byte {start}").into()),
+                });
+            }
+            b'-' | b'0'..=b'9' => {
+                position += 1;
+                while buffer.get(position).is_some_and(u8::is_ascii_digit) {
+                    position += 1;
+                }
+                let number = decode_latin1(&buffer[start..position]);
+                let value = number
+                    .parse::<i64>()
+                    .map_err(|err| format!("Invalid number at byte {start}: {err}"))?;
+                tokens.push(Token::Integer(value));
+            }
+            b'A'..=b'Z' => {
+                position += 2;
+                let keyword = buffer
+                    .get(start..position)
+                    .ok_or(format!("Truncated keyword at byte {start}"))?;
+                tokens.push(Token::Keyword(decode_latin1(keyword)));
+            }
+            _ => return Err(format!("Unexpected character at byte {start}").into()),
+        }
+    }
+    Ok(tokens)
+}
+
+// Scripts are stored in the game's single byte code page, every byte maps to the character with
+// the same value so unmodified strings are written back byte for byte
+fn decode_latin1(bytes: &[u8]) -> String {
+    bytes.iter().copied().map(char::from).collect()
+}
+
+// Characters outside of latin-1, which only come from edited source, are kept as utf-8
+fn encode_latin1(text: &str) -> Vec<u8> {
+    let mut out = vec![];
+    for character in text.chars() {
+        match u8::try_from(character) {
+            Ok(byte) => out.push(byte),
+            Err(_) => out.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    out
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<&Token, Box<dyn Error>> {
+        self.position += 1;
+        self.tokens
+            .get(self.position - 1)
+            .ok_or("Unexpected end of script".into())
+    }
+
+    fn peek_keyword(&self, expected: &str) -> bool {
+        matches!(self.peek(), Some(Token::Keyword(keyword)) if keyword == expected)
+    }
+
+    fn keyword(&mut self, expected: &str) -> Result<(), Box<dyn Error>> {
+        match self.next()? {
+            Token::Keyword(keyword) if keyword == expected => Ok(()),
+            token => Err(format!("Expected {expected}, found {token:?}").into()),
+        }
+    }
+
+    fn integer(&mut self) -> Result<i32, Box<dyn Error>> {
+        match self.next()? {
+            Token::Integer(value) => i32::try_from(*value)
+                .map_err(|_| format!("Number {value} does not fit in an i32").into()),
+            token => Err(format!("Expected a number, found {token:?}").into()),
+        }
+    }
+
+    // Ids, flags and weights are stored unsigned
+    fn unsigned(&mut self) -> Result<u32, Box<dyn Error>> {
+        match self.next()? {
+            Token::Integer(value) => u32::try_from(*value)
+                .map_err(|_| format!("Number {value} does not fit in a u32").into()),
+            token => Err(format!("Expected a number, found {token:?}").into()),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, Box<dyn Error>> {
+        match self.next()? {
+            Token::String(value) => Ok(value.clone()),
+            token => Err(format!("Expected a string, found {token:?}").into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // IF See([ENEMY]) !Global("SEEN","LOCALS",1) THEN RESPONSE #100
+    //   SetGlobal("SEEN","LOCALS",1) ActionOverride("Imoen",Attack(NearestEnemyOf(Myself)))
+    // END
+    const SCRIPT: &str = "SC
+CR
+CO
+TR
+16407 0 0 0 0 \"\" \"\" OB
+255 0 0 0 0 0 0 0 0 0 0 0 \"\"OB
+TR
+TR
+16447 1 1 0 0 [0,0] \"LOCALSSEEN\" \"\" OB
+0 0 0 0 0 0 0 0 0 0 0 0 [-1.-1.-1.-1] \"\"OB
+TR
+CO
+RS
+RE
+100AC
+30OB
+0 0 0 0 0 0 0 0 0 0 0 0 \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 \"\"OB
+1 0 0 0 0\"LOCALSSEEN\" \"\" AC
+AC
+3OB
+0 0 0 0 0 0 0 0 0 0 0 0 \"Imoen\"OB
+OB
+0 0 0 0 0 0 0 1 14 0 0 0 \"\"OB
+OB
+0 0 0 0 0 0 0 0 0 0 0 0 \"\"OB
+0 0 0 0 0\"\" \"\" AC
+RE
+RS
+CR
+SC
+";
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn Error>> {
+        let script = Script::parse(SCRIPT.as_bytes())?;
+        let block = &script.blocks[0];
+        assert_eq!(block.conditions.len(), 2);
+        assert_eq!(block.conditions[0].object.enemy_ally, 255);
+        assert!(block.conditions[1].is_negated());
+        assert_eq!(block.conditions[1].point, Some([0, 0]));
+        assert_eq!(block.conditions[1].object.rectangle, Some([-1, -1, -1, -1]));
+        assert_eq!(block.responses[0].weight, 100);
+        let actions = &block.responses[0].actions;
+        assert_eq!(actions[1].actor.name, "Imoen");
+        assert_eq!(actions[1].object_1.identifiers, [1, 14, 0, 0, 0]);
+        assert!(actions[1].object_2.is_empty());
+
+        assert_eq!(String::from_utf8(script.to_bytes())?, SCRIPT);
+        assert!(Script::parse(b"SC\nCR\nSC\n").is_err());
+
+        // Strings outside of ascii are written back byte for byte, 0xE9 is not valid utf-8 alone
+        let bytes: Vec<u8> = SCRIPT
+            .replace("\"Imoen\"", "\"Imo\u{e9}n\"")
+            .chars()
+            .map(|character| character as u8)
+            .collect();
+        assert!(bytes.contains(&0xE9));
+        let script = Script::parse(&bytes)?;
+        assert_eq!(
+            script.blocks[0].responses[0].actions[1].actor.name,
+            "Imo\u{e9}n"
+        );
+        assert_eq!(script.to_bytes(), bytes);
+
+        // Values that do not fit their field are rejected rather than wrapped
+        assert!(Script::parse(SCRIPT.replace("100AC", "-1AC").as_bytes()).is_err());
+        assert!(
+            Script::parse(
+                SCRIPT
+                    .replace("\n1 0 0 0 0\"", "\n2147483648 0 0 0 0\"")
+                    .as_bytes()
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+}
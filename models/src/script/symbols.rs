@@ -0,0 +1,169 @@
+use crate::ids::IdsRegistry;
+
+// The tables used for the seven object fields, in the order they are stored
+pub const OBJECT_TABLES: [&str; 7] = [
+    "EA", "GENERAL", "RACE", "CLASS", "SPECIFIC", "GENDER", "ALIGN",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParameterKind {
+    Integer,
+    String,
+    Object,
+    Point,
+    Action,
+}
+
+// eg. I:Class*Class, a number looked up in CLASS.IDS
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub kind: ParameterKind,
+    pub name: String,
+    pub table: Option<String>,
+}
+
+// A trigger or action definition from TRIGGER.IDS or ACTION.IDS, eg. 0x4017 See(O:Object*)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub id: i64,
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl Signature {
+    pub fn parse(id: i64, definition: &str) -> Option<Self> {
+        let (name, rest) = definition.split_once('(')?;
+        let arguments = rest
+            .rsplit_once(')')
+            .map_or(rest, |(arguments, _)| arguments);
+        let parameters = arguments
+            .split(',')
+            .filter(|argument| !argument.trim().is_empty())
+            .map(|argument| {
+                let (kind, rest) = argument.trim().split_once(':')?;
+                let kind = match kind.trim().to_ascii_uppercase().as_str() {
+                    "I" => ParameterKind::Integer,
+                    "S" => ParameterKind::String,
+                    "O" => ParameterKind::Object,
+                    "P" => ParameterKind::Point,
+                    "A" => ParameterKind::Action,
+                    _ => return None,
+                };
+                let (name, table) = rest.split_once('*').unwrap_or((rest, ""));
+                Some(Parameter {
+                    kind,
+                    name: name.trim().to_string(),
+                    table: Some(table.trim().to_ascii_uppercase())
+                        .filter(|table| !table.is_empty()),
+                })
+            })
+            .collect::<Option<Vec<Parameter>>>()?;
+        Some(Signature {
+            id,
+            name: name.trim().to_string(),
+            parameters,
+        })
+    }
+
+    pub fn parameters_of(&self, kind: ParameterKind) -> impl Iterator<Item = &Parameter> {
+        self.parameters
+            .iter()
+            .filter(move |parameter| parameter.kind == kind)
+    }
+
+    // Where each string parameter is stored, an area is stored in front of the name next to it
+    // eg. Global("SEEN","LOCALS",1) and SaveLocation("LOCALS","SEEN",[0.0]) store "LOCALSSEEN"
+    pub fn string_slots(&self) -> Vec<StringSlot> {
+        let parameters: Vec<bool> = self
+            .parameters_of(ParameterKind::String)
+            .map(|parameter| parameter.name.to_ascii_lowercase().starts_with("area"))
+            .collect();
+        let mut slots: Vec<StringSlot> = vec![];
+        // An area waiting for the name after it
+        let mut pending = None;
+        let mut next = 0;
+        for (i, is_area) in parameters.iter().copied().enumerate() {
+            match (slots.last().copied(), pending.take()) {
+                (_, Some(slot)) => slots.push(StringSlot::Name(slot)),
+                (Some(StringSlot::Whole(slot)), None) if is_area => {
+                    let last = slots.len() - 1;
+                    slots[last] = StringSlot::Name(slot);
+                    slots.push(StringSlot::Area(slot));
+                }
+                _ if is_area && parameters.get(i + 1) == Some(&false) => {
+                    slots.push(StringSlot::Area(next));
+                    pending = Some(next);
+                    next += 1;
+                }
+                _ => {
+                    slots.push(StringSlot::Whole(next));
+                    next += 1;
+                }
+            }
+        }
+        slots
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringSlot {
+    Whole(usize),
+    Name(usize),
+    Area(usize),
+}
+
+// Length of the scope stored in front of a variable name
+pub const AREA_LENGTH: usize = 6;
+
+// Everything needed to translate between script source and compiled scripts
+#[derive(Debug, Default)]
+pub struct Symbols {
+    pub triggers: Vec<Signature>,
+    pub actions: Vec<Signature>,
+    pub ids: IdsRegistry,
+    // Compile with the trigger points and object rectangles of the enhanced editions
+    pub enhanced_edition: bool,
+}
+
+impl Symbols {
+    pub fn new(ids: IdsRegistry) -> Self {
+        let signatures = |table: &str| -> Vec<Signature> {
+            ids.get(table)
+                .map(|table| {
+                    table
+                        .entries
+                        .iter()
+                        .filter_map(|entry| Signature::parse(entry.value, &entry.symbol))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Symbols {
+            triggers: signatures("TRIGGER"),
+            actions: signatures("ACTION"),
+            ids,
+            enhanced_edition: false,
+        }
+    }
+
+    // The first symbol for a value, as used by the engine
+    pub fn symbol(&self, table: &str, value: i64) -> Option<&str> {
+        self.ids.symbol(table, value)
+    }
+
+    pub fn value(&self, table: &str, symbol: &str) -> Option<i64> {
+        self.ids.value(table, symbol)
+    }
+
+    pub fn has_table(&self, table: &str) -> bool {
+        self.ids.get(table).is_some()
+    }
+
+    pub fn trigger(&self, id: i64) -> Option<&Signature> {
+        self.triggers.iter().find(|signature| signature.id == id)
+    }
+
+    pub fn action(&self, id: i64) -> Option<&Signature> {
+        self.actions.iter().find(|signature| signature.id == id)
+    }
+}
@@ -0,0 +1,299 @@
+use std::error::Error;
+
+use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::acm::{self, ACM_SIGNATURE};
+use crate::common::char_array::CharArray;
+use crate::common::header::Header;
+use crate::model::Model;
+
+// "WAVC"
+const WAVC_SIGNATURE: CharArray<4> = CharArray([87, 65, 86, 67]);
+// "V1.0"
+const WAVC_VERSION: CharArray<4> = CharArray([86, 49, 46, 48]);
+const WAVC_HEADER_SIZE: u32 = 0x1c;
+const PCM_FORMAT: u16 = 1;
+const BITS_PER_SAMPLE: u16 = 16;
+
+// Game sounds are either plain RIFF wavs, raw acm or acm wrapped in a WAVC header,
+// the model only describes the audio, the samples come from to_wav
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wavc_v1.htm
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sound {
+    #[serde(skip)]
+    pub original_bytes: Vec<u8>,
+    pub container: SoundContainer,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wavc_header: Option<WavcHeader>,
+    pub channels: u16,
+    pub sample_rate: u32,
+    // Across all channels
+    pub sample_count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundContainer {
+    Riff,
+    Acm,
+    Wavc,
+}
+
+impl Model for Sound {
+    fn new(buffer: &[u8]) -> Self {
+        match Self::parse(buffer) {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    // Sounds are read only, edits go through to_wav and from_wav
+    fn to_bytes(&self) -> Vec<u8> {
+        self.original_bytes.clone()
+    }
+}
+
+impl Sound {
+    pub fn parse(buffer: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buffer.starts_with(&WAVC_SIGNATURE.0) {
+            let header: WavcHeader = Cursor::new(buffer).read_le()?;
+            let acm = acm::AcmHeader::parse(
+                buffer
+                    .get(header.offset_to_acm as usize..)
+                    .ok_or("WAVC acm offset is past the end of the file")?,
+            )?;
+            return Ok(Self {
+                original_bytes: buffer.to_vec(),
+                container: SoundContainer::Wavc,
+                channels: header.channels,
+                sample_rate: header.sample_rate.into(),
+                sample_count: acm.sample_count,
+                wavc_header: Some(header),
+            });
+        }
+        if buffer.starts_with(&ACM_SIGNATURE) {
+            let acm = acm::AcmHeader::parse(buffer)?;
+            return Ok(Self {
+                original_bytes: buffer.to_vec(),
+                container: SoundContainer::Acm,
+                wavc_header: None,
+                channels: acm.channels,
+                sample_rate: acm.sample_rate.into(),
+                sample_count: acm.sample_count,
+            });
+        }
+        let pcm = Pcm::from_wav(buffer)?;
+        Ok(Self {
+            original_bytes: buffer.to_vec(),
+            container: SoundContainer::Riff,
+            wavc_header: None,
+            channels: pcm.channels,
+            sample_rate: pcm.sample_rate,
+            sample_count: pcm.samples.len() as u32,
+        })
+    }
+
+    pub fn to_pcm(&self) -> Result<Pcm, Box<dyn Error>> {
+        // The audio is not part of the json, so a sound read back from it has nothing to decode
+        if self.original_bytes.is_empty() {
+            return Err("Sound has no audio data, it must be read from the original file".into());
+        }
+        let acm_bytes = match &self.wavc_header {
+            Some(header) => self
+                .original_bytes
+                .get(header.offset_to_acm as usize..)
+                .ok_or("WAVC acm offset is past the end of the file")?,
+            None if self.container == SoundContainer::Riff => {
+                return Pcm::from_wav(&self.original_bytes);
+            }
+            None => &self.original_bytes[..],
+        };
+        let (_, samples) = acm::decode(acm_bytes)?;
+        // The acm channel count is unreliable in WAVC files, so the parsed one is used
+        Ok(Pcm {
+            channels: self.channels.max(1),
+            sample_rate: self.sample_rate,
+            samples,
+        })
+    }
+
+    // A standard 16 bit RIFF wav, that any audio player can open
+    pub fn to_wav(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self.to_pcm()?.to_wav())
+    }
+
+    // Encodes a 16 bit PCM RIFF wav into a WAVC resource the games can load
+    pub fn from_wav(buffer: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let pcm = Pcm::from_wav(buffer)?;
+        let sample_rate = u16::try_from(pcm.sample_rate)?;
+        let acm = acm::encode(&pcm.samples, pcm.channels, sample_rate);
+        let header = WavcHeader {
+            header: Header {
+                signature: WAVC_SIGNATURE,
+                version: WAVC_VERSION,
+            },
+            uncompressed_size: (pcm.samples.len() * 2) as u32,
+            compressed_size: acm.len() as u32,
+            offset_to_acm: WAVC_HEADER_SIZE,
+            channels: pcm.channels,
+            bits_per_sample: BITS_PER_SAMPLE,
+            sample_rate,
+            _unknown: 0,
+        };
+        let mut writer = Cursor::new(Vec::new());
+        header.write_le(&mut writer)?;
+        let mut out = writer.into_inner();
+        out.extend(acm);
+        Ok(out)
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wavc_v1.htm#wavcv1_Header
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct WavcHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub uncompressed_size: u32,
+    pub compressed_size: u32,
+    pub offset_to_acm: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub sample_rate: u16,
+    #[serde(skip)]
+    _unknown: u16,
+}
+
+// Interleaved 16 bit samples
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pcm {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub samples: Vec<i16>,
+}
+
+impl Pcm {
+    // Walks the RIFF chunks for the format and data, anything else is skipped
+    pub fn from_wav(buffer: &[u8]) -> Result<Self, Box<dyn Error>> {
+        if buffer.get(..4) != Some(b"RIFF") || buffer.get(8..12) != Some(b"WAVE") {
+            return Err("Not a RIFF wav".into());
+        }
+        let mut format = None;
+        let mut data = None;
+        let mut position = 12;
+        while let Some(chunk) = buffer.get(position..position + 8) {
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as usize;
+            let start = position + 8;
+            let body = buffer
+                .get(start..start + size)
+                .unwrap_or(&buffer[start.min(buffer.len())..]);
+            match &chunk[..4] {
+                b"fmt " => format = Some(body),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // Chunks are padded to an even length
+            position = start + size + size % 2;
+        }
+        let format = format
+            .filter(|format| format.len() >= 16)
+            .ok_or("Wav has no fmt chunk")?;
+        let data = data.ok_or("Wav has no data chunk")?;
+        let read_u16 = |offset: usize| u16::from_le_bytes([format[offset], format[offset + 1]]);
+        if read_u16(0) != PCM_FORMAT || read_u16(14) != BITS_PER_SAMPLE {
+            return Err("Only 16 bit PCM wavs are supported".into());
+        }
+        Ok(Self {
+            channels: read_u16(2),
+            sample_rate: u32::from_le_bytes([format[4], format[5], format[6], format[7]]),
+            samples: data
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|sample| i16::from_le_bytes(*sample))
+                .collect(),
+        })
+    }
+
+    pub fn to_wav(&self) -> Vec<u8> {
+        let data_size = self.samples.len() as u32 * 2;
+        let block_align = self.channels * BITS_PER_SAMPLE / 8;
+        let mut out = b"RIFF".to_vec();
+        out.extend((36 + data_size).to_le_bytes());
+        out.extend(b"WAVEfmt ");
+        out.extend(16_u32.to_le_bytes());
+        out.extend(PCM_FORMAT.to_le_bytes());
+        out.extend(self.channels.to_le_bytes());
+        out.extend(self.sample_rate.to_le_bytes());
+        out.extend((self.sample_rate * u32::from(block_align)).to_le_bytes());
+        out.extend(block_align.to_le_bytes());
+        out.extend(BITS_PER_SAMPLE.to_le_bytes());
+        out.extend(b"data");
+        out.extend(data_size.to_le_bytes());
+        for sample in &self.samples {
+            out.extend(sample.to_le_bytes());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn pcm() -> Pcm {
+        Pcm {
+            channels: 2,
+            sample_rate: 22050,
+            samples: (0..4410).map(|i| ((i * 37) % 2000 - 1000) as i16).collect(),
+        }
+    }
+
+    #[test]
+    fn wav_to_wavc_and_back() -> Result<(), Box<dyn Error>> {
+        let wav = pcm().to_wav();
+        let riff = Sound::new(&wav);
+        assert_eq!(riff.container, SoundContainer::Riff);
+        assert_eq!(riff.sample_count, 4410);
+
+        let wavc = Sound::from_wav(&wav)?;
+        assert_eq!(&wavc[..8], b"WAVCV1.0");
+        let sound = Sound::new(&wavc);
+        assert_eq!(sound.container, SoundContainer::Wavc);
+        assert_eq!(sound.channels, 2);
+        assert_eq!(sound.sample_rate, 22050);
+        assert_eq!(sound.sample_count, 4410);
+        let header = sound.wavc_header.as_ref().unwrap();
+        assert_eq!(header.uncompressed_size, 8820);
+        assert_eq!(header.compressed_size as usize, wavc.len() - 0x1c);
+        assert_eq!(sound.to_bytes(), wavc);
+        assert_eq!(sound.to_wav()?, wav);
+        assert_eq!(Pcm::from_wav(&sound.to_wav()?)?, pcm());
+
+        let truncated = Sound {
+            original_bytes: wavc[..0x10].to_vec(),
+            ..sound
+        };
+        assert!(truncated.to_pcm().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn raw_acm() -> Result<(), Box<dyn Error>> {
+        let acm = acm::encode(&[1, -1, 300], 1, 11025);
+        let sound = Sound::new(&acm);
+        assert_eq!(sound.container, SoundContainer::Acm);
+        assert_eq!(sound.to_pcm()?.samples, vec![1, -1, 300]);
+        let json = serde_json::to_value(&sound)?;
+        assert_eq!(json["container"], "acm");
+        assert!(json.get("wavc_header").is_none());
+
+        let from_json: Sound = serde_json::from_value(json)?;
+        assert!(from_json.to_pcm().is_err());
+        Ok(())
+    }
+}
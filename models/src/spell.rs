@@ -6,6 +6,7 @@ use crate::common::feature_block::FeatureBlock;
 use crate::common::header::Header;
 use crate::common::strref::Strref;
 use crate::model::Model;
+use crate::spell_types::{ExclusionFlags, SpellFlags};
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/spl_v1.htm
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
@@ -51,9 +52,9 @@ pub struct SpellHeader {
     pub identified_spell_name: u32,
     pub completion_sound: Resref,
     // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/spl_v2.htm#Header_Flags
-    pub flags: u32,
+    pub flags: SpellFlags,
     pub spell_type: u16,
-    pub exclusion_flags: u32,
+    pub exclusion_flags: ExclusionFlags,
     pub casting_graphics: u16,
     pub min_level: u8,
     pub primary_spell_school: u8,
@@ -0,0 +1,190 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::common::Resref;
+use crate::common::flags::flags;
+use crate::common::header::Header;
+use crate::model::Model;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vvc_v1.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct SpellAnimation {
+    #[serde(flatten)]
+    pub header: Header,
+    pub animation: Resref,
+    pub shadow_animation: Resref,
+    pub display_flags: DisplayFlags,
+    pub colour_flags: ColourFlags,
+    #[serde(skip)]
+    _unused_1: u32,
+    pub sequence_flags: SequenceFlags,
+    #[serde(skip)]
+    _unused_2: u32,
+    pub x_position: i32,
+    pub y_position: i32,
+    pub use_orientation: u32,
+    pub frame_rate: u32,
+    pub orientation_count: u32,
+    pub primary_orientation: u32,
+    pub travel_flags: TravelFlags,
+    pub palette: Resref,
+    pub z_position: i32,
+    pub light_spot_width: u32,
+    pub light_spot_height: u32,
+    pub light_spot_brightness: u32,
+    pub duration: u32,
+    pub resource: Resref,
+    pub starting_sequence: u32,
+    pub duration_sequence: u32,
+    pub current_sequence: u32,
+    pub continuous_sequences: u32,
+    pub starting_sound: Resref,
+    pub duration_sound: Resref,
+    pub alpha_mask: Resref,
+    pub ending_sequence: u32,
+    #[brw(pad_after = 336)]
+    pub ending_sound: Resref,
+}
+
+impl Model for SpellAnimation {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vvc_v1.htm#vvcv1_Header_0x18
+    DisplayFlags: u16 {
+        TRANSPARENT = 0x1,
+        TRANSLUCENT = 0x2,
+        TRANSLUCENT_SHADOW = 0x4,
+        BLENDED = 0x8,
+        MIRROR_X = 0x10,
+        MIRROR_Y = 0x20,
+        CLIPPED = 0x40,
+        COPY_FROM_BACK = 0x80,
+        CLEAR_FILL = 0x100,
+        BLEND_3D = 0x200,
+        NOT_COVERED_BY_WALL = 0x400,
+        PERSIST_THROUGH_TIME_STOP = 0x800,
+        IGNORE_DREAM_PALETTE = 0x1000,
+        BLEND_2D = 0x2000,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vvc_v1.htm#vvcv1_Header_0x1a
+    ColourFlags: u16 {
+        NOT_LIGHT_SOURCE = 0x1,
+        LIGHT_SOURCE = 0x2,
+        INTERNAL_BRIGHTNESS = 0x4,
+        TIME_STOPPED = 0x8,
+        INTERNAL_GAMMA = 0x20,
+        NON_RESERVED_PALETTE = 0x40,
+        FULL_PALETTE = 0x80,
+        DREAM_PALETTE = 0x200,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vvc_v1.htm#vvcv1_Header_0x20
+    SequenceFlags: u32 {
+        LOOPING = 0x1,
+        SPECIAL_LIGHTING = 0x2,
+        MODIFY_FOR_HEIGHT = 0x4,
+        DRAW_ANIMATION = 0x8,
+        CUSTOM_PALETTE = 0x10,
+        PURGEABLE = 0x20,
+        NOT_COVERED_BY_WALL = 0x40,
+        MID_LEVEL_BRIGHTEN = 0x80,
+        HIGH_LEVEL_BRIGHTEN = 0x100,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vvc_v1.htm#vvcv1_Header_0x40
+    TravelFlags: u32 {
+        ORBIT_TARGET = 0x1,
+        RELATIVE_TO_TARGET = 0x2,
+        IGNORE_ORIENTATION = 0x8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use binrw::io::Read;
+    use pretty_assertions::assert_eq;
+    use std::{error::Error, fs::File};
+
+    fn read_file(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buffer = vec![0_u8; 0x1ec];
+        buffer[..8].copy_from_slice(b"VVC V1.0");
+        buffer[0x08..0x10].copy_from_slice(b"SPFIREBA");
+        buffer[0x18] = 0x0a;
+        buffer[0x20] = 0x09;
+        buffer[0x34] = 15;
+        buffer[0x4c..0x50].copy_from_slice(&(-20_i32).to_le_bytes());
+        buffer[0x78..0x80].copy_from_slice(b"EFF_E02\0");
+        let animation = SpellAnimation::new(&buffer);
+        assert_eq!(animation.animation.to_string(), "SPFIREBA");
+        assert_eq!(
+            animation.display_flags,
+            DisplayFlags::TRANSLUCENT | DisplayFlags::BLENDED
+        );
+        assert!(animation.sequence_flags.contains(SequenceFlags::LOOPING));
+        assert_eq!(animation.frame_rate, 15);
+        assert_eq!(animation.z_position, -20);
+        assert_eq!(animation.to_bytes(), buffer);
+
+        let json = serde_json::to_value(&animation).unwrap();
+        assert_eq!(
+            json["sequence_flags"],
+            serde_json::json!(["LOOPING", "DRAW_ANIMATION"])
+        );
+        let from_json: SpellAnimation = serde_json::from_value(json).unwrap();
+        assert_eq!(from_json.to_bytes(), buffer);
+    }
+
+    // The flame strike pillar from the fixture biff
+    #[test]
+    fn parse_game_animation() -> Result<(), Box<dyn Error>> {
+        let buffer = read_file("fixtures/anpillar.vvc")?;
+        let animation = SpellAnimation::new(&buffer);
+        assert_eq!(animation.animation.to_string(), "FLMSTRK\0");
+        assert_eq!(animation.display_flags, DisplayFlags::BLENDED);
+        assert_eq!(
+            animation.sequence_flags,
+            SequenceFlags::MODIFY_FOR_HEIGHT
+                | SequenceFlags::DRAW_ANIMATION
+                | SequenceFlags::NOT_COVERED_BY_WALL
+        );
+        assert_eq!(animation.frame_rate, 15);
+        assert_eq!(animation.duration, 550);
+        assert_eq!(animation.starting_sound.to_string(), "MISC_01B");
+        assert_eq!(animation.duration_sound.to_string(), "EFF_M12B");
+        assert_eq!(animation.to_bytes(), buffer);
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::common::flags::flags;
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/spl_v1.htm#Header_Flags
+    SpellFlags: u32 {
+        HOSTILE = 0x200,
+        NO_LOS_REQUIRED = 0x400,
+        ALLOW_SPOTTING = 0x800,
+        OUTDOORS_ONLY = 0x1000,
+        NON_MAGICAL_ABILITY = 0x2000,
+        IGNORE_WILD_SURGE = 0x4000,
+        NON_COMBAT_ABILITY = 0x8000,
+        CAN_TARGET_INVISIBLE = 0x1000000,
+        CASTABLE_WHEN_SILENCED = 0x2000000,
+    }
+}
+
+flags! {
+    // Alignments and schools for wizard spells, the top two bits for priest spells
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/spl_v1.htm#Header_Exclusion
+    ExclusionFlags: u32 {
+        UNUSABLE_BY_CHAOTIC = 0x1,
+        UNUSABLE_BY_EVIL = 0x2,
+        UNUSABLE_BY_GOOD = 0x4,
+        UNUSABLE_BY_GOOD_EVIL_NEUTRAL = 0x8,
+        UNUSABLE_BY_LAWFUL = 0x10,
+        UNUSABLE_BY_LAWFUL_CHAOTIC_NEUTRAL = 0x20,
+        UNUSABLE_BY_ABJURER = 0x40,
+        UNUSABLE_BY_CONJURER = 0x80,
+        UNUSABLE_BY_DIVINER = 0x100,
+        UNUSABLE_BY_ENCHANTER = 0x200,
+        UNUSABLE_BY_ILLUSIONIST = 0x400,
+        UNUSABLE_BY_INVOKER = 0x800,
+        UNUSABLE_BY_NECROMANCER = 0x1000,
+        UNUSABLE_BY_TRANSMUTER = 0x2000,
+        UNUSABLE_BY_GENERALIST = 0x4000,
+        UNUSABLE_BY_CLERIC_PALADIN = 0x40000000,
+        UNUSABLE_BY_DRUID_RANGER = 0x80000000,
+    }
+}
@@ -0,0 +1,115 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::common::strref::Strref;
+use crate::model::Model;
+use crate::tlk::TLK;
+
+// Strings spoken outside of dialogues, such as interjections, picked by weight
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/src.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Src {
+    #[serde(skip)]
+    #[bw(map = |_: &u32| entries.len() as u32)]
+    pub count_of_entries: u32,
+    #[br(count=count_of_entries)]
+    pub entries: Vec<SrcEntry>,
+}
+
+impl Model for Src {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Src {
+    // Fills in the text of each entry, strrefs missing from the tlk are left empty
+    pub fn resolve_strings(&mut self, tlk: &TLK) {
+        for entry in &mut self.entries {
+            entry.text = tlk
+                .strings
+                .get(entry.strref.0 as usize)
+                .map(|text| text.to_string());
+        }
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/src.htm#srcv1_Entry
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct SrcEntry {
+    pub strref: Strref,
+    pub weight: u32,
+    // Only used for display, never written back
+    #[br(ignore)]
+    #[bw(ignore)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() {
+        let mut buffer = vec![];
+        for value in [2_u32, 1, 1, 0, 3] {
+            buffer.extend(value.to_le_bytes());
+        }
+        let src = Src::new(&buffer);
+        assert_eq!(src.entries.len(), 2);
+        assert_eq!(src.entries[1].weight, 3);
+        assert_eq!(src.to_bytes(), buffer);
+
+        // Adding an entry through json updates the count
+        let mut json = serde_json::to_value(&src).unwrap();
+        json["entries"]
+            .as_array_mut()
+            .unwrap()
+            .push(serde_json::json!({"strref": 7, "weight": 1, "text": "Ignored"}));
+        let edited: Src = serde_json::from_value(json).unwrap();
+        let bytes = edited.to_bytes();
+        assert_eq!(bytes[..4], 3_u32.to_le_bytes());
+        assert_eq!(bytes.len(), 4 + 3 * 8);
+    }
+
+    #[test]
+    fn resolve_strings() -> Result<(), Box<dyn std::error::Error>> {
+        let strings = ["Hello", "Bye"];
+        let mut tlk = b"TLK V1  \0\0".to_vec();
+        tlk.extend(2_u32.to_le_bytes());
+        tlk.extend((18_u32 + 2 * 26).to_le_bytes());
+        let mut offset = 0_u32;
+        for text in strings {
+            tlk.extend(1_u16.to_le_bytes());
+            tlk.extend([0; 16]);
+            tlk.extend(offset.to_le_bytes());
+            tlk.extend((text.len() as u32).to_le_bytes());
+            offset += text.len() as u32;
+        }
+        tlk.extend(strings.concat().as_bytes());
+
+        let mut buffer = vec![];
+        for value in [1_u32, 1, 1] {
+            buffer.extend(value.to_le_bytes());
+        }
+        let mut src = Src::new(&buffer);
+        src.resolve_strings(&TLK::parse(&tlk)?);
+        assert_eq!(src.entries[0].text.as_deref(), Some("Bye"));
+        Ok(())
+    }
+}
@@ -1,31 +1,46 @@
-use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor};
+use binrw::{
+    BinRead, BinReaderExt, BinWrite,
+    helpers::until_eof,
+    io::{Cursor, SeekFrom},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::common::Resref;
+use crate::common::char_array::CharArray;
+use crate::common::flags::{flags, ids_enum};
 use crate::common::header::Header;
+use crate::common::sections::Sections;
 use crate::common::strref::Strref;
 use crate::model::Model;
 
+const HEADER_SIZE: u32 = 0x9c;
+const PURCHASED_SIZE: u32 = 0x04;
+const ITEM_SIZE: u32 = 0x1c;
+// Items with a trigger have it followed by 56 unknown bytes
+const TRIGGER_SIZE: u32 = 0x3c;
+const DRINK_SIZE: u32 = 0x14;
+const CURE_SIZE: u32 = 0x0c;
+// "V1.1"
+const VERSION_PST: CharArray<4> = CharArray([86, 49, 46, 49]);
+// "V9.0"
+const VERSION_IWD: CharArray<4> = CharArray([86, 57, 46, 48]);
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, Serialize, Deserialize)]
 pub struct Store {
     #[serde(skip)]
     #[br(parse_with = until_eof, restore_position)]
     pub original_bytes: Vec<u8>,
-    #[bw(ignore)]
     #[serde(flatten)]
     pub header: StoreHeader,
-    #[bw(ignore)]
-    #[br(count=header.count_of_items_in_items_purchased_section)]
+    #[br(count=header.count_of_items_in_items_purchased_section, seek_before=SeekFrom::Start(header.offset_to_items_purchased_section as u64))]
     pub items_purchased_here: Vec<u32>,
-    #[bw(ignore)]
-    #[br(count=header.count_of_items_for_sale_section)]
+    #[br(args { count: header.count_of_items_for_sale_section as usize, inner: (header.has_triggers(),) })]
+    #[br(seek_before=SeekFrom::Start(header.offset_to_items_for_sale_section as u64))]
     pub items_for_sale: Vec<ItemsForSale>,
-    #[bw(ignore)]
-    #[br(count=header.count_of_drinks_section)]
+    #[br(count=header.count_of_drinks_section, seek_before=SeekFrom::Start(header.offset_to_drinks_section as u64))]
     pub drinks_for_sale: Vec<DrinksForSale>,
-    #[bw(ignore)]
-    #[br(count=header.count_of_cures_section)]
+    #[br(count=header.count_of_cures_section, seek_before=SeekFrom::Start(header.offset_to_cures_section as u64))]
     pub cures_for_sale: Vec<CuresForSale>,
 }
 
@@ -40,68 +55,210 @@ impl Model for Store {
         }
     }
 
+    // Purchased types, items for sale, drinks then cures follow the header, items for sale
+    // taking their trigger along when the version has one
     fn to_bytes(&self) -> Vec<u8> {
+        let triggers = self.header.has_triggers();
+        let item_size = if triggers {
+            ITEM_SIZE + TRIGGER_SIZE
+        } else {
+            ITEM_SIZE
+        };
+        let mut sections = Sections::starting_at(HEADER_SIZE);
+        let layout = StoreLayout {
+            purchased: sections.place(self.items_purchased_here.len(), PURCHASED_SIZE),
+            for_sale: sections.place(self.items_for_sale.len(), item_size),
+            drinks: sections.place(self.drinks_for_sale.len(), DRINK_SIZE),
+            cures: sections.place(self.cures_for_sale.len(), CURE_SIZE),
+        };
+
         let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
+        self.header.write_le_args(&mut writer, (layout,)).unwrap();
+        self.items_purchased_here.write_le(&mut writer).unwrap();
+        for item in &self.items_for_sale {
+            item.write_le_args(&mut writer, (triggers,)).unwrap();
+        }
+        self.drinks_for_sale.write_le(&mut writer).unwrap();
+        self.cures_for_sale.write_le(&mut writer).unwrap();
         writer.into_inner()
     }
 }
 
+impl Store {
+    // Restocks the item if the store already sells it, otherwise adds it identified
+    pub fn add_stock(&mut self, item: &str, amount: u32, infinite: bool) -> &mut ItemsForSale {
+        let index = match self.stock_index(item) {
+            Some(index) => index,
+            None => {
+                self.items_for_sale.push(ItemsForSale {
+                    filename_of_item: item.into(),
+                    item_expiration_time: 0,
+                    quantity_charges_1: 0,
+                    quantity_charges_2: 0,
+                    quantity_charges_3: 0,
+                    flags: SaleFlags::IDENTIFIED,
+                    amount_of_this_item_in_stock: 0,
+                    infinite_supply_flag: 0,
+                    trigger: None,
+                });
+                self.items_for_sale.len() - 1
+            }
+        };
+        let stock = &mut self.items_for_sale[index];
+        stock.amount_of_this_item_in_stock = amount;
+        stock.infinite_supply_flag = infinite.into();
+        stock
+    }
+
+    pub fn remove_stock(&mut self, item: &str) -> Option<ItemsForSale> {
+        let index = self.stock_index(item)?;
+        Some(self.items_for_sale.remove(index))
+    }
+
+    fn stock_index(&self, item: &str) -> Option<usize> {
+        self.items_for_sale.iter().position(|stock| {
+            stock
+                .filename_of_item
+                .to_string()
+                .trim_end_matches('\0')
+                .eq_ignore_ascii_case(item)
+        })
+    }
+}
+
+// The offset and count of each store section
+#[derive(Debug, Clone, Copy)]
+pub struct StoreLayout {
+    purchased: (u32, u32),
+    for_sale: (u32, u32),
+    drinks: (u32, u32),
+    cures: (u32, u32),
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Header
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(layout: StoreLayout))]
 pub struct StoreHeader {
     #[serde(flatten)]
     pub header: Header,
-    //  (0=Store, 1=Tavern, 2=Inn, 3=Temple, 5=Container)
-    pub store_type: u32,
+    pub store_type: StoreType,
     pub name: Strref,
-    pub flags: u32,
+    pub flags: StoreFlags,
     pub sell_price_markup: u32,
     pub buy_price_markup: u32,
     pub depreciation_rate: u32,
     pub chance_of_steal_failure: u16,
+    #[brw(pad_after = 8)]
     pub capacity: u16,
-    #[serde(skip)]
-    #[br(count = 8)]
-    _unknown1: Vec<u8>,
+    #[bw(map = |_: &u32| layout.purchased.0)]
     pub offset_to_items_purchased_section: u32,
+    #[bw(map = |_: &u32| layout.purchased.1)]
     pub count_of_items_in_items_purchased_section: u32,
+    #[bw(map = |_: &u32| layout.for_sale.0)]
     pub offset_to_items_for_sale_section: u32,
+    #[bw(map = |_: &u32| layout.for_sale.1)]
     pub count_of_items_for_sale_section: u32,
     pub lore: u32,
     pub id_price: u32,
     pub rumours_tavern: Resref,
+    #[bw(map = |_: &u32| layout.drinks.0)]
     pub offset_to_drinks_section: u32,
+    #[bw(map = |_: &u32| layout.drinks.1)]
     pub count_of_drinks_section: u32,
     pub rumours_temple: Resref,
-    pub room_flags: u32,
+    pub room_flags: RoomFlags,
     pub price_of_a_peasant_room: u32,
     pub price_of_a_merchant_room: u32,
     pub price_of_a_noble_room: u32,
     pub price_of_a_royal_room: u32,
+    #[bw(map = |_: &u32| layout.cures.0)]
     pub offset_to_cures_section: u32,
+    #[brw(pad_after = 36)]
+    #[bw(map = |_: &u32| layout.cures.1)]
     pub count_of_cures_section: u32,
-    #[serde(skip)]
-    #[br(count = 36)]
-    _unknown2: Vec<u8>,
+}
+
+impl StoreHeader {
+    // Torment and Icewind Dale stores can hide items behind a trigger
+    pub fn has_triggers(&self) -> bool {
+        self.header.version == VERSION_PST || self.header.version == VERSION_IWD
+    }
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Header_0x08
+    StoreType: u32 {
+        Store = 0 => "STORE",
+        Tavern = 1 => "TAVERN",
+        Inn = 2 => "INN",
+        Temple = 3 => "TEMPLE",
+        Container = 5 => "CONTAINER",
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Header_0x10
+    StoreFlags: u32 {
+        CAN_BUY = 0x1,
+        CAN_SELL = 0x2,
+        CAN_IDENTIFY = 0x4,
+        CAN_STEAL = 0x8,
+        CAN_DONATE = 0x10,
+        CAN_PURCHASE_CURES = 0x20,
+        CAN_PURCHASE_DRINKS = 0x40,
+        TAVERN_QUALITY_1 = 0x100,
+        TAVERN_QUALITY_2 = 0x200,
+        FENCE = 0x800,
+        IGNORE_REPUTATION = 0x1000,
+        RECHARGE_ITEMS = 0x2000,
+        CAN_SELL_CRITICAL = 0x4000,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Header_0x5c
+    RoomFlags: u32 {
+        PEASANT = 0x1,
+        MERCHANT = 0x2,
+        NOBLE = 0x4,
+        ROYAL = 0x8,
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Sale_0x10
+    SaleFlags: u32 {
+        IDENTIFIED = 0x1,
+        UNSTEALABLE = 0x2,
+        STOLEN = 0x4,
+        UNDROPPABLE = 0x8,
+    }
 }
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Sale
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+#[br(import(triggers: bool))]
+#[bw(import(triggers: bool))]
 pub struct ItemsForSale {
     pub filename_of_item: Resref,
     pub item_expiration_time: u16,
     pub quantity_charges_1: u16,
     pub quantity_charges_2: u16,
     pub quantity_charges_3: u16,
-    pub flags: u32,
+    pub flags: SaleFlags,
     pub amount_of_this_item_in_stock: u32,
     //  (0=limited stock, 1=infinite stock)
     pub infinite_supply_flag: u32,
+    // Only kept by stores with triggers, the item is for sale while the trigger is true
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[br(if(triggers), pad_after = 56)]
+    #[bw(if(triggers), pad_after = 56)]
+    #[bw(map = |trigger: &Option<Strref>| trigger.as_ref().map_or(0, |trigger| trigger.0))]
+    pub trigger: Option<Strref>,
 }
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Drink
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct DrinksForSale {
     pub rumour_resource: Resref,
     pub drink_name: Strref,
@@ -110,8 +267,142 @@ pub struct DrinksForSale {
 }
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/sto_v1.htm#storv1_0_Cure
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct CuresForSale {
     pub filename_of_spell: Resref,
     pub spell_price: u32,
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use binrw::io::Read;
+    use pretty_assertions::assert_eq;
+    use std::{error::Error, fs::File};
+
+    fn read_file(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut file = File::open(path)?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn item(name: &[u8; 8], flags: u8, amount: u8, infinite: u8) -> Vec<u8> {
+        let mut buffer = vec![0_u8; ITEM_SIZE as usize];
+        buffer[..8].copy_from_slice(name);
+        buffer[0x10] = flags;
+        buffer[0x14] = amount;
+        buffer[0x18] = infinite;
+        buffer
+    }
+
+    // A temple selling a potion and a cure, with a purchased item type
+    fn store(version: &[u8; 4], sale_items: &[Vec<u8>]) -> Vec<u8> {
+        let item_size = sale_items.first().map_or(0, |item| item.len() as u32);
+        let mut buffer = vec![0_u8; HEADER_SIZE as usize];
+        buffer[..4].copy_from_slice(b"STOR");
+        buffer[4..8].copy_from_slice(version);
+        buffer[0x08] = 3;
+        buffer[0x10] = 0x25;
+        let for_sale = HEADER_SIZE + PURCHASED_SIZE;
+        let cures = for_sale + item_size * sale_items.len() as u32;
+        for (offset, value) in [
+            (0x2c, HEADER_SIZE),
+            (0x30, 1),
+            (0x34, for_sale),
+            (0x38, sale_items.len() as u32),
+            (0x4c, cures),
+            (0x70, cures),
+            (0x74, 1),
+        ] {
+            buffer[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        buffer.extend(9_u32.to_le_bytes());
+        for item in sale_items {
+            buffer.extend(item);
+        }
+        buffer.extend(b"SPPR103\0");
+        buffer.extend(50_u32.to_le_bytes());
+        buffer
+    }
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn Error>> {
+        let store = Store::new(&read_file("fixtures/wallace.sto")?);
+        let json = serde_json::to_value(&store)?;
+        assert_eq!(json["store_type"], "STORE");
+        let from_json: Store = serde_json::from_value(json)?;
+        assert_eq!(from_json.to_bytes(), store.to_bytes());
+        Ok(())
+    }
+
+    // V1.1 sale items carry a trigger, no game store of that version is available
+    #[test]
+    fn triggers() {
+        let mut sale_item = item(b"MISC01\0\0", 0, 1, 1);
+        sale_item.extend(1234_u32.to_le_bytes());
+        sale_item.extend([0; 56]);
+        let buffer = store(b"V1.1", &[sale_item]);
+        let store = Store::new(&buffer);
+        assert_eq!(store.items_for_sale[0].trigger, Some(Strref(1234)));
+        assert_eq!(store.to_bytes(), buffer);
+    }
+
+    #[test]
+    fn edit_stock() -> Result<(), Box<dyn Error>> {
+        let mut store = Store::new(&read_file("fixtures/wallace.sto")?);
+        store.add_stock("arow01", 10, false);
+        store.add_stock("SW1H01", 1, true);
+        assert_eq!(store.items_for_sale.len(), 16);
+        assert_eq!(store.items_for_sale[1].amount_of_this_item_in_stock, 10);
+        assert!(store.remove_stock("POTN08").is_some());
+        assert!(store.remove_stock("POTN08").is_none());
+
+        let reread = Store::new(&store.to_bytes());
+        assert_eq!(reread.header.count_of_items_for_sale_section, 15);
+        assert_eq!(
+            reread.header.offset_to_drinks_section,
+            reread.header.offset_to_items_for_sale_section + 15 * ITEM_SIZE
+        );
+        let last = &reread.items_for_sale[14];
+        assert_eq!(last.filename_of_item.to_string(), "SW1H01\0\0");
+        assert_eq!(last.infinite_supply_flag, 1);
+        assert_eq!(reread.items_for_sale, store.items_for_sale);
+        assert_eq!(reread.cures_for_sale, store.cures_for_sale);
+        Ok(())
+    }
+
+    // Wallace's store from a save, with its items for sale stored before the purchased types
+    #[test]
+    fn parse_game_store() -> Result<(), Box<dyn Error>> {
+        let store = Store::new(&read_file("fixtures/wallace.sto")?);
+        assert_eq!(store.header.store_type, StoreType::Store);
+        assert_eq!(store.header.name, Strref(104032));
+        assert!(store.header.flags.contains(StoreFlags::CAN_PURCHASE_CURES));
+        assert!(!store.header.flags.contains(StoreFlags::CAN_PURCHASE_DRINKS));
+        assert_eq!(store.header.sell_price_markup, 150);
+        assert_eq!(store.items_purchased_here.len(), 39);
+        assert_eq!(store.items_for_sale.len(), 15);
+        assert_eq!(
+            store.items_for_sale[1].filename_of_item.to_string(),
+            "AROW01\0\0"
+        );
+        assert_eq!(store.items_for_sale[1].amount_of_this_item_in_stock, 20);
+        assert_eq!(store.drinks_for_sale[0].drink_name, Strref(14767));
+        assert_eq!(store.drinks_for_sale[4].drink_price, 20);
+        assert_eq!(
+            store.cures_for_sale[3].filename_of_spell.to_string(),
+            "SPPR307\0"
+        );
+        assert_eq!(store.cures_for_sale[3].spell_price, 60);
+
+        let reread = Store::new(&store.to_bytes());
+        assert_eq!(reread.header.offset_to_items_purchased_section, 0x9c);
+        assert_eq!(reread.items_purchased_here, store.items_purchased_here);
+        assert_eq!(reread.items_for_sale, store.items_for_sale);
+        assert_eq!(reread.drinks_for_sale, store.drinks_for_sale);
+        assert_eq!(reread.cures_for_sale, store.cures_for_sale);
+        Ok(())
+    }
+}
@@ -1,18 +1,39 @@
-use binrw::{BinReaderExt, BinWrite, binread, io::Cursor};
+use std::{collections::HashMap, error::Error};
+
+use binrw::{
+    BinRead, BinReaderExt, BinWrite,
+    io::{Cursor, SeekFrom},
+};
 use serde::{Deserialize, Serialize};
 
-use crate::model::Model;
+use crate::{
+    common::{char_array::CharArray, header::Header, image::Image},
+    model::Model,
+};
 
-#[binread]
-#[derive(Debug, PartialEq, BinWrite, Serialize, Deserialize)]
-pub struct Tileset {
-    #[serde(skip)]
-    #[br(temp)]
-    #[bw(ignore)]
-    length: u32,
+// "TIS "
+const TIS_SIGNATURE: CharArray<4> = CharArray([84, 73, 83, 32]);
+// "V1  "
+const VERSION1: CharArray<4> = CharArray([86, 49, 32, 32]);
+const HEADER_SIZE: u32 = 0x18;
+const TILE_DIMENSION: u32 = 64;
+const PALETTE_TILE_SIZE: u32 = 0x1400;
+const PVRZ_TILE_SIZE: u32 = 0x0c;
+const BLACK_TILE_PAGE: u32 = u32::MAX;
 
-    #[br(count=length)]
-    pub data: Vec<u8>,
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/tis_v1.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Tileset {
+    #[serde(flatten)]
+    pub header: TilesetHeader,
+    // If palette based
+    #[br(if(header.length_of_tiles == PALETTE_TILE_SIZE))]
+    #[br(count=header.count_of_tiles, seek_before=SeekFrom::Start(header.offset_to_tiles as u64))]
+    pub palette_tiles: Vec<PaletteTile>,
+    // If pvrz based
+    #[br(if(header.length_of_tiles == PVRZ_TILE_SIZE))]
+    #[br(count=header.count_of_tiles, seek_before=SeekFrom::Start(header.offset_to_tiles as u64))]
+    pub pvrz_tiles: Vec<PvrzTile>,
 }
 
 impl Model for Tileset {
@@ -27,6 +48,226 @@ impl Model for Tileset {
     }
 
     fn to_bytes(&self) -> Vec<u8> {
-        self.data.clone()
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Tileset {
+    // Tilesets stored in biffs have no header, only the raw tile data
+    pub fn from_tiles(buffer: &[u8], tile_count: u32, tile_size: u32) -> Self {
+        let mut tis = TilesetHeader {
+            header: Header {
+                signature: TIS_SIGNATURE,
+                version: VERSION1,
+            },
+            count_of_tiles: tile_count,
+            length_of_tiles: tile_size,
+            offset_to_tiles: HEADER_SIZE,
+            dimension_of_tiles: TILE_DIMENSION,
+        }
+        .to_bytes();
+        tis.extend_from_slice(buffer);
+        Tileset::new(&tis)
+    }
+
+    pub fn tile_count(&self) -> usize {
+        self.palette_tiles.len() + self.pvrz_tiles.len()
+    }
+
+    // The pvrz pages referenced by this tileset, in ascending order
+    pub fn pvrz_pages(&self) -> Vec<u32> {
+        let mut pages: Vec<u32> = self
+            .pvrz_tiles
+            .iter()
+            .filter(|tile| !tile.is_black())
+            .map(|tile| tile.page)
+            .collect();
+        pages.sort_unstable();
+        pages.dedup();
+        pages
+    }
+
+    // eg. AR2600.TIS, page 3 => A260003.PVRZ
+    pub fn pvrz_page_name(tileset_name: &str, page: u32) -> String {
+        let name = tileset_name.replace('\0', "");
+        let first = name.get(..1).unwrap_or_default();
+        let rest = name.get(2..).unwrap_or_default();
+        format!("{first}{rest}{page:02}")
+    }
+
+    // Decodes a single 64x64 tile, pvrz tiles are cut out of the given pages
+    pub fn tile_to_image(
+        &self,
+        index: usize,
+        pvrz_pages: &HashMap<u32, Image>,
+    ) -> Result<Image, Box<dyn Error>> {
+        if let Some(tile) = self.palette_tiles.get(index) {
+            return Ok(tile.to_image());
+        }
+        let tile = self
+            .pvrz_tiles
+            .get(index)
+            .ok_or(format!("No tile found at index {index}"))?;
+        if tile.is_black() {
+            return Ok(PvrzTile::black_tile());
+        }
+        let page = pvrz_pages
+            .get(&tile.page)
+            .ok_or(format!("Missing pvrz page {}", tile.page))?;
+        Ok(tile.to_image(page))
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/tis_v1.htm#tisv1_Header
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct TilesetHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub count_of_tiles: u32,
+    // 0x1400 for palette based tiles, 0x000c for pvrz based tiles
+    pub length_of_tiles: u32,
+    pub offset_to_tiles: u32,
+    // Always 64
+    pub dimension_of_tiles: u32,
+}
+
+impl TilesetHeader {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/tis_v1.htm#tisv1_Data
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct PaletteTile {
+    // 256 BGRA entries
+    #[br(count = 1024)]
+    pub palette: Vec<u8>,
+    // 64 * 64 indices into the palette
+    #[br(count = 4096)]
+    pub pixels: Vec<u8>,
+}
+
+impl PaletteTile {
+    // Pure green is treated as transparent
+    pub fn to_image(&self) -> Image {
+        let mut image = Image::new(TILE_DIMENSION, TILE_DIMENSION);
+        for (i, index) in self.pixels.iter().enumerate() {
+            let start = *index as usize * 4;
+            let colour = match self.palette.get(start..start + 3) {
+                Some([0, 255, 0]) | None => [0, 0, 0, 0],
+                Some(bgr) => [bgr[2], bgr[1], bgr[0], 255],
+            };
+            image.set_pixel(i as u32 % TILE_DIMENSION, i as u32 / TILE_DIMENSION, colour);
+        }
+        image
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/tis_v1.htm#tisv1_Data
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct PvrzTile {
+    // -1 for a solid black tile
+    pub page: u32,
+    pub x_coordinate: u32,
+    pub y_coordinate: u32,
+}
+
+impl PvrzTile {
+    pub fn is_black(&self) -> bool {
+        self.page == BLACK_TILE_PAGE
+    }
+
+    fn black_tile() -> Image {
+        Image::from_pixels(
+            TILE_DIMENSION,
+            TILE_DIMENSION,
+            [0, 0, 0, 255].repeat((TILE_DIMENSION * TILE_DIMENSION) as usize),
+        )
+        .unwrap()
+    }
+
+    pub fn to_image(&self, page: &Image) -> Image {
+        if self.is_black() {
+            return PvrzTile::black_tile();
+        }
+        page.crop(
+            self.x_coordinate,
+            self.y_coordinate,
+            TILE_DIMENSION,
+            TILE_DIMENSION,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // No game tileset is checked in to fixtures, so tiles are built by hand
+    fn palette_tile() -> Vec<u8> {
+        let mut palette = vec![0_u8; 1024];
+        // index 1 is blue, index 2 is the transparent green
+        palette[4..8].copy_from_slice(&[255, 0, 0, 0]);
+        palette[8..12].copy_from_slice(&[0, 255, 0, 0]);
+        let mut pixels = vec![1_u8; 4096];
+        pixels[1] = 2;
+        [palette, pixels].concat()
+    }
+
+    #[test]
+    fn parse_palette_tiles() -> Result<(), Box<dyn Error>> {
+        let tiles = [palette_tile(), palette_tile()].concat();
+        let tileset = Tileset::from_tiles(&tiles, 2, PALETTE_TILE_SIZE);
+        assert_eq!(tileset.header.count_of_tiles, 2);
+        assert_eq!(tileset.tile_count(), 2);
+
+        let bytes = tileset.to_bytes();
+        assert_eq!(bytes.get(..4), Some("TIS ".as_bytes()));
+        assert_eq!(bytes.get(HEADER_SIZE as usize..), Some(tiles.as_slice()));
+        assert_eq!(Tileset::new(&bytes), tileset);
+
+        let image = tileset.tile_to_image(1, &HashMap::new())?;
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(image.pixel(1, 0), Some([0, 0, 0, 0]));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_pvrz_tiles() -> Result<(), Box<dyn Error>> {
+        let tiles: Vec<u8> = [3_u32, 64, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let tileset = Tileset::from_tiles(&tiles, 1, PVRZ_TILE_SIZE);
+        assert_eq!(tileset.pvrz_pages(), vec![3]);
+        assert_eq!(Tileset::pvrz_page_name("AR2600\0\0", 3), "A260003");
+
+        let mut page = Image::new(128, 64);
+        page.set_pixel(64, 0, [1, 2, 3, 255]);
+        let image = tileset.tile_to_image(0, &HashMap::from([(3, page)]))?;
+        assert_eq!(image.pixel(0, 0), Some([1, 2, 3, 255]));
+        assert!(tileset.tile_to_image(0, &HashMap::new()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn render_black_pvrz_tiles_without_pages() -> Result<(), Box<dyn Error>> {
+        let tiles: Vec<u8> = [u32::MAX, 0, 0, 3, 0, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let tileset = Tileset::from_tiles(&tiles, 2, PVRZ_TILE_SIZE);
+        assert_eq!(tileset.pvrz_pages(), vec![3]);
+
+        let image = tileset.tile_to_image(0, &HashMap::new())?;
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 0, 255]));
+        assert_eq!(image.pixel(63, 63), Some([0, 0, 0, 255]));
+        Ok(())
     }
 }
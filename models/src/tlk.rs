@@ -96,6 +96,16 @@ pub struct TLKEntry {
     pub length_of_this_string: u32,
 }
 
+impl TLKEntry {
+    // The wav resource spoken with this string, if any
+    pub fn sound_resource(&self) -> Option<String> {
+        let name = self.resource_name_of_associated_sound;
+        let name = String::from_utf8_lossy(&name);
+        let name = name.trim_end_matches('\0');
+        (!name.is_empty()).then(|| name.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -1,28 +1,505 @@
-use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use std::{error::Error, fmt::Display};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::parsers::{read_to_end, write_string},
+    common::encryption::{decrypt, encrypt, is_encrypted},
+    ids::parse_value,
     model::Model,
 };
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/2da.htm
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct TwoDA {
-    #[bw(write_with = write_string)]
-    #[br(parse_with = read_to_end)]
-    pub data: String,
+    // "2DA V1.0"
+    pub signature: String,
+    // Returned for cells a row doesn't have
+    pub default_value: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<TwoDARow>,
+    // Written back encrypted, as some original games shipped them
+    #[serde(default)]
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TwoDARow {
+    pub label: String,
+    // Rows can be shorter or longer than the column headers
+    pub cells: Vec<String>,
 }
 
 impl Model for TwoDA {
     fn new(buffer: &[u8]) -> Self {
-        let mut reader = Cursor::new(buffer);
-        reader.read_le().unwrap()
+        let mut table = TwoDA::parse(&String::from_utf8_lossy(&decrypt(buffer)));
+        table.encrypted = is_encrypted(buffer);
+        table
     }
 
+    // Columns are padded to line up, which the engine doesn't mind
     fn to_bytes(&self) -> Vec<u8> {
-        let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
-        writer.into_inner()
+        let mut widths: Vec<usize> = self.columns.iter().map(String::len).collect();
+        for row in &self.rows {
+            for (i, cell) in row.cells.iter().enumerate() {
+                match widths.get_mut(i) {
+                    Some(width) => *width = (*width).max(cell.len()),
+                    None => widths.push(cell.len()),
+                }
+            }
+        }
+        let label_width = self
+            .rows
+            .iter()
+            .map(|row| row.label.len())
+            .max()
+            .unwrap_or(0);
+        let line = |label: &str, cells: &[String]| {
+            let mut out = format!("{label:label_width$}");
+            for (cell, width) in cells.iter().zip(&widths) {
+                out.push_str(&format!("  {cell:width$}"));
+            }
+            format!("{}\r\n", out.trim_end())
+        };
+        let mut out = format!("{}\r\n{}\r\n", self.signature, self.default_value);
+        out.push_str(&line("", &self.columns));
+        for row in &self.rows {
+            out.push_str(&line(&row.label, &row.cells));
+        }
+        if self.encrypted {
+            return encrypt(out.as_bytes());
+        }
+        out.into_bytes()
+    }
+}
+
+impl TwoDA {
+    pub fn parse(text: &str) -> Self {
+        let mut lines = text.lines();
+        let signature = lines.next().unwrap_or_default().trim().to_string();
+        let default_value = lines.next().unwrap_or_default().trim().to_string();
+        let columns = lines
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let rows = lines
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace().map(str::to_string);
+                Some(TwoDARow {
+                    label: tokens.next()?,
+                    cells: tokens.collect(),
+                })
+            })
+            .collect();
+        TwoDA {
+            signature,
+            default_value,
+            columns,
+            rows,
+            encrypted: false,
+        }
+    }
+
+    // Labels are matched case insensitively, as the engine does
+    pub fn row_index(&self, row: &str) -> Option<usize> {
+        self.rows
+            .iter()
+            .position(|entry| entry.label.eq_ignore_ascii_case(row))
+    }
+
+    pub fn column_index(&self, column: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|entry| entry.eq_ignore_ascii_case(column))
+    }
+
+    // None when the row or column doesn't exist, the default value when the row is too short
+    pub fn get(&self, row: &str, column: &str) -> Option<&str> {
+        let row = &self.rows[self.row_index(row)?];
+        let column = self.column_index(column)?;
+        Some(
+            row.cells
+                .get(column)
+                .map_or(self.default_value.as_str(), String::as_str),
+        )
+    }
+
+    pub fn get_i32(&self, row: &str, column: &str) -> Option<i32> {
+        parse_value(self.get(row, column)?).map(|value| value as i32)
+    }
+
+    // A rectangular view, short rows are filled with the default value
+    pub fn to_csv(&self) -> String {
+        let escape = |cell: &str| {
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        };
+        let mut out = String::new();
+        let header: Vec<String> = std::iter::once("")
+            .chain(self.columns.iter().map(String::as_str))
+            .map(escape)
+            .collect();
+        out.push_str(&header.join(","));
+        out.push('\n');
+        for row in &self.rows {
+            let cells: Vec<String> = std::iter::once(row.label.as_str())
+                .chain((0..self.columns.len().max(row.cells.len())).map(|i| {
+                    row.cells
+                        .get(i)
+                        .map_or(self.default_value.as_str(), String::as_str)
+                }))
+                .map(escape)
+                .collect();
+            out.push_str(&cells.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+// Edits in the style of WeiDU's APPEND, SET_2DA_ENTRY and friends
+impl TwoDA {
+    pub fn append_row(&mut self, label: &str, cells: Vec<String>) {
+        self.rows.push(TwoDARow {
+            label: label.to_string(),
+            cells,
+        });
+    }
+
+    // Short rows are padded with the default value up to the cell being set
+    pub fn set(&mut self, row: &str, column: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        let index = self
+            .row_index(row)
+            .ok_or(format!("No row {row} in table"))?;
+        let column = self
+            .column_index(column)
+            .ok_or(format!("No column {column} in table"))?;
+        let cells = &mut self.rows[index].cells;
+        if cells.len() <= column {
+            cells.resize(column + 1, self.default_value.clone());
+        }
+        cells[column] = value.to_string();
+        Ok(())
+    }
+
+    pub fn add_column(&mut self, column: &str, fill: &str) -> Result<(), Box<dyn Error>> {
+        if self.column_index(column).is_some() {
+            return Err(format!("Column {column} already exists").into());
+        }
+        let index = self.columns.len();
+        self.columns.push(column.to_string());
+        for row in &mut self.rows {
+            if row.cells.len() < index {
+                row.cells.resize(index, self.default_value.clone());
+            }
+            row.cells.insert(index, fill.to_string());
+        }
+        Ok(())
+    }
+
+    // Combines two edited copies of the same table, cells changed differently by both sides are
+    // conflicts and keep our value
+    pub fn merge(original: &TwoDA, ours: &TwoDA, theirs: &TwoDA) -> (TwoDA, Vec<MergeConflict>) {
+        let mut conflicts = vec![];
+        let default_value = match pick(
+            Some(&original.default_value),
+            Some(&ours.default_value),
+            Some(&theirs.default_value),
+        ) {
+            Some(value) => value.unwrap_or_default().to_string(),
+            None => {
+                conflicts.push(MergeConflict {
+                    row: None,
+                    column: None,
+                    ours: Some(ours.default_value.clone()),
+                    theirs: Some(theirs.default_value.clone()),
+                });
+                ours.default_value.clone()
+            }
+        };
+
+        // Only cells of the original columns count as edits, a column added by one side would
+        // otherwise make every row look edited
+        let edited = |table: &TwoDA, label: &str| {
+            original.columns.iter().any(|column| {
+                table.column_index(column).is_some()
+                    && table.get(label, column) != original.get(label, column)
+            })
+        };
+        let column_edited = |table: &TwoDA, column: &str| {
+            table.column_index(column).is_some()
+                && original.rows.iter().any(|row| {
+                    table.row_index(&row.label).is_some()
+                        && table.get(&row.label, column) != original.get(&row.label, column)
+                })
+        };
+        // A column deleted by one side stays deleted unless the other side edited it
+        let mut deleted_columns = vec![];
+        for column in &original.columns {
+            let deleted_by_us = ours.column_index(column).is_none();
+            let deleted_by_them = theirs.column_index(column).is_none();
+            if (deleted_by_us && !column_edited(theirs, column))
+                || (deleted_by_them && !column_edited(ours, column))
+            {
+                deleted_columns.push(column);
+            } else if deleted_by_us || deleted_by_them {
+                conflicts.push(MergeConflict {
+                    row: None,
+                    column: Some(column.clone()),
+                    ours: None,
+                    theirs: None,
+                });
+            }
+        }
+        let mut columns: Vec<String> = vec![];
+        for column in ours
+            .columns
+            .iter()
+            .chain(&original.columns)
+            .chain(&theirs.columns)
+        {
+            let same = |other: &&String| other.eq_ignore_ascii_case(column);
+            if !deleted_columns.iter().any(same) && !columns.iter().any(|other| same(&other)) {
+                columns.push(column.clone());
+            }
+        }
+        let mut labels: Vec<&str> = ours.rows.iter().map(|row| row.label.as_str()).collect();
+        for row in &theirs.rows {
+            if ours.row_index(&row.label).is_none() {
+                labels.push(&row.label);
+            }
+        }
+
+        let mut rows = vec![];
+        for label in labels {
+            let in_original = original.row_index(label).is_some();
+            let deleted_by_us = in_original && ours.row_index(label).is_none();
+            let deleted_by_them = in_original && theirs.row_index(label).is_none();
+            // A row deleted by one side stays deleted unless the other side edited it
+            if (deleted_by_us && !edited(theirs, label))
+                || (deleted_by_them && !edited(ours, label))
+            {
+                continue;
+            }
+            if deleted_by_us || deleted_by_them {
+                conflicts.push(MergeConflict {
+                    row: Some(label.to_string()),
+                    column: None,
+                    ours: None,
+                    theirs: None,
+                });
+            }
+            let mut cells = vec![];
+            for column in &columns {
+                let base = original.get(label, column);
+                let (mut mine, mut other) = (ours.get(label, column), theirs.get(label, column));
+                if deleted_by_us || ours.column_index(column).is_none() {
+                    mine = other;
+                } else if deleted_by_them || theirs.column_index(column).is_none() {
+                    other = mine;
+                }
+                let value = match pick(base, mine, other) {
+                    Some(value) => value,
+                    None => {
+                        conflicts.push(MergeConflict {
+                            row: Some(label.to_string()),
+                            column: Some(column.clone()),
+                            ours: mine.map(str::to_string),
+                            theirs: other.map(str::to_string),
+                        });
+                        mine
+                    }
+                };
+                cells.push(value.unwrap_or(&default_value).to_string());
+            }
+            rows.push(TwoDARow {
+                label: label.to_string(),
+                cells,
+            });
+        }
+        let table = TwoDA {
+            signature: ours.signature.clone(),
+            default_value,
+            columns,
+            rows,
+            encrypted: ours.encrypted,
+        };
+        (table, conflicts)
+    }
+}
+
+// The value a three way merge settles on, None when both sides changed it differently
+fn pick<'a>(
+    base: Option<&str>,
+    ours: Option<&'a str>,
+    theirs: Option<&'a str>,
+) -> Option<Option<&'a str>> {
+    if ours == theirs || theirs == base {
+        Some(ours)
+    } else if ours == base {
+        Some(theirs)
+    } else {
+        None
+    }
+}
+
+// No row or column means the default value, a row without a column or a column without a row was
+// deleted by one side and edited by the other
+#[derive(Debug, PartialEq)]
+pub struct MergeConflict {
+    pub row: Option<String>,
+    pub column: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |value: &Option<String>| value.clone().unwrap_or("missing".to_string());
+        match (&self.row, &self.column) {
+            (Some(row), Some(column)) => write!(
+                f,
+                "{row} {column}: ours {}, theirs {}",
+                value(&self.ours),
+                value(&self.theirs)
+            ),
+            (Some(name), None) | (None, Some(name)) => {
+                write!(f, "{name}: deleted by one side but edited by the other")
+            }
+            _ => write!(
+                f,
+                "default value: ours {}, theirs {}",
+                value(&self.ours),
+                value(&self.theirs)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse() {
+        let buffer = b"2DA V1.0\r\n*\r\n     LEVEL  XP\r\nFIGHTER 1  0\r\nMAGE  2\tfoo,bar   \r\n\r\nTHIEF\r\nBARD 4 0x10 extra\r\n";
+        let table = TwoDA::new(buffer);
+        assert_eq!(table.signature, "2DA V1.0");
+        assert_eq!(table.default_value, "*");
+        assert_eq!(table.columns, ["LEVEL", "XP"]);
+        assert_eq!(table.rows.len(), 4);
+        assert_eq!(table.get("mage", "xp"), Some("foo,bar"));
+        assert_eq!(table.get("THIEF", "LEVEL"), Some("*"));
+        assert_eq!(table.get("THIEF", "HP"), None);
+        assert_eq!(table.get("CLERIC", "LEVEL"), None);
+        assert_eq!(table.get_i32("FIGHTER", "LEVEL"), Some(1));
+        assert_eq!(table.get_i32("BARD", "XP"), Some(16));
+        assert_eq!(table.get_i32("MAGE", "XP"), None);
+
+        let expected = "2DA V1.0\r\n*\r\n         LEVEL  XP\r\nFIGHTER  1      0\r\nMAGE     2      foo,bar\r\nTHIEF\r\nBARD     4      0x10     extra\r\n";
+        assert_eq!(String::from_utf8(table.to_bytes()).unwrap(), expected);
+        assert_eq!(TwoDA::new(&table.to_bytes()), table);
+
+        let expected = ",LEVEL,XP\nFIGHTER,1,0\nMAGE,2,\"foo,bar\"\nTHIEF,*,*\nBARD,4,0x10,extra\n";
+        assert_eq!(table.to_csv(), expected);
+    }
+
+    #[test]
+    fn parse_encrypted() {
+        let text = b"2DA V1.0\r\n0\r\n     VALUE\r\nROW  1\r\n";
+        let buffer = encrypt(text);
+        assert_eq!(&buffer[..4], [0xff, 0xff, 0xba, 0xec]);
+        let mut table = TwoDA::new(&buffer);
+        assert!(table.encrypted);
+        assert_eq!(table.get_i32("ROW", "VALUE"), Some(1));
+        assert_eq!(table.to_bytes(), buffer);
+
+        table.encrypted = false;
+        assert_eq!(table.to_bytes(), text);
+    }
+
+    #[test]
+    fn patch() -> Result<(), Box<dyn Error>> {
+        let mut table = TwoDA::parse("2DA V1.0\n0\n   A  B\nONE 1\nTWO 2  3\n");
+        table.set("one", "B", "4")?;
+        table.append_row("THREE", vec!["5".to_string()]);
+        table.add_column("C", "*")?;
+        assert_eq!(table.rows[0].cells, ["1", "4", "*"]);
+        assert_eq!(table.rows[2].cells, ["5", "0", "*"]);
+        assert!(table.set("FOUR", "A", "1").is_err());
+        assert!(table.add_column("c", "0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn merge() {
+        let original =
+            TwoDA::parse("2DA V1.0\n0\n   A  B\nONE 1  2\nTWO 3  4\nSIX 6  6\nTEN 1  0\n");
+        let ours = TwoDA::parse("2DA V1.0\n0\n   A  B\nONE 9  2\nTWO 3  7\nSIX 6  6\nNEW 0  0\n");
+        let theirs = TwoDA::parse(
+            "2DA V1.0\n0\n   A  B  C\nONE 1  2  x\nTWO 3  8  y\nTEN 2  0  z\nTHEIRS 1  1  1\n",
+        );
+        let (merged, conflicts) = TwoDA::merge(&original, &ours, &theirs);
+        assert_eq!(merged.columns, ["A", "B", "C"]);
+        let labels: Vec<&str> = merged.rows.iter().map(|row| row.label.as_str()).collect();
+        assert_eq!(labels, ["ONE", "TWO", "NEW", "TEN", "THEIRS"]);
+        assert_eq!(merged.rows[0].cells, ["9", "2", "x"]);
+        assert_eq!(merged.get("NEW", "C"), Some("0"));
+        assert_eq!(merged.get("TEN", "A"), Some("2"));
+        assert_eq!(
+            conflicts,
+            [
+                MergeConflict {
+                    row: Some("TWO".to_string()),
+                    column: Some("B".to_string()),
+                    ours: Some("7".to_string()),
+                    theirs: Some("8".to_string()),
+                },
+                MergeConflict {
+                    row: Some("TEN".to_string()),
+                    column: None,
+                    ours: None,
+                    theirs: None,
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].to_string(), "TWO B: ours 7, theirs 8");
+    }
+
+    #[test]
+    fn merge_columns() {
+        let original = TwoDA::parse("2DA V1.0\n0\n   A  B\nONE 1  2\nTWO 3  4\n");
+        // Adding a column doesn't edit the rows the other side deletes
+        let ours = TwoDA::parse("2DA V1.0\n0\n   A  B  C\nONE 1  2  x\nTWO 3  4  y\n");
+        let theirs = TwoDA::parse("2DA V1.0\n0\n   A\nONE 1\n");
+        let (merged, conflicts) = TwoDA::merge(&original, &ours, &theirs);
+        assert_eq!(merged.columns, ["A", "C"]);
+        assert_eq!(merged.rows.len(), 1);
+        assert_eq!(merged.rows[0].cells, ["1", "x"]);
+        assert!(conflicts.is_empty());
+
+        // A deleted column the other side edited is kept with the edits
+        let ours = TwoDA::parse("2DA V1.0\n0\n   A  B\nONE 1  5\nTWO 3  4\n");
+        let (merged, conflicts) = TwoDA::merge(&original, &ours, &theirs);
+        assert_eq!(merged.columns, ["A", "B"]);
+        assert_eq!(merged.get("ONE", "B"), Some("5"));
+        assert_eq!(
+            conflicts,
+            [MergeConflict {
+                row: None,
+                column: Some("B".to_string()),
+                ours: None,
+                theirs: None,
+            }]
+        );
+        assert_eq!(
+            conflicts[0].to_string(),
+            "B: deleted by one side but edited by the other"
+        );
     }
 }
@@ -0,0 +1,133 @@
+use binrw::{
+    BinRead, BinReaderExt, BinWrite,
+    io::{Cursor, SeekFrom},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::common::Resref;
+use crate::common::flags::ids_enum;
+use crate::common::header::Header;
+use crate::common::sections::Sections;
+use crate::model::Model;
+
+const HEADER_SIZE: u32 = 0x18;
+const COMPONENT_SIZE: u32 = 0xe0;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vef_v1.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct VisualEffect {
+    #[serde(flatten)]
+    pub header: VisualEffectHeader,
+    #[br(count=header.count_of_primary_components, seek_before=SeekFrom::Start(header.offset_to_primary_components as u64))]
+    pub primary_components: Vec<Component>,
+    #[br(count=header.count_of_secondary_components, seek_before=SeekFrom::Start(header.offset_to_secondary_components as u64))]
+    pub secondary_components: Vec<Component>,
+}
+
+impl Model for VisualEffect {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    // Primary then secondary components follow the header
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut sections = Sections::starting_at(HEADER_SIZE);
+        let primary = sections.place(self.primary_components.len(), COMPONENT_SIZE);
+        let secondary = sections.place(self.secondary_components.len(), COMPONENT_SIZE);
+        let mut writer = Cursor::new(Vec::new());
+        self.header.header.write_le(&mut writer).unwrap();
+        (primary.0, primary.1, secondary.0, secondary.1)
+            .write_le(&mut writer)
+            .unwrap();
+        self.primary_components.write_le(&mut writer).unwrap();
+        self.secondary_components.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vef_v1.htm#vefv1_Header
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct VisualEffectHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub offset_to_primary_components: u32,
+    pub count_of_primary_components: u32,
+    pub offset_to_secondary_components: u32,
+    pub count_of_secondary_components: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vef_v1.htm#vefv1_Component
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Component {
+    pub start_ticks: u32,
+    #[serde(skip)]
+    _unknown: u32,
+    pub loop_ticks: u32,
+    pub resource_type: ComponentType,
+    pub resource: Resref,
+    #[brw(pad_after = 196)]
+    pub continuous_cycles: u32,
+}
+
+ids_enum! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/vef_v1.htm#vefv1_Component_0x0c
+    ComponentType: u32 {
+        Sound = 0 => "WAV",
+        Animation = 1 => "VVC_BAM",
+        VisualEffect = 2 => "VEF_VVC_BAM",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // No game vef is checked in to fixtures, so components are built by hand
+    fn component(start_ticks: u8, resource_type: u8, resource: &[u8; 8]) -> Vec<u8> {
+        let mut buffer = vec![0_u8; COMPONENT_SIZE as usize];
+        buffer[0] = start_ticks;
+        buffer[0x0c] = resource_type;
+        buffer[0x10..0x18].copy_from_slice(resource);
+        buffer
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut buffer = b"VEF V1.0".to_vec();
+        for value in [0x18_u32, 2, 0x1d8, 1] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend(component(0, 0, b"EFF_M09\0"));
+        buffer.extend(component(5, 1, b"SPFLSRIN"));
+        buffer.extend(component(10, 2, b"SPMETSWA"));
+        let effect = VisualEffect::new(&buffer);
+        assert_eq!(effect.primary_components.len(), 2);
+        assert_eq!(
+            effect.primary_components[1].resource_type,
+            ComponentType::Animation
+        );
+        assert_eq!(effect.secondary_components[0].start_ticks, 10);
+        assert_eq!(effect.to_bytes(), buffer);
+
+        // Dropping a component through json moves the secondary list up
+        let mut json = serde_json::to_value(&effect).unwrap();
+        json["primary_components"].as_array_mut().unwrap().remove(0);
+        let edited: VisualEffect = serde_json::from_value(json).unwrap();
+        let bytes = edited.to_bytes();
+        let reread = VisualEffect::new(&bytes);
+        assert_eq!(reread.header.offset_to_secondary_components, 0xf8);
+        assert_eq!(reread.primary_components.len(), 1);
+        assert_eq!(
+            reread.secondary_components[0].resource.to_string(),
+            "SPMETSWA"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+use std::{collections::HashMap, error::Error};
+
+use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor, io::SeekFrom};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Resref, header::Header, image::Image},
+    model::Model,
+    tileset::Tileset,
+};
+
+const TILE_DIMENSION: u32 = 64;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Wed {
+    #[serde(skip)]
+    #[br(parse_with = until_eof, restore_position)]
+    pub original_bytes: Vec<u8>,
+    #[bw(ignore)]
+    #[serde(flatten)]
+    pub header: WedHeader,
+    #[bw(ignore)]
+    #[br(count=header.count_of_overlays, seek_before=SeekFrom::Start(header.offset_to_overlays as u64))]
+    pub overlays: Vec<Overlay>,
+    #[bw(ignore)]
+    #[br(seek_before=SeekFrom::Start(header.offset_to_secondary_header as u64))]
+    pub secondary_header: SecondaryHeader,
+    #[bw(ignore)]
+    #[br(count=header.count_of_doors, seek_before=SeekFrom::Start(header.offset_to_doors as u64))]
+    pub doors: Vec<Door>,
+    #[bw(ignore)]
+    #[br(count=doors.iter().map(|door| door.count_of_door_tile_cells as usize).sum::<usize>())]
+    #[br(seek_before=SeekFrom::Start(header.offset_to_door_tile_cell_indices as u64))]
+    pub door_tile_cells: Vec<u16>,
+    #[bw(ignore)]
+    #[br(count=wall_group_count(&overlays), seek_before=SeekFrom::Start(secondary_header.offset_to_wall_groups as u64))]
+    pub wall_groups: Vec<WallGroup>,
+    #[bw(ignore)]
+    #[br(count=secondary_header.count_of_polygons, seek_before=SeekFrom::Start(secondary_header.offset_to_polygons as u64))]
+    pub polygons: Vec<Polygon>,
+    #[bw(ignore)]
+    #[br(count=wall_groups.iter().map(|group| group.end()).max().unwrap_or_default())]
+    #[br(seek_before=SeekFrom::Start(secondary_header.offset_to_polygon_index_lookup as u64))]
+    pub polygon_indices: Vec<u16>,
+    #[bw(ignore)]
+    #[br(count=vertex_count(&polygons, &doors), seek_before=SeekFrom::Start(secondary_header.offset_to_vertices as u64))]
+    pub vertices: Vec<Vertex>,
+}
+
+// Each wall group covers a 640x480 pixel section of the base overlay
+fn wall_group_count(overlays: &[Overlay]) -> usize {
+    overlays.first().map_or(0, |overlay| {
+        (overlay.width as usize).div_ceil(10) * (overlay.height as usize * 2).div_ceil(15)
+    })
+}
+
+fn vertex_count(polygons: &[Polygon], doors: &[Door]) -> usize {
+    polygons
+        .iter()
+        .chain(doors.iter().flat_map(|door| door.polygons()))
+        .map(|polygon| polygon.end())
+        .max()
+        .unwrap_or_default()
+}
+
+impl Model for Wed {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+impl Wed {
+    // The tileset used by the base overlay
+    pub fn tileset_name(&self) -> Option<String> {
+        self.overlays
+            .first()
+            .map(|overlay| overlay.tileset_name.to_string().replace('\0', ""))
+    }
+
+    // Size of the area in pixels, the base overlay covers all of it
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.overlays.first().map(|overlay| {
+            (
+                overlay.width as u32 * TILE_DIMENSION,
+                overlay.height as u32 * TILE_DIMENSION,
+            )
+        })
+    }
+
+    // Renders the base overlay, doors are drawn in their default state
+    pub fn render(
+        &self,
+        tileset: &Tileset,
+        pvrz_pages: &HashMap<u32, Image>,
+    ) -> Result<Image, Box<dyn Error>> {
+        let overlay = self.overlays.first().ok_or("Wed has no overlays")?;
+        let closed_cells: Vec<u16> = self
+            .doors
+            .iter()
+            .filter(|door| door.is_closed())
+            .flat_map(|door| {
+                let start = door.first_door_tile_cell_index as usize;
+                let end = start + door.count_of_door_tile_cells as usize;
+                self.door_tile_cells.get(start..end).unwrap_or_default()
+            })
+            .copied()
+            .collect();
+
+        let width = overlay.width as u32;
+        let mut image = Image::new(
+            width * TILE_DIMENSION,
+            overlay.height as u32 * TILE_DIMENSION,
+        );
+        for (cell, tilemap) in overlay.tilemaps.iter().enumerate() {
+            let tile = if closed_cells.contains(&(cell as u16)) && tilemap.secondary_tile_index >= 0
+            {
+                tilemap.secondary_tile_index as u16
+            } else {
+                *overlay
+                    .tile_indices
+                    .get(tilemap.start_index_in_tile_index_lookup as usize)
+                    .ok_or(format!("Missing tile index for cell {cell}"))?
+            };
+            let tile_image = tileset.tile_to_image(tile as usize, pvrz_pages)?;
+            image.blit(
+                &tile_image,
+                (cell as u32 % width * TILE_DIMENSION) as i64,
+                (cell as u32 / width * TILE_DIMENSION) as i64,
+            );
+        }
+        Ok(image)
+    }
+
+    pub fn polygon_points(&self, polygon: &Polygon) -> Vec<(i64, i64)> {
+        let start = polygon.start_vertex_index as usize;
+        let end = polygon.end();
+        self.vertices
+            .get(start..end)
+            .unwrap_or_default()
+            .iter()
+            .map(|vertex| (vertex.x as i64, vertex.y as i64))
+            .collect()
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Header
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct WedHeader {
+    #[serde(flatten)]
+    pub header: Header,
+    pub count_of_overlays: u32,
+    pub count_of_doors: u32,
+    pub offset_to_overlays: u32,
+    pub offset_to_secondary_header: u32,
+    pub offset_to_doors: u32,
+    pub offset_to_door_tile_cell_indices: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Overlay
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Overlay {
+    // In tiles
+    pub width: u16,
+    pub height: u16,
+    pub tileset_name: Resref,
+    pub count_of_unique_tiles: u16,
+    // 0 = default, 1 = disable rendering, 2 = alternate rendering
+    pub movement_type: u16,
+    pub offset_to_tilemap: u32,
+    pub offset_to_tile_index_lookup: u32,
+    #[bw(ignore)]
+    #[br(count=width as usize * height as usize, seek_before=SeekFrom::Start(offset_to_tilemap as u64), restore_position)]
+    pub tilemaps: Vec<Tilemap>,
+    #[bw(ignore)]
+    #[br(count=tilemaps.iter().map(|tilemap| tilemap.end()).max().unwrap_or_default())]
+    #[br(seek_before=SeekFrom::Start(offset_to_tile_index_lookup as u64), restore_position)]
+    pub tile_indices: Vec<u16>,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_SecHeader
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct SecondaryHeader {
+    pub count_of_polygons: u32,
+    pub offset_to_polygons: u32,
+    pub offset_to_vertices: u32,
+    pub offset_to_wall_groups: u32,
+    pub offset_to_polygon_index_lookup: u32,
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Door
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Door {
+    pub name: Resref,
+    // 0 = open, 1 = closed
+    pub open_closed: u16,
+    pub first_door_tile_cell_index: u16,
+    pub count_of_door_tile_cells: u16,
+    pub count_of_polygons_open_state: u16,
+    pub count_of_polygons_closed_state: u16,
+    pub offset_to_polygons_open_state: u32,
+    pub offset_to_polygons_closed_state: u32,
+    #[bw(ignore)]
+    #[br(count=count_of_polygons_open_state, seek_before=SeekFrom::Start(offset_to_polygons_open_state as u64), restore_position)]
+    pub open_polygons: Vec<Polygon>,
+    #[bw(ignore)]
+    #[br(count=count_of_polygons_closed_state, seek_before=SeekFrom::Start(offset_to_polygons_closed_state as u64), restore_position)]
+    pub closed_polygons: Vec<Polygon>,
+}
+
+impl Door {
+    pub fn is_closed(&self) -> bool {
+        self.open_closed == 1
+    }
+
+    pub fn polygons(&self) -> impl Iterator<Item = &Polygon> {
+        self.open_polygons.iter().chain(self.closed_polygons.iter())
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Tilemap
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Tilemap {
+    pub start_index_in_tile_index_lookup: u16,
+    // More than one tile means the cell is animated
+    pub count_of_tiles: u16,
+    // Used when a door is closed, -1 for none
+    pub secondary_tile_index: i16,
+    // Bit flags of the overlays drawn on top of this cell
+    pub overlay_flags: u8,
+    pub animation_speed: u8,
+    pub wflags: u16,
+}
+
+impl Tilemap {
+    fn end(&self) -> usize {
+        self.start_index_in_tile_index_lookup as usize + self.count_of_tiles as usize
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_WallGroups
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct WallGroup {
+    pub start_polygon_index: u16,
+    pub count_of_polygon_indices: u16,
+}
+
+impl WallGroup {
+    fn end(&self) -> usize {
+        self.start_polygon_index as usize + self.count_of_polygon_indices as usize
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Polygon
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Polygon {
+    pub start_vertex_index: u32,
+    pub count_of_vertices: u32,
+    // bit 0 = shade wall, bit 1 = hovering, bit 2 = cover animations, bit 7 = door
+    pub flags: u8,
+    pub height: i8,
+    pub minimum_x: u16,
+    pub maximum_x: u16,
+    pub minimum_y: u16,
+    pub maximum_y: u16,
+}
+
+impl Polygon {
+    fn end(&self) -> usize {
+        self.start_vertex_index as usize + self.count_of_vertices as usize
+    }
+}
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wed_v1.3.htm#wedv1_3_Vertex
+#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Vertex {
+    pub x: u16,
+    pub y: u16,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn write_u16s(out: &mut Vec<u8>, values: &[u16]) {
+        out.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+    }
+
+    fn write_u32s(out: &mut Vec<u8>, values: &[u32]) {
+        out.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+    }
+
+    // A 2x1 overlay with a single door over the second cell and one wall polygon, built by hand
+    // as no game wed is checked in to fixtures
+    fn wed() -> Vec<u8> {
+        let mut out = b"WED V1.3".to_vec();
+        write_u32s(&mut out, &[1, 1, 0x20, 0x38, 0x4c, 0x66]);
+        // Overlay
+        write_u16s(&mut out, &[2, 1]);
+        out.extend(b"AR0100\0\0");
+        write_u16s(&mut out, &[2, 0]);
+        write_u32s(&mut out, &[0x68, 0x7c]);
+        // Secondary header
+        write_u32s(&mut out, &[1, 0x80, 0x92, 0x9e, 0xa2]);
+        // Door
+        out.extend(b"DOOR01\0\0");
+        write_u16s(&mut out, &[1, 0, 1, 1, 0]);
+        write_u32s(&mut out, &[0x80, 0]);
+        // Door tile cells
+        write_u16s(&mut out, &[1]);
+        // Tilemaps
+        write_u16s(&mut out, &[0, 1, 0xffff, 0, 0]);
+        write_u16s(&mut out, &[1, 1, 0, 0, 0]);
+        // Tile index lookup
+        write_u16s(&mut out, &[0, 1]);
+        // Polygon
+        write_u32s(&mut out, &[0, 3]);
+        out.extend([1, 0]);
+        write_u16s(&mut out, &[0, 10, 0, 10]);
+        // Vertices
+        write_u16s(&mut out, &[0, 0, 10, 0, 10, 10]);
+        // Wall group and polygon index lookup
+        write_u16s(&mut out, &[0, 1, 0]);
+        out
+    }
+
+    fn tileset() -> Tileset {
+        let mut tiles = vec![];
+        for blue in [255, 0] {
+            let mut palette = vec![0_u8; 1024];
+            palette[..4].copy_from_slice(&[blue, 0, 255 - blue, 0]);
+            tiles.extend(palette);
+            tiles.extend([0_u8; 4096]);
+        }
+        Tileset::from_tiles(&tiles, 2, 0x1400)
+    }
+
+    #[test]
+    fn parse() -> Result<(), Box<dyn Error>> {
+        let buffer = wed();
+        let wed = Wed::new(&buffer);
+        assert_eq!(wed.to_bytes(), buffer);
+        assert_eq!(wed.tileset_name(), Some("AR0100".to_string()));
+        assert_eq!(wed.overlays[0].tilemaps.len(), 2);
+        assert_eq!(wed.overlays[0].tile_indices, vec![0, 1]);
+        assert_eq!(wed.doors[0].open_polygons.len(), 1);
+        assert_eq!(wed.wall_groups.len(), 1);
+        assert_eq!(wed.polygon_indices, vec![0]);
+        assert_eq!(
+            wed.polygon_points(&wed.polygons[0]),
+            vec![(0, 0), (10, 0), (10, 10)]
+        );
+
+        // The closed door swaps the second cell over to its secondary tile
+        let image = wed.render(&tileset(), &HashMap::new())?;
+        assert_eq!((image.width, image.height), (128, 64));
+        assert_eq!(image.pixel(0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(image.pixel(64, 0), Some([0, 0, 255, 255]));
+        Ok(())
+    }
+}
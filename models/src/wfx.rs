@@ -0,0 +1,74 @@
+use binrw::{BinRead, BinReaderExt, BinWrite, io::Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::common::flags::flags;
+use crate::common::header::Header;
+use crate::model::Model;
+
+// https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wfx_v1.htm
+#[derive(Debug, PartialEq, BinRead, BinWrite, Serialize, Deserialize)]
+pub struct Wfx {
+    #[serde(flatten)]
+    pub header: Header,
+    pub curve_radius: u32,
+    pub flags: WfxFlags,
+    pub pitch_variance: u32,
+    #[brw(pad_after = 240)]
+    pub volume_variance: u32,
+}
+
+impl Model for Wfx {
+    fn new(buffer: &[u8]) -> Self {
+        let mut reader = Cursor::new(buffer);
+        match reader.read_le() {
+            Ok(res) => res,
+            Err(err) => {
+                panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = Cursor::new(Vec::new());
+        self.write_le(&mut writer).unwrap();
+        writer.into_inner()
+    }
+}
+
+flags! {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wfx_v1.htm#wfxv1_Header_0x0c
+    WfxFlags: u32 {
+        CUTSCENE_AUDIO = 0x1,
+        USE_CURVE_RADIUS = 0x2,
+        RANDOM_PITCH_VARIATION = 0x4,
+        RANDOM_VOLUME_VARIATION = 0x8,
+        NO_ENVIRONMENTAL_EFFECTS = 0x10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() {
+        let mut buffer = b"WFX V1.0".to_vec();
+        for value in [0_u32, 0xc, 30, 15] {
+            buffer.extend(value.to_le_bytes());
+        }
+        buffer.extend([0; 240]);
+        let wfx = Wfx::new(&buffer);
+        assert_eq!(
+            wfx.flags,
+            WfxFlags::RANDOM_PITCH_VARIATION | WfxFlags::RANDOM_VOLUME_VARIATION
+        );
+        assert_eq!(wfx.pitch_variance, 30);
+        assert_eq!(wfx.to_bytes(), buffer);
+
+        let json = serde_json::to_string(&wfx).unwrap();
+        let from_json: Wfx = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json.to_bytes(), buffer);
+    }
+}
@@ -1,34 +1,61 @@
-use binrw::{BinRead, BinReaderExt, BinWrite, helpers::until_eof, io::Cursor, io::SeekFrom};
+use std::{error::Error, ops::Range};
+
+use binrw::{
+    BinRead, BinResult, BinWrite,
+    helpers::until_eof,
+    io::{Cursor, Read, Seek, SeekFrom},
+};
 use serde::{Deserialize, Serialize};
 
 use crate::common::Resref;
 use crate::common::char_array::CharArray;
 use crate::common::header::Header;
+use crate::common::sections::Sections;
 use crate::common::strref::Strref;
 use crate::model::Model;
 
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+const HEADER_SIZE: u32 = 0x10;
+const MAP_SIZE: u32 = 0xb8;
+const AREA_SIZE: u32 = 0xf0;
+const LINK_SIZE: u32 = 0xd8;
+
+// The areas and links of every map are kept back to back, in map order
+#[derive(Debug, BinRead, Serialize, Deserialize)]
 pub struct WorldMap {
     #[serde(skip)]
     #[br(parse_with = until_eof, restore_position)]
     pub original_bytes: Vec<u8>,
     #[serde(flatten)]
     pub header: WorldMapHeader,
-    #[bw(ignore)]
     #[br(count=header.count_of_worldmap_entries, seek_before=SeekFrom::Start(header.offset_to_worldmap_entries as u64))]
     pub world_map_entries: Vec<WorldMapEntry>,
-    #[bw(ignore)]
-    #[br(count=world_map_entries.iter().map(|x| x.count_of_area_entries).reduce(|a,b| a+b).unwrap_or_default())]
+    #[br(parse_with = |reader, _, _:()| parse_sections(reader, &world_map_entries, |x| (x.offset_to_area_entries, x.count_of_area_entries)))]
     pub area_entries: Vec<AreaEntry>,
-    #[bw(ignore)]
-    #[br(parse_with = binrw::helpers::until_eof)]
+    #[br(parse_with = |reader, _, _:()| parse_sections(reader, &world_map_entries, |x| (x.offset_to_area_link_entries, x.count_of_area_link_entries)))]
     pub area_link_entries: Vec<AreaLink>,
 }
 
+// Each map has its own offsets, its sections needn't follow the previous map's
+fn parse_sections<R: Read + Seek, T: for<'a> BinRead<Args<'a> = ()>>(
+    reader: &mut R,
+    maps: &[WorldMapEntry],
+    section: impl Fn(&WorldMapEntry) -> (u32, u32),
+) -> BinResult<Vec<T>> {
+    let mut out = vec![];
+    for map in maps {
+        let (offset, count) = section(map);
+        reader.seek(SeekFrom::Start(offset as u64))?;
+        for _ in 0..count {
+            out.push(T::read_le(reader)?);
+        }
+    }
+    Ok(out)
+}
+
 impl Model for WorldMap {
     fn new(buffer: &[u8]) -> Self {
         let mut reader = Cursor::new(buffer);
-        match reader.read_le() {
+        match WorldMap::read_le(&mut reader) {
             Ok(res) => res,
             Err(err) => {
                 panic!("Errored with {err:?}, dumping buffer: {buffer:?}");
@@ -36,13 +63,182 @@ impl Model for WorldMap {
         }
     }
 
+    // Every map's areas come before any links, each map pointing at its own run of both
     fn to_bytes(&self) -> Vec<u8> {
+        let count_of_maps = self.world_map_entries.len() as u32;
+        let mut areas = Sections::starting_at(HEADER_SIZE + count_of_maps * MAP_SIZE);
+        let mut links = areas;
+        links.place(self.area_entries.len(), AREA_SIZE);
         let mut writer = Cursor::new(Vec::new());
-        self.write_le(&mut writer).unwrap();
+        self.header.header.write_le(&mut writer).unwrap();
+        (count_of_maps, HEADER_SIZE).write_le(&mut writer).unwrap();
+        for entry in &self.world_map_entries {
+            let (area_offset, _) = areas.place(entry.count_of_area_entries as usize, AREA_SIZE);
+            let (link_offset, _) =
+                links.place(entry.count_of_area_link_entries as usize, LINK_SIZE);
+            entry
+                .write_le_args(&mut writer, (area_offset, link_offset))
+                .unwrap();
+        }
+        self.area_entries.write_le(&mut writer).unwrap();
+        self.area_link_entries.write_le(&mut writer).unwrap();
         writer.into_inner()
     }
 }
 
+impl WorldMap {
+    fn area_range(&self, map: usize) -> Range<usize> {
+        let entries = &self.world_map_entries[..map + 1];
+        let start: u32 = entries[..map].iter().map(|x| x.count_of_area_entries).sum();
+        start as usize..(start + entries[map].count_of_area_entries) as usize
+    }
+
+    fn link_range(&self, map: usize) -> Range<usize> {
+        let entries = &self.world_map_entries[..map + 1];
+        let start: u32 = entries[..map]
+            .iter()
+            .map(|x| x.count_of_area_link_entries)
+            .sum();
+        start as usize..(start + entries[map].count_of_area_link_entries) as usize
+    }
+
+    fn check_map(&self, map: usize) -> Result<(), Box<dyn Error>> {
+        match map < self.world_map_entries.len() {
+            true => Ok(()),
+            false => Err(format!("No world map {map}").into()),
+        }
+    }
+
+    pub fn areas(&self, map: usize) -> &[AreaEntry] {
+        &self.area_entries[self.area_range(map)]
+    }
+
+    // The index of an area within its map, as used by the links
+    pub fn area_index(&self, map: usize, area: &str) -> Option<usize> {
+        self.areas(map).iter().position(|entry| {
+            entry
+                .area_resref
+                .to_string()
+                .trim_end_matches('\0')
+                .eq_ignore_ascii_case(area)
+        })
+    }
+
+    pub fn links(&self, map: usize, area: usize, direction: Direction) -> &[AreaLink] {
+        let (index, count) = self.areas(map)[area].links(direction);
+        let links = &self.area_link_entries[self.link_range(map)];
+        links
+            .get(index as usize..(index + count) as usize)
+            .unwrap_or_default()
+    }
+
+    // Appends the area to the map without any links, returning its index
+    pub fn add_area(&mut self, map: usize, mut area: AreaEntry) -> Result<usize, Box<dyn Error>> {
+        self.check_map(map)?;
+        for direction in DIRECTIONS {
+            area.set_links(direction, 0, 0);
+        }
+        let areas = self.area_range(map);
+        self.area_entries.insert(areas.end, area);
+        self.world_map_entries[map].count_of_area_entries += 1;
+        Ok(areas.len())
+    }
+
+    pub fn add_link(
+        &mut self,
+        map: usize,
+        area: usize,
+        direction: Direction,
+        link: AreaLink,
+    ) -> Result<(), Box<dyn Error>> {
+        self.check_map(map)?;
+        let count = self.area_range(map).len();
+        if area >= count || link.index_of_destination_area as usize >= count {
+            return Err(format!("Map {map} has only {count} areas").into());
+        }
+        self.regroup_links(map, |groups| {
+            groups[area][direction as usize].push(link);
+        });
+        Ok(())
+    }
+
+    // Links both areas to each other, leaving from and arriving at the given sides
+    pub fn link_areas(
+        &mut self,
+        map: usize,
+        from: (usize, Direction),
+        to: (usize, Direction),
+        travel_time: u32,
+        random_encounters: &[&str],
+        random_encounter_probability: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        let link = |destination: usize, arrival: Direction| {
+            AreaLink::new(
+                destination as u32,
+                arrival,
+                travel_time,
+                random_encounters,
+                random_encounter_probability,
+            )
+        };
+        self.add_link(map, from.0, from.1, link(to.0, to.1))?;
+        self.add_link(map, to.0, to.1, link(from.0, from.1))
+    }
+
+    // Splits the map's links into each area's direction groups, then lays them out again
+    // in area order, recomputing every link index and count
+    fn regroup_links(&mut self, map: usize, edit: impl FnOnce(&mut Vec<[Vec<AreaLink>; 4]>)) {
+        let areas = self.area_range(map);
+        let links = self.link_range(map);
+        let mut groups: Vec<[Vec<AreaLink>; 4]> = (0..areas.len())
+            .map(|area| DIRECTIONS.map(|direction| self.links(map, area, direction).to_vec()))
+            .collect();
+        edit(&mut groups);
+
+        let mut layout = vec![];
+        for (area, group) in self.area_entries[areas].iter_mut().zip(groups) {
+            for (direction, links) in DIRECTIONS.into_iter().zip(group) {
+                area.set_links(direction, layout.len() as u32, links.len() as u32);
+                layout.extend(links);
+            }
+        }
+        self.world_map_entries[map].count_of_area_link_entries = layout.len() as u32;
+        self.area_link_entries.splice(links, layout);
+    }
+}
+
+// In the order the link groups are stored in an area entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::West,
+    Direction::South,
+    Direction::East,
+];
+
+impl Direction {
+    // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wmap_v1.htm#wmapv1_0_AreaLink_0x24
+    fn entry_location(self) -> u32 {
+        match self {
+            Direction::North => 0x1,
+            Direction::East => 0x2,
+            Direction::South => 0x4,
+            Direction::West => 0x8,
+        }
+    }
+}
+
+fn unused<const N: usize>() -> Vec<u8> {
+    vec![0; N]
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wmap_v1.htm#wmapv1_0_Header
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct WorldMapHeader {
@@ -54,6 +250,7 @@ pub struct WorldMapHeader {
 
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wmap_v1.htm#wmapv1_0_Entry
 #[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[bw(import(area_offset: u32, link_offset: u32))]
 pub struct WorldMapEntry {
     pub background_image_mos_file: Resref,
     pub width: u32,
@@ -63,13 +260,15 @@ pub struct WorldMapEntry {
     pub start_centered_on_x: u32,
     pub start_centered_on_y: u32,
     pub count_of_area_entries: u32,
+    #[bw(map = |_: &u32| area_offset)]
     pub offset_to_area_entries: u32,
+    #[bw(map = |_: &u32| link_offset)]
     pub offset_to_area_link_entries: u32,
     pub count_of_area_link_entries: u32,
     pub map_icons_bam_file: Resref,
     // BGEE field only
     pub flags: u32,
-    #[serde(skip)]
+    #[serde(skip, default = "unused::<124>")]
     #[br(count = 124)]
     _unused: Vec<u8>,
 }
@@ -96,13 +295,61 @@ pub struct AreaEntry {
     pub link_count_south: u32,
     pub link_index_east: u32,
     pub link_count_east: u32,
-    #[serde(skip)]
+    #[serde(skip, default = "unused::<128>")]
     #[br(count = 128)]
     _unused: Vec<u8>,
 }
 
+impl AreaEntry {
+    // A hidden area at the given position, named by its resref
+    pub fn new(area: &str, x_coordinate: u32, y_coordinate: u32) -> Self {
+        Self {
+            area_resref: area.into(),
+            area_name_short: area.into(),
+            area_name_long: area.into(),
+            bitmask_indicating_status_of_area: vec![0; 4],
+            bam_file_sequence_icons: 0,
+            x_coordinate,
+            y_coordinate,
+            name_caption: Strref(u32::MAX),
+            name_tooltips: Strref(u32::MAX),
+            loading_screen_mos_file: CharArray([0; 8]),
+            link_index_north: 0,
+            link_count_north: 0,
+            link_index_west: 0,
+            link_count_west: 0,
+            link_index_south: 0,
+            link_count_south: 0,
+            link_index_east: 0,
+            link_count_east: 0,
+            _unused: unused::<128>(),
+        }
+    }
+
+    // The index and count into the map's links
+    pub fn links(&self, direction: Direction) -> (u32, u32) {
+        match direction {
+            Direction::North => (self.link_index_north, self.link_count_north),
+            Direction::West => (self.link_index_west, self.link_count_west),
+            Direction::South => (self.link_index_south, self.link_count_south),
+            Direction::East => (self.link_index_east, self.link_count_east),
+        }
+    }
+
+    fn set_links(&mut self, direction: Direction, index: u32, count: u32) {
+        let (link_index, link_count) = match direction {
+            Direction::North => (&mut self.link_index_north, &mut self.link_count_north),
+            Direction::West => (&mut self.link_index_west, &mut self.link_count_west),
+            Direction::South => (&mut self.link_index_south, &mut self.link_count_south),
+            Direction::East => (&mut self.link_index_east, &mut self.link_count_east),
+        };
+        *link_index = index;
+        *link_count = count;
+    }
+}
+
 // https://gibberlings3.github.io/iesdp/file_formats/ie_formats/wmap_v1.htm#wmapv1_0_AreaLink
-#[derive(Debug, BinRead, BinWrite, Serialize, Deserialize)]
+#[derive(Debug, Clone, BinRead, BinWrite, Serialize, Deserialize)]
 pub struct AreaLink {
     pub index_of_destination_area: u32,
     pub entry_point: CharArray<32>,
@@ -114,15 +361,46 @@ pub struct AreaLink {
     pub random_encounter_area_4: Resref,
     pub random_encounter_area_5: Resref,
     pub random_encounter_probability: u32,
-    #[serde(skip)]
+    #[serde(skip, default = "unused::<128>")]
     #[br(count = 128)]
     _unused: Vec<u8>,
 }
 
+impl AreaLink {
+    // Arrives at the destination's default entrance on the given side, only the first
+    // five random encounter areas are kept
+    pub fn new(
+        index_of_destination_area: u32,
+        arrival: Direction,
+        travel_time: u32,
+        random_encounters: &[&str],
+        random_encounter_probability: u32,
+    ) -> Self {
+        let encounter = |index: usize| -> Resref {
+            random_encounters
+                .get(index)
+                .map_or(CharArray([0; 8]), |area| (*area).into())
+        };
+        Self {
+            index_of_destination_area,
+            entry_point: CharArray([0; 32]),
+            travel_time,
+            default_entry_location: arrival.entry_location(),
+            random_encounter_area_1: encounter(0),
+            random_encounter_area_2: encounter(1),
+            random_encounter_area_3: encounter(2),
+            random_encounter_area_4: encounter(3),
+            random_encounter_area_5: encounter(4),
+            random_encounter_probability,
+            _unused: unused::<128>(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use binrw::io::Read;
+    use binrw::io::Write;
     use pretty_assertions::assert_eq;
     use serde_json::Value;
     use std::{error::Error, fs::File};
@@ -147,4 +425,125 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn round_trip() -> Result<(), Box<dyn Error>> {
+        let buffer = read_file("fixtures/worldmap.wmp")?;
+        let world_map = WorldMap::new(&buffer);
+        assert_eq!(world_map.to_bytes(), buffer);
+
+        let json = serde_json::to_value(&world_map)?;
+        let from_json: WorldMap = serde_json::from_value(json.clone())?;
+        let reread = WorldMap::new(&from_json.to_bytes());
+        assert_eq!(serde_json::to_value(reread)?, json);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_maps_with_their_own_offsets() -> Result<(), Box<dyn Error>> {
+        // The fixture's map twice, each followed by its own areas and links
+        let buffer = read_file("fixtures/worldmap.wmp")?;
+        let world_map = WorldMap::new(&buffer);
+        let map = &world_map.world_map_entries[0];
+        let areas = map.offset_to_area_entries as usize;
+        let areas = &buffer[areas..areas + (map.count_of_area_entries * AREA_SIZE) as usize];
+        let links = map.offset_to_area_link_entries as usize;
+        let links = &buffer[links..links + (map.count_of_area_link_entries * LINK_SIZE) as usize];
+
+        let mut writer = Cursor::new(Vec::new());
+        world_map.header.header.write_le(&mut writer)?;
+        (2u32, HEADER_SIZE).write_le(&mut writer)?;
+        let first = HEADER_SIZE + 2 * MAP_SIZE;
+        let second = first + (areas.len() + links.len()) as u32;
+        for offset in [first, second] {
+            map.write_le_args(&mut writer, (offset, offset + areas.len() as u32))?;
+        }
+        for _ in 0..2 {
+            writer.write_all(areas)?;
+            writer.write_all(links)?;
+        }
+
+        let two_maps = WorldMap::new(writer.get_ref());
+        let names = |map: usize| -> Vec<String> {
+            let areas = two_maps.areas(map);
+            areas.iter().map(|x| x.area_resref.to_string()).collect()
+        };
+        assert_eq!(names(1), names(0));
+        assert_eq!(
+            two_maps.area_link_entries.len(),
+            2 * world_map.area_link_entries.len()
+        );
+        let area = two_maps.area_index(1, "AR1000").unwrap();
+        assert!(!two_maps.links(1, area, Direction::East).is_empty());
+        assert_eq!(
+            serde_json::to_value(two_maps.links(1, area, Direction::East))?,
+            serde_json::to_value(world_map.links(0, area, Direction::East))?
+        );
+
+        // Written back with both maps' areas first, then their links
+        let reread = WorldMap::new(&two_maps.to_bytes());
+        assert_eq!(
+            serde_json::to_value(&reread.area_entries)?,
+            serde_json::to_value(&two_maps.area_entries)?
+        );
+        assert_eq!(
+            serde_json::to_value(&reread.area_link_entries)?,
+            serde_json::to_value(&two_maps.area_link_entries)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn add_travel_destination() -> Result<(), Box<dyn Error>> {
+        let mut world_map = WorldMap::new(&read_file("fixtures/worldmap.wmp")?);
+        let count_of_links = world_map.area_link_entries.len();
+        let last_area = world_map.areas(0).len() - 1;
+        let before: Vec<u32> = world_map
+            .links(0, last_area, Direction::South)
+            .iter()
+            .map(|link| link.index_of_destination_area)
+            .collect();
+
+        let area = world_map.add_area(0, AreaEntry::new("AR9900", 100, 200))?;
+        assert_eq!(area, last_area + 1);
+        world_map.link_areas(
+            0,
+            (last_area, Direction::South),
+            (area, Direction::North),
+            4,
+            &["AR9901", "AR9902"],
+            30,
+        )?;
+        assert!(
+            world_map
+                .add_link(
+                    0,
+                    area,
+                    Direction::East,
+                    AreaLink::new(999, Direction::West, 1, &[], 0)
+                )
+                .is_err()
+        );
+
+        let reread = WorldMap::new(&world_map.to_bytes());
+        assert_eq!(reread.area_index(0, "ar9900"), Some(area));
+        assert_eq!(reread.area_link_entries.len(), count_of_links + 2);
+        assert_eq!(
+            reread.world_map_entries[0].count_of_area_link_entries as usize,
+            count_of_links + 2
+        );
+        let after: Vec<u32> = reread
+            .links(0, last_area, Direction::South)
+            .iter()
+            .map(|link| link.index_of_destination_area)
+            .collect();
+        assert_eq!(after, [before, vec![area as u32]].concat());
+        let back = &reread.links(0, area, Direction::North)[0];
+        assert_eq!(back.index_of_destination_area as usize, last_area);
+        assert_eq!(back.default_entry_location, 0x4);
+        assert_eq!(back.travel_time, 4);
+        assert_eq!(back.random_encounter_area_2.to_string(), "AR9902\0\0");
+        assert_eq!(back.random_encounter_probability, 30);
+        Ok(())
+    }
 }
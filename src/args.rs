@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use clap::{ArgAction, Error, Parser, error::ErrorKind};
 
-use crate::writer::{Printer, as_binary, as_json, as_stdout};
+use crate::writer::{Printer, as_annotated, as_binary, as_csv, as_json, as_stdout};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
@@ -10,13 +10,54 @@ pub struct Args {
     /// Game lang
     #[clap(env, short = 'l', long, value_parser, default_value = "en_US")]
     pub game_lang: String,
-    /// Flag to process tiles
+    /// Flag to export each tile of a tileset as a png into the destination
     #[clap(env, long, short, action=ArgAction::SetTrue)]
     pub tiles: bool,
+    /// Flag to render a wed, or an area over its wed, as a png into the destination,
+    /// or each window of a chu with its controls outlined over the background
+    #[clap(env, long, short, action=ArgAction::SetTrue)]
+    pub render: bool,
+    /// Flag to check an area's actors, entrances and spawn points against its search map
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub lint: bool,
+    /// Flag to decompile a bcs or bs script to baf source in the destination
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub decompile: bool,
+    /// Flag to compile a baf source file to a bcs script in the destination
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub compile: bool,
+    /// Flag to decode a WAVC or acm sound to a plain 16 bit RIFF wav in the destination
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub decode_sound: bool,
+    /// Flag to encode a 16 bit PCM wav to a WAVC sound in the destination
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub encode_sound: bool,
+    /// Export the party member at this position in a gam to a chr in the destination
+    #[clap(env, long, value_name = "INDEX")]
+    pub export_character: Option<usize>,
+    /// Flag to extract the cre of a chr into the destination
+    #[clap(env, long, action=ArgAction::SetTrue)]
+    pub extract_creature: bool,
+    /// Set a 2da cell, can be repeated
+    #[clap(env, long, num_args = 3, value_names = ["ROW", "COLUMN", "VALUE"])]
+    pub set_entry: Vec<String>,
+    /// Append a 2da row given as a whitespace separated label and cells, can be repeated
+    #[clap(env, long)]
+    pub append_row: Vec<String>,
+    /// Add a 2da column filled with a value, can be repeated
+    #[clap(env, long, num_args = 2, value_names = ["COLUMN", "FILL"])]
+    pub add_column: Vec<String>,
+    /// Three way merge the 2da file with another edited copy of the original
+    #[clap(env, long, num_args = 2, value_names = ["ORIGINAL", "THEIRS"])]
+    pub merge: Vec<PathBuf>,
+    /// Directory containing chitin.key, defaults to the directory of the file
+    #[clap(env, long, short)]
+    pub game_directory: Option<PathBuf>,
     /// Flag to process tlk file
     #[clap(env, long, short, action=ArgAction::SetTrue)]
     pub process_tlk: bool,
-    /// Output Format, expects json(j), binary(b), print(p), or none(empty value)
+    /// Output Format, expects json(j), binary(b), csv(c) for 2da files, print(p),
+    /// annotated(a) to print with effect descriptions, or none(empty value)
     #[clap(env, long, short, value_parser = output_format_parser, default_value = "p")]
     pub output_format: Printer,
     /// Filename or prefix to extract [WARNING: EXPERIMENTAL]
@@ -37,8 +78,10 @@ fn output_format_parser(input: &str) -> Result<Printer, clap::Error> {
     match input.to_lowercase().as_str() {
         "json" | "j" => Ok(as_json),
         "binary" | "bin" | "b" => Ok(as_binary),
+        "csv" | "c" => Ok(as_csv),
         "" | "n" | "no" | "none" => Ok(|_, _, _| Ok(())),
         "p" | "print" => Ok(as_stdout),
+        "a" | "annotated" => Ok(as_annotated),
         _ => Err(Error::new(ErrorKind::ValueValidation)),
     }
 }
@@ -1,8 +1,33 @@
-use std::{error::Error, fs::File, io::Read, path::Path};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 
 use binrw::io::BufReader;
 use models::{
-    IEModels, common::types::ResourceType, from_buffer, from_json, key::Key, model::Model, tlk::TLK,
+    IEModels,
+    area::Area,
+    bmp::Bmp,
+    character::ExpandedCharacter,
+    chu::Chu,
+    common::{find_file, image::Image, types::ResourceType},
+    from_buffer, from_json,
+    game::Game,
+    ids::IdsRegistry,
+    key::Key,
+    model::Model,
+    mos::Mos,
+    pvrz::Pvrz,
+    script::{Script, symbols::Symbols},
+    sound::Sound,
+    src::Src,
+    tileset::Tileset,
+    tlk::TLK,
+    twoda::TwoDA,
+    wed::Wed,
 };
 
 use crate::{
@@ -15,6 +40,249 @@ fn read_file(path: &Path) -> Result<BufReader<File>, Box<dyn Error>> {
     Ok(BufReader::new(file))
 }
 
+fn read_buffer(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buffer = vec![];
+    read_file(path)?.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn find_resource(directory: &Path, name: &str, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let file_name = format!("{name}.{extension}");
+    find_file(directory, &file_name)
+        .ok_or(format!("Could not find {file_name} in {directory:?}").into())
+}
+
+// Guards the commands that write a file under the input's own name, with the default
+// destination of "." they would replace the input
+fn create_output(input: &Path, output: &Path) -> Result<File, Box<dyn Error>> {
+    if output.exists() && input.canonicalize()? == output.canonicalize()? {
+        return Err(
+            format!("Refusing to overwrite the input {input:?}, pass a destination").into(),
+        );
+    }
+    Ok(File::create(output)?)
+}
+
+fn load_pvrz_pages(
+    directory: &Path,
+    name: &str,
+    tileset: &Tileset,
+) -> Result<HashMap<u32, Image>, Box<dyn Error>> {
+    let mut pages = HashMap::new();
+    for page in tileset.pvrz_pages() {
+        let page_path = find_resource(directory, &Tileset::pvrz_page_name(name, page), "pvrz")?;
+        pages.insert(page, Pvrz::new(&read_buffer(&page_path)?).to_image()?);
+    }
+    Ok(pages)
+}
+
+fn export_tiles(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let tileset = Tileset::new(&read_buffer(path)?);
+    let name = path
+        .file_stem()
+        .ok_or("Path has no file name")?
+        .to_string_lossy();
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let pages = load_pvrz_pages(directory, &name, &tileset)?;
+
+    for index in 0..tileset.tile_count() {
+        let out_path = dest.join(format!("{name}_{index:04}.png"));
+        let mut file = File::create(&out_path)?;
+        file.write_all(&tileset.tile_to_image(index, &pages)?.to_png()?)?;
+    }
+    log::info!("Saved {} tiles to {dest:#?}", tileset.tile_count());
+    Ok(())
+}
+
+// Renders a wed, or the wed of an area with the area's contents drawn on top
+fn render_map(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let (wed_path, area) = match ResourceType::try_from(path)? {
+        ResourceType::FileTypeAre => {
+            let area = Area::new(&read_buffer(path)?);
+            let wed_name = area.header.area_wed.to_string().replace('\0', "");
+            (find_resource(directory, &wed_name, "wed")?, Some(area))
+        }
+        ResourceType::FileTypeWed => (path.to_path_buf(), None),
+        _ => return Err(format!("Can only render wed or are files, {path:?}").into()),
+    };
+    let wed = Wed::new(&read_buffer(&wed_path)?);
+    let tileset_name = wed.tileset_name().ok_or("Wed has no overlays")?;
+    let tileset = Tileset::new(&read_buffer(&find_resource(
+        directory,
+        &tileset_name,
+        "tis",
+    )?)?);
+    let pages = load_pvrz_pages(directory, &tileset_name, &tileset)?;
+
+    let mut image = wed.render(&tileset, &pages)?;
+    if let Some(area) = area {
+        area.draw_overlay(&mut image);
+    }
+    let name = path.file_stem().ok_or("Path has no file name")?;
+    let out_path = dest.join(name).with_extension("png");
+    File::create(&out_path)?.write_all(&image.to_png()?)?;
+    log::info!("Saved map to {out_path:#?}");
+    Ok(())
+}
+
+// Renders each window of a gui file, with its controls outlined over the mos background
+fn render_gui(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let chu = Chu::new(&read_buffer(path)?);
+    let name = path
+        .file_stem()
+        .ok_or("Path has no file name")?
+        .to_string_lossy();
+    for window in &chu.windows {
+        let background = if window.has_background() {
+            let mos_name = window.background.to_string().replace('\0', "");
+            let mos = Mos::new(&read_buffer(&find_resource(directory, &mos_name, "mos")?)?);
+            let mut pages = HashMap::new();
+            for page in mos.pvrz_pages() {
+                let page_path = find_resource(directory, &Mos::pvrz_page_name(page), "pvrz")?;
+                pages.insert(page, Pvrz::new(&read_buffer(&page_path)?).to_image()?);
+            }
+            Some(mos.to_image(&pages)?)
+        } else {
+            None
+        };
+        let out_path = dest.join(format!("{name}_{}.png", window.id));
+        File::create(&out_path)?.write_all(&window.render(background.as_ref()).to_png()?)?;
+    }
+    log::info!("Saved {} windows to {dest:#?}", chu.windows.len());
+    Ok(())
+}
+
+// Reports anything in an area placed outside of the walkable parts of its search map
+fn lint_area(path: &Path) -> Result<(), Box<dyn Error>> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let area = Area::new(&read_buffer(path)?);
+    let wed_name = area.header.area_wed.to_string().replace('\0', "");
+    let search_map_path = find_resource(directory, &format!("{wed_name}SR"), "bmp")?;
+    let wed = Wed::new(&read_buffer(&find_resource(directory, &wed_name, "wed")?)?);
+    let area_size = wed.size().ok_or("Wed has no overlays")?;
+    let issues = area.lint_placements(&Bmp::new(&read_buffer(&search_map_path)?), area_size);
+    for issue in &issues {
+        println!("{issue}");
+    }
+    log::info!("Found {} placement issues in {path:?}", issues.len());
+    Ok(())
+}
+
+// Only the enhanced editions keep their dialog.tlk files in a lang directory
+fn load_symbols(game_directory: &Path) -> Result<Symbols, Box<dyn Error>> {
+    Ok(Symbols {
+        enhanced_edition: game_directory.join("lang").is_dir(),
+        ..Symbols::new(IdsRegistry::from_game(game_directory)?)
+    })
+}
+
+fn decompile_script(path: &Path, game_directory: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let script = Script::parse(&read_buffer(path)?)?;
+    let source = script.decompile(&load_symbols(game_directory)?)?;
+    let name = path.file_stem().ok_or("Path has no file name")?;
+    let out_path = dest.join(name).with_extension("baf");
+    File::create(&out_path)?.write_all(source.as_bytes())?;
+    log::info!("Saved script to {out_path:#?}");
+    Ok(())
+}
+
+fn compile_script(path: &Path, game_directory: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let source = std::fs::read_to_string(path)?;
+    let script = Script::compile(&source, &load_symbols(game_directory)?)?;
+    let name = path.file_stem().ok_or("Path has no file name")?;
+    let out_path = dest.join(name).with_extension("bcs");
+    File::create(&out_path)?.write_all(&script.to_bytes())?;
+    log::info!("Saved script to {out_path:#?}");
+    Ok(())
+}
+
+// The output keeps the file name, as the games load either kind of wav
+fn convert_sound(path: &Path, dest: &Path, encode: bool) -> Result<(), Box<dyn Error>> {
+    let buffer = read_buffer(path)?;
+    let out = if encode {
+        Sound::from_wav(&buffer)?
+    } else {
+        Sound::parse(&buffer)?.to_wav()?
+    };
+    let name = path.file_name().ok_or("Path has no file name")?;
+    let out_path = dest.join(name);
+    create_output(path, &out_path)?.write_all(&out)?;
+    log::info!("Saved sound to {out_path:#?}");
+    Ok(())
+}
+
+// Characters are named after the party member, the protagonist has no name in the
+// save so falls back to the save's name and the party position
+fn export_character(path: &Path, index: usize, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let game = Game::new(&read_buffer(path)?);
+    let (npc, creature) = game
+        .party_member(index)
+        .ok_or(format!("No party member at {index}"))?;
+    let name = npc.name.to_string();
+    let name = match name.trim_end_matches('\0') {
+        "" => format!(
+            "{}{index}",
+            path.file_stem()
+                .ok_or("Path has no file name")?
+                .to_string_lossy()
+        ),
+        name => name.to_string(),
+    };
+    let out_path = dest.join(format!("{name}.chr"));
+    let character = ExpandedCharacter::from_game_npc(npc, creature);
+    File::create(&out_path)?.write_all(&character.to_bytes())?;
+    log::info!("Saved character to {out_path:#?}");
+    Ok(())
+}
+
+fn extract_creature(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let character = ExpandedCharacter::new(&read_buffer(path)?);
+    let out_path = dest.join(
+        path.with_extension("cre")
+            .file_name()
+            .ok_or("Path has no file name")?,
+    );
+    File::create(&out_path)?.write_all(&character.into_creature().to_bytes())?;
+    log::info!("Saved creature to {out_path:#?}");
+    Ok(())
+}
+
+// Merges first so the edits apply on top of the merged table
+fn patch_table(path: &Path, args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut table = TwoDA::new(&read_buffer(path)?);
+    if let [original, theirs] = args.merge.as_slice() {
+        let original = TwoDA::new(&read_buffer(original)?);
+        let (merged, conflicts) =
+            TwoDA::merge(&original, &table, &TwoDA::new(&read_buffer(theirs)?));
+        for conflict in &conflicts {
+            log::warn!("Conflict, keeping ours: {conflict}");
+        }
+        table = merged;
+    }
+    for row in &args.append_row {
+        let mut cells = row.split_whitespace().map(str::to_string);
+        let label = cells.next().ok_or("Appended rows need a label")?;
+        table.append_row(&label, cells.collect());
+    }
+    for column in args.add_column.chunks(2) {
+        if let [column, fill] = column {
+            table.add_column(column, fill)?;
+        }
+    }
+    for entry in args.set_entry.chunks(3) {
+        if let [row, column, value] = entry {
+            table.set(row, column, value)?;
+        }
+    }
+    let name = path.file_name().ok_or("Path has no file name")?;
+    let out_path = args.destination.join(name);
+    create_output(path, &out_path)?.write_all(&table.to_bytes())?;
+    log::info!("Saved table to {out_path:#?}");
+    Ok(())
+}
+
 fn json_back_to_ie_type(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
     let extension = path
         .extension()
@@ -33,7 +301,12 @@ fn json_back_to_ie_type(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>>
     write_file(&out_path, &extension, &out)
 }
 
-fn get_models_from_file(path: &Path, printer: Printer, dest: &Path) -> Result<(), Box<dyn Error>> {
+fn get_models_from_file(
+    path: &Path,
+    printer: Printer,
+    dest: &Path,
+    dialogue_path: &Path,
+) -> Result<(), Box<dyn Error>> {
     let resource_type = ResourceType::try_from(path)?;
     let mut reader: BufReader<File> = read_file(path)?;
 
@@ -56,6 +329,17 @@ fn get_models_from_file(path: &Path, printer: Printer, dest: &Path) -> Result<()
             log::info!("{model:#?}");
             return Ok(());
         }
+        // Strrefs are shown with their text when the game's tlk is available
+        ResourceType::FileTypeSrc => {
+            let mut buffer = vec![];
+            reader.read_to_end(&mut buffer)?;
+            let mut src = Src::new(&buffer);
+            match std::fs::read(dialogue_path) {
+                Ok(tlk) => src.resolve_strings(&TLK::parse(&tlk)?),
+                Err(err) => log::warn!("Could not read {dialogue_path:?}, {err}"),
+            }
+            IEModels::Src(src)
+        }
         _ => {
             log::debug!("{resource_type:?}");
             let mut buffer = vec![];
@@ -70,18 +354,69 @@ fn get_models_from_file(path: &Path, printer: Printer, dest: &Path) -> Result<()
 pub fn run(args: &Args) -> Result<(), Box<dyn Error>> {
     log::debug!("{args:?}");
     let path = &args.file;
-    get_models_from_file(path, args.output_format, &args.destination)?;
+    let game_directory = match &args.game_directory {
+        Some(game_directory) => game_directory.as_path(),
+        None => path.parent().ok_or("Could not find parent")?,
+    };
+
+    // Baf source isn't a game resource, so it has no model to print
+    if args.compile {
+        return compile_script(path, game_directory, &args.destination);
+    }
+
+    let dialogue_path = game_directory
+        .join("lang")
+        .join(&args.game_lang)
+        .join("dialog.tlk");
+    get_models_from_file(path, args.output_format, &args.destination, &dialogue_path)?;
 
     if args.to_ie_type {
         return json_back_to_ie_type(path, &args.destination);
     }
 
+    if args.tiles && ResourceType::try_from(path.as_path())? == ResourceType::FileTypeTi {
+        export_tiles(path, &args.destination)?;
+    }
+
+    if args.lint && ResourceType::try_from(path.as_path())? == ResourceType::FileTypeAre {
+        lint_area(path)?;
+    }
+
+    let is_patched = !(args.set_entry.is_empty()
+        && args.append_row.is_empty()
+        && args.add_column.is_empty()
+        && args.merge.is_empty());
+    if is_patched && ResourceType::try_from(path.as_path())? == ResourceType::FileType2da {
+        patch_table(path, args)?;
+    }
+
+    if args.decompile {
+        decompile_script(path, game_directory, &args.destination)?;
+    }
+
+    if args.decode_sound || args.encode_sound {
+        convert_sound(path, &args.destination, args.encode_sound)?;
+    }
+
+    if let Some(index) = args.export_character
+        && ResourceType::try_from(path.as_path())? == ResourceType::FileTypeGam
+    {
+        export_character(path, index, &args.destination)?;
+    }
+
+    if args.extract_creature && ResourceType::try_from(path.as_path())? == ResourceType::FileTypeChr
+    {
+        extract_creature(path, &args.destination)?;
+    }
+
+    if args.render {
+        match ResourceType::try_from(path.as_path())? {
+            ResourceType::FileTypeChu => render_gui(path, &args.destination)?,
+            _ => render_map(path, &args.destination)?,
+        }
+    }
+
     if args.process_tlk {
-        let game_directory = path.parent().ok_or("Could not find parent")?;
-        let dialogue_path = game_directory
-            .join("lang")
-            .join(args.game_lang.clone())
-            .join("dialog.tlk");
         let mut reader: BufReader<File> = read_file(&dialogue_path)?;
         let mut buffer = vec![];
         reader.read_to_end(&mut buffer)?;
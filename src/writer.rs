@@ -21,6 +21,18 @@ pub(crate) fn as_stdout(_: &Path, model: IEModels, _: ResourceType) -> Result<()
     Ok(())
 }
 
+pub(crate) fn as_annotated(
+    _: &Path,
+    model: IEModels,
+    _: ResourceType,
+) -> Result<(), Box<dyn Error>> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&model.to_annotated_json()?)?
+    );
+    Ok(())
+}
+
 pub(crate) fn as_binary(
     dest: &Path,
     model: IEModels,
@@ -44,3 +56,17 @@ pub(crate) fn as_json(
     let file = File::create(file_name)?;
     Ok(serde_json::to_writer(file, &model.to_json()?)?)
 }
+
+// Only tables have a csv view
+pub(crate) fn as_csv(
+    dest: &Path,
+    model: IEModels,
+    resource_type: ResourceType,
+) -> Result<(), Box<dyn Error>> {
+    let IEModels::TwoDA(table) = model else {
+        return Err(format!("No csv view for {resource_type:?}").into());
+    };
+    let file_name = Path::new(dest.file_stem().unwrap_or_default()).with_extension("csv");
+    log::info!("Saved as {file_name:#?}");
+    Ok(File::create(file_name)?.write_all(table.to_csv().as_bytes())?)
+}